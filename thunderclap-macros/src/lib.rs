@@ -27,7 +27,10 @@ enum IdentOrIndex {
     Index(syn::Index),
 }
 
-#[proc_macro_derive(LayableWidget, attributes(widget_layout, thunderclap_crate))]
+#[proc_macro_derive(
+    LayableWidget,
+    attributes(widget_layout, widget_measure, widget_arrange, thunderclap_crate)
+)]
 pub fn layable_widget_macro_derive(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = syn::parse(input).unwrap();
 
@@ -40,6 +43,8 @@ fn impl_layable_widget_macro(ast: syn::DeriveInput) -> TokenStream {
             let crate_name = find_crate_name(&ast.attrs)
                 .unwrap_or(syn::Ident::new("thunderclap", proc_macro2::Span::call_site()));
             let mut layout_ident = None;
+            let measure = find_attr_ident(&ast.attrs, "widget_measure");
+            let arrange = find_attr_ident(&ast.attrs, "widget_arrange");
             let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
             let name = &ast.ident;
 
@@ -71,6 +76,29 @@ fn impl_layable_widget_macro(ast: syn::DeriveInput) -> TokenStream {
                     IdentOrIndex::Index(index) => quote! { self.#index },
                 };
 
+                let measure = if let Some(measure) = measure {
+                    quote! { self.#measure(constraints) }
+                } else {
+                    quote! {
+                        let size = #ident
+                            .intrinsic_size()
+                            .unwrap_or_else(|| #crate_name::base::Resizable::size(self));
+                        constraints.constrain(size)
+                    }
+                };
+
+                // Without `#[widget_arrange]`, the provided `arrange` simply moves the widget.
+                let arrange = arrange
+                    .map(|arrange| {
+                        quote! {
+                            #[inline]
+                            fn arrange(&mut self, rect: #crate_name::geom::AbsoluteRect) {
+                                self.#arrange(rect)
+                            }
+                        }
+                    })
+                    .unwrap_or_else(|| quote! {});
+
                 {
                     quote!{
                         impl #impl_generics #crate_name::base::LayableWidget for #name #ty_generics #where_clause {
@@ -83,6 +111,17 @@ fn impl_layable_widget_macro(ast: syn::DeriveInput) -> TokenStream {
                             fn layout_id(&self) -> Option<u64> {
                                 #ident.id()
                             }
+
+                            #[inline]
+                            fn layout_events(&mut self) -> &mut #crate_name::base::WidgetLayoutEvents {
+                                &mut #ident
+                            }
+
+                            fn measure(&mut self, constraints: #crate_name::base::Constraints) -> #crate_name::reclutch::display::Size {
+                                #measure
+                            }
+
+                            #arrange
                         }
                     }
                 }.into()
@@ -342,12 +381,52 @@ fn impl_movable_macro(ast: syn::DeriveInput) -> TokenStream {
                 .unwrap_or(syn::Ident::new("thunderclap", proc_macro2::Span::call_site()));
             let mut assignment = None;
             let mut return_val = None;
+            let mut layout_ident = None;
             let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
             let name = &ast.ident;
             let callback = find_widget_transform_callback(&ast.attrs)
                 .map(|ident| quote! { self.#ident(); })
                 .unwrap_or_else(|| quote! {});
 
+            match &data.fields {
+                syn::Fields::Named(fields) => {
+                    for field in fields.named.iter() {
+                        if let Some(ref ident) = field.ident {
+                            if chk_attrs_is_layout(&field.attrs) {
+                                layout_ident = Some(quote! { self.#ident });
+                            }
+                        }
+                    }
+                }
+                syn::Fields::Unnamed(fields) => {
+                    for (i, field) in fields.unnamed.iter().enumerate() {
+                        if chk_attrs_is_layout(&field.attrs) {
+                            let index: syn::Index = i.into();
+                            layout_ident = Some(quote! { self.#index });
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            // Widgets with a layout take part in the relayout pass, which arranges them into the rectangle given by the layout.
+            let receive_layout = layout_ident
+                .map(|layout| {
+                    quote! {
+                        #[inline]
+                        fn layout_node(&self) -> Option<#crate_name::base::LayoutNode> {
+                            Some(#layout.node().clone())
+                        }
+
+                        fn receive_layout(&mut self) {
+                            if let Some(rect) = #layout.receive() {
+                                #crate_name::base::LayableWidget::arrange(self, rect);
+                            }
+                        }
+                    }
+                })
+                .unwrap_or_else(|| quote! {});
+
             match &data.fields {
                 syn::Fields::Named(fields) => {
                     for field in fields.named.iter() {
@@ -413,6 +492,8 @@ fn impl_movable_macro(ast: syn::DeriveInput) -> TokenStream {
                             fn position(&self) -> #crate_name::geom::RelativePoint {
                                 #return_val
                             }
+
+                            #receive_layout
                         }
                     }
                 }.into()
@@ -443,14 +524,13 @@ fn chk_attrs_is_rect(attrs: &[syn::Attribute]) -> bool {
 }
 
 fn find_widget_transform_callback(attrs: &[syn::Attribute]) -> Option<syn::Ident> {
+    find_attr_ident(attrs, "widget_transform_callback")
+}
+
+/// Finds the identifier given to an attribute such as `#[name(ident)]`.
+fn find_attr_ident(attrs: &[syn::Attribute], name: &str) -> Option<syn::Ident> {
     for attr in attrs {
-        if attr
-            .path
-            .segments
-            .first()
-            .map(|i| i.ident == "widget_transform_callback")
-            .unwrap_or(false)
-        {
+        if attr.path.segments.first().map(|i| i.ident == name).unwrap_or(false) {
             if let proc_macro2::TokenTree::Group(grp) =
                 attr.clone().tokens.into_iter().nth(0).unwrap()
             {
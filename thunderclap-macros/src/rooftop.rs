@@ -505,9 +505,9 @@ impl RooftopData {
                     G: #crate_name::base::GraphicalAuxiliary,
                 {
                     fn on_transform(&mut self) {
-                        use #crate_name::{base::{Repaintable}, geom::ContextuallyRectangular};
+                        use #crate_name::base::Repaintable;
                         self.repaint();
-                        self.layout.notify(self.#root_name.abs_rect());
+                        #crate_name::base::LayableWidget::notify_layout(self);
                     }
                 }
 
@@ -543,11 +543,6 @@ impl RooftopData {
                         graph.update_all(self, aux);
                         self.graph = Some(graph);
                         #after_graph
-                        if let Some(rect) = self.layout.receive() {
-                            use #crate_name::geom::ContextuallyRectangular;
-                            self.#root_name.set_ctxt_rect(rect);
-                            self.command_group.repaint();
-                        }
                     }
 
                     fn draw(&mut self, display: &mut dyn #crate_name::reclutch::display::GraphicsDisplay, aux: &mut G) {
@@ -569,6 +564,18 @@ impl RooftopData {
                     fn position(&self) -> #crate_name::geom::RelativePoint {
                         self.#root_name.position()
                     }
+
+                    #[inline]
+                    fn layout_node(&self) -> Option<#crate_name::base::LayoutNode> {
+                        Some(self.layout.node().clone())
+                    }
+
+                    fn receive_layout(&mut self) {
+                        if let Some(rect) = self.layout.receive() {
+                            #crate_name::base::LayableWidget::arrange(&mut self.#root_name, rect);
+                            self.command_group.repaint();
+                        }
+                    }
                 }
 
                 impl<U, G> #crate_name::base::Resizable for #widget_name<U, G>
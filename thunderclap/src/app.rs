@@ -13,6 +13,7 @@ use {
         event::RcEventQueue,
         prelude::*,
     },
+    std::time::Instant,
 };
 
 /// Creates an application with a given theme and root widget.
@@ -44,13 +45,25 @@ where
             size: (opts.window_size.width as _, opts.window_size.height as _),
         })?;
 
-    let mut u_aux = UAux { window_queue: RcEventQueue::new(), cursor: Default::default() };
+    let mut u_aux = UAux {
+        window_queue: RcEventQueue::new(),
+        cursor: Default::default(),
+        now: Instant::now(),
+        wakeup: None,
+        cursor_icon: base::CursorIcon::Default,
+    };
 
     let mut g_aux = GAux { scale: hidpi_factor as _ };
 
     let theme = theme(&mut g_aux, &mut display);
     let root = root(&mut u_aux, &mut g_aux, &theme);
 
+    // Widgets which depend on the window size (such as `Responsive`) learn the initial size during warmup.
+    u_aux.window_queue.emit_owned(base::WindowEvent::Resize(Size::new(
+        opts.window_size.width / g_aux.scale,
+        opts.window_size.height / g_aux.scale,
+    )));
+
     let mut app = App {
         root,
         background: opts.background,
@@ -67,12 +80,25 @@ where
 
     for _ in 0..opts.warmup {
         app.root.update(&mut app.u_aux);
+        base::invoke_layout(&mut app.root);
         app.root.draw(&mut app.display, &mut app.g_aux);
     }
 
     Ok(app)
 }
 
+fn convert_cursor_icon(icon: base::CursorIcon) -> glutin::window::CursorIcon {
+    match icon {
+        base::CursorIcon::Default => glutin::window::CursorIcon::Default,
+        base::CursorIcon::Pointer => glutin::window::CursorIcon::Hand,
+        base::CursorIcon::Text => glutin::window::CursorIcon::Text,
+        base::CursorIcon::Move => glutin::window::CursorIcon::Move,
+        base::CursorIcon::ResizeHorizontal => glutin::window::CursorIcon::EwResize,
+        base::CursorIcon::ResizeVertical => glutin::window::CursorIcon::NsResize,
+        base::CursorIcon::NotAllowed => glutin::window::CursorIcon::NotAllowed,
+    }
+}
+
 fn convert_modifiers(modifiers: event::ModifiersState) -> base::KeyModifiers {
     base::KeyModifiers {
         shift: modifiers.shift,
@@ -154,11 +180,22 @@ where
             mut command_group_post,
         } = self;
 
+        let mut cursor_icon = base::CursorIcon::Default;
+
         event_loop.run(move |event, _, control_flow| {
-            *control_flow = ControlFlow::Wait;
+            u_aux.now = Instant::now();
+            if u_aux.wakeup.map(|wakeup| wakeup <= u_aux.now).unwrap_or(false) {
+                u_aux.wakeup = None;
+            }
+
+            *control_flow = match u_aux.wakeup {
+                Some(wakeup) => ControlFlow::WaitUntil(wakeup),
+                None => ControlFlow::Wait,
+            };
 
             match event {
                 Event::EventsCleared => context.window().request_redraw(),
+                Event::NewEvents(event::StartCause::ResumeTimeReached { .. }) => {}
                 Event::WindowEvent { event: WindowEvent::RedrawRequested, .. } => {
                     if display.size().0 != size.width as _ || display.size().1 != size.height as _ {
                         display.resize((size.width as _, size.height as _)).unwrap();
@@ -203,6 +240,11 @@ where
                     command_group_pre.repaint();
                 }
                 Event::WindowEvent { event: WindowEvent::Resized(window_size), .. } => {
+                    u_aux.window_queue.emit_owned(base::WindowEvent::Resize(Size::new(
+                        window_size.width as _,
+                        window_size.height as _,
+                    )));
+
                     let window_size = window_size.to_physical(g_aux.scale as _);
                     size = Size::new(window_size.width as _, window_size.height as _);
                 }
@@ -219,6 +261,23 @@ where
                         base::ConsumableEvent::new((position.cast_unit(), modifiers)),
                     ));
                 }
+                Event::WindowEvent {
+                    event: WindowEvent::MouseWheel { delta, modifiers, .. },
+                    ..
+                } => {
+                    let delta = match delta {
+                        event::MouseScrollDelta::LineDelta(x, y) => base::ScrollDelta::Lines(x, y),
+                        // Logical like the cursor position, so the delta matches widget geometry.
+                        event::MouseScrollDelta::PixelDelta(position) => {
+                            base::ScrollDelta::Pixels(position.x as _, position.y as _)
+                        }
+                    };
+                    let modifiers = convert_modifiers(modifiers);
+
+                    u_aux.window_queue.emit_owned(base::WindowEvent::MouseScroll(
+                        base::ConsumableEvent::new((u_aux.cursor, delta, modifiers)),
+                    ));
+                }
                 Event::WindowEvent {
                     event: WindowEvent::MouseInput { state, button, modifiers, .. },
                     ..
@@ -277,7 +336,19 @@ where
                 *control_flow = cf;
             }
 
+            u_aux.cursor_icon = base::CursorIcon::Default;
             root.update(&mut u_aux);
+            // Every widget is moved into its arranged rectangle here, so the next redraw never sees a stale layout.
+            base::invoke_layout(&mut root);
+
+            if u_aux.cursor_icon != cursor_icon {
+                cursor_icon = u_aux.cursor_icon;
+                context.window().set_cursor_icon(convert_cursor_icon(cursor_icon));
+            }
+
+            if let (ControlFlow::Wait, Some(wakeup)) = (*control_flow, u_aux.wakeup) {
+                *control_flow = ControlFlow::WaitUntil(wakeup);
+            }
         })
     }
 }
@@ -286,6 +357,12 @@ where
 pub struct UAux {
     pub window_queue: RcEventQueue<base::WindowEvent>,
     pub cursor: AbsolutePoint,
+    /// Time at which the current update began.
+    pub now: Instant,
+    /// Earliest requested wake-up.
+    pub wakeup: Option<Instant>,
+    /// Icon of the mouse cursor requested during the current update.
+    pub cursor_icon: base::CursorIcon,
 }
 
 impl base::UpdateAuxiliary for UAux {
@@ -298,6 +375,23 @@ impl base::UpdateAuxiliary for UAux {
     fn window_queue_mut(&mut self) -> &mut RcEventQueue<base::WindowEvent> {
         &mut self.window_queue
     }
+
+    #[inline]
+    fn now(&self) -> Instant {
+        self.now
+    }
+
+    fn request_wakeup(&mut self, deadline: Instant) {
+        self.wakeup = Some(match self.wakeup {
+            Some(wakeup) => wakeup.min(deadline),
+            None => deadline,
+        });
+    }
+
+    #[inline]
+    fn set_cursor_icon(&mut self, icon: base::CursorIcon) {
+        self.cursor_icon = icon;
+    }
 }
 
 /// Rudimentary graphical auxiliary.
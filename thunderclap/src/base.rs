@@ -1,17 +1,20 @@
 use {
     crate::{draw, geom::*},
     reclutch::{
-        display::{Color, CommandGroup, DisplayClip, DisplayCommand, GraphicsDisplay, Rect, Size},
+        display::{
+            Color, CommandGroup, DisplayClip, DisplayCommand, GraphicsDisplay, Rect, Size, Vector,
+        },
         event::RcEventQueue,
         prelude::*,
         verbgraph,
         widget::Widget,
     },
     std::{
-        cell::RefCell,
+        cell::{Cell, RefCell},
         collections::{HashMap, HashSet},
-        rc::Rc,
+        rc::{Rc, Weak},
         sync::Mutex,
+        time::Instant,
     },
 };
 
@@ -257,6 +260,12 @@ pub trait WidgetChildren:
     > {
         Vec::new()
     }
+
+    /// Re-positions the children of a layout if it has been marked dirty.
+    ///
+    /// This is invoked by the relayout pass on widgets with pending layout work (see `invoke_layout`) rather than from `update`,
+    /// so that layouts only arrange their children once the whole tree has settled.
+    fn relayout(&mut self) {}
 }
 
 /// Implemented by widgets that can be repainted.
@@ -271,6 +280,21 @@ pub trait Movable: Widget {
     fn set_position(&mut self, position: RelativePoint);
     /// Returns the current position of the widget.
     fn position(&self) -> RelativePoint;
+
+    /// Returns the node which tracks pending layout work for the widget, if the widget has a layout.
+    ///
+    /// `derive(Movable)` implements this for widgets with a `#[widget_layout]` field.
+    #[inline]
+    fn layout_node(&self) -> Option<LayoutNode> {
+        None
+    }
+
+    /// Moves the widget into the rectangle most recently given to it by its layout, if there is one.
+    ///
+    /// This is invoked by the relayout pass on widgets with pending layout work (see `invoke_layout`), so that widgets are arranged before they're next drawn.
+    /// `derive(Movable)` implements this for widgets with a `#[widget_layout]` field, passing the rectangle on to `LayableWidget::arrange`.
+    #[inline]
+    fn receive_layout(&mut self) {}
 }
 
 /// Implemented by widgets that can be resized.
@@ -347,6 +371,26 @@ pub trait UpdateAuxiliary: 'static {
     fn window_queue(&self) -> &RcEventQueue<WindowEvent>;
     /// Returns the queue where window events (`WindowEvent`) are emitted, mutably.
     fn window_queue_mut(&mut self) -> &mut RcEventQueue<WindowEvent>;
+    /// Returns the time at which the current update began.
+    /// Time-based widget behaviour should use this rather than `Instant::now()` so that all widgets agree on the time within an update.
+    ///
+    /// By default this is simply `Instant::now()`.
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+    /// Requests that the widget tree is updated again no later than `deadline`, even if no window events occur in the meantime.
+    /// If multiple wake-ups are requested, the earliest is used.
+    ///
+    /// By default this does nothing, in which case time-based widgets only progress when the tree is updated for other reasons.
+    #[inline]
+    fn request_wakeup(&mut self, _deadline: Instant) {}
+    /// Requests the icon of the mouse cursor.
+    /// The icon is reset to `CursorIcon::Default` before every update, so hovered widgets should request their icon each update.
+    ///
+    /// By default this does nothing, in which case the cursor keeps its default icon.
+    #[inline]
+    fn set_cursor_icon(&mut self, _icon: CursorIcon) {}
 }
 
 /// Trait required for any type passed as the `GraphicalAux` type (seen as `G` in the widget type parameters)
@@ -430,6 +474,10 @@ pub enum WindowEvent {
     /// The user moved the cursor.
     #[event_key(mouse_move)]
     MouseMove(ConsumableEvent<(AbsolutePoint, KeyModifiers)>),
+    /// The user scrolled with a mouse wheel or touchpad.
+    /// The point is the cursor position at the time of scrolling.
+    #[event_key(mouse_scroll)]
+    MouseScroll(ConsumableEvent<(AbsolutePoint, ScrollDelta, KeyModifiers)>),
     /// Emitted when a text input is received.
     #[event_key(text_input)]
     TextInput(ConsumableEvent<char>),
@@ -444,6 +492,9 @@ pub enum WindowEvent {
     /// the local "focused" flag (which should ideally be stored as `draw::state::InteractionState`).
     #[event_key(clear_focus)]
     ClearFocus,
+    /// Emitted when the window is resized, with the new size of the window.
+    #[event_key(resize)]
+    Resize(Size),
 }
 
 // Most of these are copied from `winit`.
@@ -457,6 +508,26 @@ pub struct KeyModifiers {
     pub logo: bool,
 }
 
+/// Amount scrolled by a mouse wheel or touchpad.
+/// Positive values scroll content up/left (i.e. the user is moving towards the top/left).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScrollDelta {
+    /// Scroll amount in lines (typically from a mouse wheel).
+    Lines(f32, f32),
+    /// Scroll amount in pixels (typically from a touchpad).
+    Pixels(f32, f32),
+}
+
+impl ScrollDelta {
+    /// Converts the scroll amount to pixels, given the height of a single line.
+    pub fn to_pixels(self, line_height: f32) -> Vector {
+        match self {
+            ScrollDelta::Lines(x, y) => Vector::new(x * line_height, y * line_height),
+            ScrollDelta::Pixels(x, y) => Vector::new(x, y),
+        }
+    }
+}
+
 /// Button on a mouse.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum MouseButton {
@@ -465,6 +536,22 @@ pub enum MouseButton {
     Right,
 }
 
+/// Icon of the mouse cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorIcon {
+    Default,
+    /// Pointing hand, indicating a link.
+    Pointer,
+    /// I-beam, indicating editable text.
+    Text,
+    Move,
+    /// Indicates something can be resized horizontally.
+    ResizeHorizontal,
+    /// Indicates something can be resized vertically.
+    ResizeVertical,
+    NotAllowed,
+}
+
 /// Key on a keyboard.
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -632,16 +719,172 @@ pub enum KeyInput {
     Cut,
 }
 
-/// Information about a parent layout with a queue which receives updated rectangles.
+/// Minimum and maximum size a widget may take, as given to it by its parent layout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constraints {
+    pub min: Size,
+    pub max: Size,
+}
+
+impl Default for Constraints {
+    fn default() -> Self {
+        Constraints::unbounded()
+    }
+}
+
+impl Constraints {
+    pub fn new(min: Size, max: Size) -> Self {
+        Constraints { min, max }
+    }
+
+    /// Constraints which only allow `size`.
+    pub fn tight(size: Size) -> Self {
+        Constraints { min: size, max: size }
+    }
+
+    /// Constraints which allow any size up to `max`.
+    pub fn loose(max: Size) -> Self {
+        Constraints { min: Size::zero(), max }
+    }
+
+    /// Constraints which allow any size.
+    pub fn unbounded() -> Self {
+        Constraints::loose(Size::new(std::f32::INFINITY, std::f32::INFINITY))
+    }
+
+    /// Clamps `size` to fit within the constraints.
+    pub fn constrain(&self, size: Size) -> Size {
+        Size::new(
+            size.width.min(self.max.width).max(self.min.width),
+            size.height.min(self.max.height).max(self.min.height),
+        )
+    }
+
+    /// Returns `constraints` clamped to fit within these constraints, i.e. respecting `constraints` as far as these constraints allow.
+    pub fn enforce(&self, constraints: Constraints) -> Self {
+        Constraints { min: self.constrain(constraints.min), max: self.constrain(constraints.max) }
+    }
+
+    /// Shrinks both the minimum and maximum size by `width` and `height` (such as for margins).
+    pub fn deflate(&self, width: f32, height: f32) -> Self {
+        Constraints {
+            min: Size::new((self.min.width - width).max(0.0), (self.min.height - height).max(0.0)),
+            max: Size::new((self.max.width - width).max(0.0), (self.max.height - height).max(0.0)),
+        }
+    }
+}
+
+/// Direction in which content flows horizontally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LayoutDirection {
+    /// Left-to-right.
+    Ltr,
+    /// Right-to-left; layouts mirror their children horizontally.
+    Rtl,
+}
+
+impl Default for LayoutDirection {
+    fn default() -> Self {
+        LayoutDirection::Ltr
+    }
+}
+
+/// Layout information exchanged between a layout and one of its children.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayoutEvent {
+    /// Sent from a layout to a child; the constraints the child should be measured within, the rectangle it has been arranged in,
+    /// and the layout direction inherited by the child.
+    Arrange { constraints: Constraints, rect: AbsoluteRect, direction: LayoutDirection },
+    /// Sent from a child to its layout; the current rectangle of the child and the size it measured within the constraints it was last given.
+    Measure { rect: AbsoluteRect, size: Size },
+}
+
+/// Records whether a widget, or any widget below it in the widget tree, has layout work pending.
+///
+/// The relayout pass (see `invoke_layout`) only descends into widgets whose node is dirty.
+/// Nodes are linked to the node of the nearest widget above them as they're visited by the relayout pass,
+/// and start out dirty so that new widgets are always layed out.
+#[derive(Debug, Clone)]
+pub struct LayoutNode(Rc<LayoutNodeInner>);
+
+#[derive(Debug)]
+struct LayoutNodeInner {
+    dirty: Cell<bool>,
+    parent: RefCell<Weak<LayoutNodeInner>>,
+}
+
+impl Default for LayoutNode {
+    fn default() -> Self {
+        LayoutNode(Rc::new(LayoutNodeInner {
+            dirty: Cell::new(true),
+            parent: RefCell::new(Weak::new()),
+        }))
+    }
+}
+
+impl LayoutNode {
+    /// Marks the node and all the nodes above it as dirty.
+    pub fn invalidate(&self) {
+        self.0.dirty.set(true);
+        let mut parent = self.0.parent.borrow().upgrade();
+        while let Some(node) = parent {
+            node.dirty.set(true);
+            parent = node.parent.borrow().upgrade();
+        }
+    }
+
+    /// Returns whether the node has been invalidated since it was last visited by the relayout pass.
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.0.dirty.get()
+    }
+
+    #[inline]
+    fn take_dirty(&self) -> bool {
+        self.0.dirty.replace(false)
+    }
+
+    #[inline]
+    fn set_parent(&self, parent: &LayoutNode) {
+        *self.0.parent.borrow_mut() = Rc::downgrade(&parent.0);
+    }
+}
+
+/// Information about a parent layout with a queue which exchanges `LayoutEvent`s.
 #[derive(Debug)]
 pub struct WidgetLayoutEventsInner {
     pub id: u64,
-    pub evq: reclutch::event::bidir_single::Secondary<AbsoluteRect, AbsoluteRect>,
+    pub evq: reclutch::event::bidir_single::Secondary<LayoutEvent, LayoutEvent>,
+    /// Node of the layout, invalidated whenever the widget notifies the layout.
+    pub layout_node: LayoutNode,
 }
 
 /// Helper layout over `WidgetLayoutEventsInner`; optionally stores information about a parent layout.
+///
+/// This also caches the measurement of the widget, so that a widget is only re-measured when its constraints change,
+/// when it resizes itself, or when it's explicitly invalidated.
 #[derive(Default, Debug)]
-pub struct WidgetLayoutEvents(Option<WidgetLayoutEventsInner>);
+pub struct WidgetLayoutEvents {
+    inner: Option<WidgetLayoutEventsInner>,
+    constraints: Constraints,
+    /// Size measured within `constraints`, unless invalidated.
+    measured: Option<Size>,
+    /// Size the widget last gave itself (as opposed to being arranged by the layout).
+    intrinsic: Option<Size>,
+    /// Size the widget had when it last notified the layout.
+    notified: Option<Size>,
+    /// Whether the widget has been arranged by the layout but hasn't yet notified the layout.
+    arranging: bool,
+    /// Layout direction inherited from the layout.
+    inherited_direction: LayoutDirection,
+    /// Layout direction overriding the inherited direction.
+    direction: Option<LayoutDirection>,
+    /// Whether the layout direction has changed since it was last checked.
+    direction_changed: bool,
+    /// Whether the widget needs to be layed out again in the next relayout pass.
+    dirty: bool,
+    node: LayoutNode,
+}
 
 impl WidgetLayoutEvents {
     pub fn new() -> Self {
@@ -650,36 +893,199 @@ impl WidgetLayoutEvents {
 
     /// Creates `WidgetLayoutEvents` from the given layout information.
     pub fn from_layout(layout: WidgetLayoutEventsInner) -> Self {
-        WidgetLayoutEvents(Some(layout))
+        WidgetLayoutEvents { inner: Some(layout), ..Default::default() }
     }
 
     /// Possibly returns the inner associated layout ID.
     pub fn id(&self) -> Option<u64> {
-        self.0.as_ref().map(|inner| inner.id)
+        self.inner.as_ref().map(|inner| inner.id)
     }
 
     /// Possibly updates the layout information.
     pub fn update(&mut self, layout: impl Into<Option<WidgetLayoutEventsInner>>) {
-        self.0 = layout.into();
+        self.inner = layout.into();
+        self.constraints = Default::default();
+        self.measured = None;
+
+        let previous = self.direction();
+        self.inherited_direction = Default::default();
+        if self.direction() != previous {
+            self.direction_changed = true;
+            self.node.invalidate();
+        }
+    }
+
+    /// Returns the node which tracks pending layout work for the widget.
+    #[inline]
+    pub fn node(&self) -> &LayoutNode {
+        &self.node
     }
 
-    /// Notifies the layout that the widget rectangle has been updated from the widget side.
-    pub fn notify(&mut self, rect: AbsoluteRect) {
-        if let Some(inner) = &mut self.0 {
-            inner.evq.emit_owned(rect);
+    /// Returns the constraints last given by the layout (or unbounded constraints without a layout).
+    #[inline]
+    pub fn constraints(&self) -> Constraints {
+        self.constraints
+    }
+
+    /// Returns the cached measurement, if it's still valid.
+    #[inline]
+    pub fn measured(&self) -> Option<Size> {
+        self.measured
+    }
+
+    /// Caches a measurement made within the current constraints.
+    #[inline]
+    pub fn set_measured(&mut self, size: Size) {
+        self.measured = Some(size);
+    }
+
+    /// Returns the size the widget last gave itself, rather than the size it was arranged with.
+    #[inline]
+    pub fn intrinsic_size(&self) -> Option<Size> {
+        self.intrinsic
+    }
+
+    /// Discards the cached measurement, such as when the content of the widget changes.
+    #[inline]
+    pub fn invalidate(&mut self) {
+        self.measured = None;
+    }
+
+    /// Marks the widget as needing to be layed out again, which is done by the next relayout pass (see `invoke_layout`).
+    #[inline]
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+        self.node.invalidate();
+    }
+
+    /// Returns whether the widget has been marked dirty since it was last layed out.
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Marks the widget as layed out; this should be invoked once the widget has arranged its children.
+    #[inline]
+    pub fn clear_dirty(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Records the current size of the widget; if the widget resized itself, the cached measurement is discarded.
+    pub fn track_size(&mut self, size: Size) {
+        if !self.arranging && self.notified != Some(size) {
+            self.intrinsic = Some(size);
+            self.measured = None;
+        }
+        self.notified = Some(size);
+        self.arranging = false;
+    }
+
+    /// Notifies the layout of the widget rectangle and measured size.
+    ///
+    /// Widgets should generally use `LayableWidget::notify_layout` instead, which measures the widget if needed.
+    pub fn notify(&mut self, rect: AbsoluteRect, size: Size) {
+        if let Some(inner) = &mut self.inner {
+            inner.evq.emit_owned(LayoutEvent::Measure { rect, size });
+            inner.layout_node.invalidate();
         }
     }
 
-    /// Returns the most up-to-date widget rectangle from the layout.
+    /// Returns the layout direction of the widget; the overridden direction if there is one, otherwise the direction inherited from the layout.
+    #[inline]
+    pub fn direction(&self) -> LayoutDirection {
+        self.direction.unwrap_or(self.inherited_direction)
+    }
+
+    /// Overrides the layout direction of the widget (and of any children, if the widget is a layout), or inherits it from the layout if `None`.
+    pub fn set_direction(&mut self, direction: impl Into<Option<LayoutDirection>>) {
+        let previous = self.direction();
+        self.direction = direction.into();
+        if self.direction() != previous {
+            self.direction_changed = true;
+            self.node.invalidate();
+        }
+    }
+
+    /// Returns whether the layout direction has changed since this was last invoked.
+    #[inline]
+    pub fn direction_changed(&mut self) -> bool {
+        std::mem::take(&mut self.direction_changed)
+    }
+
+    /// Returns the most up-to-date widget rectangle from the layout, updating the constraints the widget is measured within.
     pub fn receive(&mut self) -> Option<AbsoluteRect> {
-        self.0.as_mut().and_then(|inner| inner.evq.retrieve_newest())
+        match self.inner.as_mut().and_then(|inner| inner.evq.retrieve_newest()) {
+            Some(LayoutEvent::Arrange { constraints, rect, direction }) => {
+                if constraints != self.constraints {
+                    self.constraints = constraints;
+                    self.measured = None;
+                }
+                if direction != self.inherited_direction {
+                    let previous = self.direction();
+                    self.inherited_direction = direction;
+                    self.direction_changed |= self.direction() != previous;
+                }
+                self.arranging = true;
+                Some(rect)
+            }
+            _ => None,
+        }
     }
 }
 
 /// Widget that is capable of listening to layout events.
+///
+/// Layout happens in two passes; first the layout gives each child `Constraints` which the child measures itself within (`measure`),
+/// then the layout decides the final rectangle of each child from the measured sizes (`arrange`).
 pub trait LayableWidget: WidgetChildren + ContextuallyRectangular + DropNotifier {
     fn listen_to_layout(&mut self, layout: impl Into<Option<WidgetLayoutEventsInner>>);
     fn layout_id(&self) -> Option<u64>;
+    fn layout_events(&mut self) -> &mut WidgetLayoutEvents;
+
+    /// Returns the size the widget would like to be within `constraints`.
+    ///
+    /// Unless the widget provides its own measurement (through `#[widget_measure]`), this is the size the widget last gave itself.
+    fn measure(&mut self, constraints: Constraints) -> Size;
+
+    /// Overrides the layout direction of the widget, or inherits it from the parent layout if `None`.
+    ///
+    /// Layouts pass their direction on to their children, so setting the direction of a layout sets it for the whole subtree.
+    #[inline]
+    fn set_layout_direction(&mut self, direction: Option<LayoutDirection>) {
+        self.layout_events().set_direction(direction);
+    }
+
+    /// Moves and resizes the widget to `rect`, as decided by a layout.
+    ///
+    /// The relayout pass invokes this (through `Movable::receive_layout`) whenever the layout arranges the widget.
+    /// Widgets which need to do more than move themselves can provide their own arrangement through `#[widget_arrange]`.
+    #[inline]
+    fn arrange(&mut self, rect: AbsoluteRect)
+    where
+        Self: Sized,
+    {
+        self.set_ctxt_rect(rect);
+    }
+
+    /// Reports the rectangle of the widget and its measured size to the layout, measuring the widget if the cached measurement is no longer valid.
+    ///
+    /// This should be invoked whenever the widget is transformed.
+    fn notify_layout(&mut self) {
+        let rect = self.abs_rect();
+        self.layout_events().track_size(rect.size.cast_unit());
+
+        let size = match self.layout_events().measured() {
+            Some(size) => size,
+            None => {
+                let constraints = self.layout_events().constraints();
+                let size = self.measure(constraints);
+                self.layout_events().set_measured(size);
+                size
+            }
+        };
+
+        self.layout_events().notify(rect, size);
+    }
 }
 
 /// Widget which emits layout events to registered widgets.
@@ -691,6 +1097,9 @@ pub trait Layout: WidgetChildren + Rectangular + Sized {
 
     /// De-registers a widget from the layout, optionally restoring the original widget rectangle.
     fn remove(&mut self, child: &mut impl LayableWidget, restore_original: bool);
+
+    /// Returns the size the layout would like to be within `constraints`, from the sizes last measured by its children.
+    fn measure_children(&self, constraints: Constraints) -> Size;
 }
 
 /// Empty event indicating `Observed` data has changed.
@@ -765,6 +1174,70 @@ macro_rules! observe {
     };
 }
 
+/// Upper bound on the number of times `invoke_layout` descends into the widget tree before leaving the rest to the next pass.
+const MAX_RELAYOUT_ITERATIONS: usize = 64;
+
+fn invoke_layout_impl<U, G>(
+    widget: &mut dyn WidgetChildren<
+        UpdateAux = U,
+        GraphicalAux = G,
+        DisplayObject = DisplayCommand,
+    >,
+    parent: &LayoutNode,
+) {
+    let node = widget.layout_node();
+    if let Some(node) = &node {
+        node.set_parent(parent);
+    }
+
+    if widget.visibility() == Visibility::None {
+        return;
+    }
+
+    // Widgets without a layout of their own are always descended into, since their children may have pending work.
+    if let Some(node) = &node {
+        if !node.take_dirty() {
+            return;
+        }
+    }
+
+    widget.receive_layout();
+    widget.relayout();
+
+    let parent = node.as_ref().unwrap_or(parent);
+    for child in widget.children_mut() {
+        invoke_layout_impl(child, parent);
+    }
+}
+
+/// Performs the relayout pass; invokes `receive_layout` and `relayout` (top-down) on every widget in the tree of `widget` with pending layout work.
+///
+/// Marking a widget dirty (or notifying its layout) invalidates the widget and everything above it in the tree,
+/// so that only the paths leading to invalidated widgets are visited; everything else is left untouched.
+/// Since a layout and its children are usually siblings in the widget tree rather than parent and child,
+/// this is repeated until nothing in the tree is invalidated anymore, so that a change anywhere in the tree
+/// is settled within a single pass instead of rippling through the layouts over several frames.
+///
+/// This should be invoked once after updating the widget tree and before drawing it (as done by `app::App`).
+/// Widgets with visibility `None` (along with all descendants) are skipped.
+pub fn invoke_layout<U, G>(
+    widget: &mut dyn WidgetChildren<
+        UpdateAux = U,
+        GraphicalAux = G,
+        DisplayObject = DisplayCommand,
+    >,
+) {
+    // Collects the invalidations of the whole tree, regardless of whether the root has a layout.
+    let tree = LayoutNode::default();
+    for _ in 0..MAX_RELAYOUT_ITERATIONS {
+        tree.take_dirty();
+        invoke_layout_impl(widget, &tree);
+        if !tree.is_dirty() {
+            break;
+        }
+    }
+}
+
 /// Propagates `update` to the children of a widget.
 pub fn invoke_update<U: UpdateAuxiliary, G>(
     widget: &mut dyn WidgetChildren<
@@ -839,8 +1312,10 @@ fn invoke_draw_impl<U, G: GraphicalAuxiliary>(
         }
     }
 
-    for child in widget.children_mut() {
-        invoke_draw_impl(child, display, aux, clip_list, checked);
+    if widget.visibility() != Visibility::None {
+        for child in widget.children_mut() {
+            invoke_draw_impl(child, display, aux, clip_list, checked);
+        }
     }
 }
 
@@ -850,6 +1325,7 @@ fn invoke_draw_impl<U, G: GraphicalAuxiliary>(
 ///
 /// Extra processing steps:
 /// - Skip if widget visibility is `Invisible` or `None`.
+/// - Skip all descendants as well if widget visibility is `None`.
 /// - Clip to absolute widget bounds.
 /// - Add widget position to auxiliary tracer.
 pub fn invoke_draw<U, G: GraphicalAuxiliary>(
@@ -4,7 +4,10 @@ pub mod state;
 
 use {
     crate::{base, geom::*},
-    reclutch::display::{Color, DisplayCommand, FontInfo, ResourceReference, Size},
+    reclutch::display::{
+        Color, DisplayCommand, DisplayText, FontInfo, Gradient, Point, Rect, ResourceReference,
+        Size, StyleColor, TextDisplayItem,
+    },
 };
 
 /// Implemented by types which are capable of changing themes.
@@ -65,6 +68,133 @@ pub fn weaken(color: Color, amount: f32, contrast: ThemeContrast) -> Color {
     }
 }
 
+/// Creates a color from hue (in degrees), saturation, value (both between 0 and 1) and alpha.
+pub fn hsva(hue: f32, saturation: f32, value: f32, alpha: f32) -> Color {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    Color::new(r + m, g + m, b + m, alpha)
+}
+
+/// Converts a color to hue (in degrees), saturation, value (both between 0 and 1) and alpha.
+pub fn to_hsva(color: Color) -> (f32, f32, f32, f32) {
+    let (r, g, b) = (color.red, color.green, color.blue);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+
+    let hue = if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+
+    (hue, saturation, max, color.alpha)
+}
+
+/// Width of the area over which `TextOverflow::Fade` fades out text.
+const FADE_WIDTH: f32 = 24.0;
+
+/// Shortens a positioned text item to fit horizontally within `bounds`, as specified by `overflow`.
+///
+/// Overflowing text is also moved to the left edge of `bounds`, so that its beginning is visible.
+/// The text isn't clipped; `TextOverflow::Clip` and `TextOverflow::Fade` rely on the caller to clip to `bounds`.
+pub fn fit_text(item: &mut TextDisplayItem, bounds: Rect, overflow: TextOverflow) {
+    let text = match (&item.text, overflow) {
+        (_, TextOverflow::Clip) => return,
+        (DisplayText::Simple(text), _) => text.clone(),
+        // Shaped text can't be shortened.
+        _ => return,
+    };
+
+    // Byte offset of each character boundary, indexed by character.
+    let boundaries: Vec<usize> =
+        text.char_indices().map(|(i, _)| i).chain(std::iter::once(text.len())).collect();
+    let char_count = boundaries.len() - 1;
+
+    // Width of the first `count` characters.
+    let width = |count: usize| item.limited_bounds(count).map(|b| b.size.width).unwrap_or_default();
+    let total = width(char_count);
+    if total <= bounds.size.width {
+        return;
+    }
+
+    // Returns the character count of the longest prefix which is at most `max_width` wide.
+    let longest_prefix = |max_width: f32| {
+        let (mut low, mut high) = (0, char_count);
+        while low < high {
+            let mid = (low + high + 1) / 2;
+            if width(mid) <= max_width {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        low
+    };
+
+    let ellipsis = TextDisplayItem { text: "…".to_string().into(), ..item.clone() };
+    let available = bounds.size.width - ellipsis.bounds().map(|b| b.size.width).unwrap_or_default();
+
+    let shortened = match overflow {
+        TextOverflow::Clip => unreachable!(),
+        TextOverflow::Ellipsis => {
+            Some(format!("{}…", text[..boundaries[longest_prefix(available)]].trim_end()))
+        }
+        TextOverflow::EllipsisMiddle => {
+            let head = longest_prefix(available / 2.0);
+            let remaining = available - width(head);
+            // Find the start of the longest suffix which fits in the remaining space.
+            let (mut low, mut high) = (head, char_count);
+            while low < high {
+                let mid = (low + high) / 2;
+                if total - width(mid) <= remaining {
+                    high = mid;
+                } else {
+                    low = mid + 1;
+                }
+            }
+            Some(format!(
+                "{}…{}",
+                text[..boundaries[head]].trim_end(),
+                text[boundaries[low]..].trim_start()
+            ))
+        }
+        TextOverflow::Fade => {
+            if let StyleColor::Color(color) = item.color {
+                item.color = StyleColor::LinearGradient(Gradient {
+                    start: Point::new(bounds.max_x() - FADE_WIDTH, bounds.min_y()),
+                    end: Point::new(bounds.max_x(), bounds.min_y()),
+                    stops: vec![
+                        (0.0, color),
+                        (1.0, Color::new(color.red, color.green, color.blue, 0.0)),
+                    ],
+                });
+            }
+            None
+        }
+    };
+
+    if let Some(shortened) = shortened {
+        item.text = shortened.into();
+    }
+    item.bottom_left.x = bounds.min_x();
+}
+
 /// A consistent palette of colors used throughout the UI.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ColorScheme {
@@ -136,6 +266,19 @@ pub enum TextStyle {
     BoldItalic,
 }
 
+/// How text which doesn't fit within its bounds is displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextOverflow {
+    /// The text is simply clipped.
+    Clip,
+    /// The end of the text is replaced with "…".
+    Ellipsis,
+    /// The middle of the text is replaced with "…", keeping both ends (e.g. of a file path) visible.
+    EllipsisMiddle,
+    /// The text is clipped and fades out towards the end of its bounds.
+    Fade,
+}
+
 /// A typeface with text size and text style.
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypefaceStyle {
@@ -183,8 +326,36 @@ pub trait Theme {
     fn button(&self) -> Box<dyn Painter<state::ButtonState>>;
     /// Constructs a painter for a checkbox.
     fn checkbox(&self) -> Box<dyn Painter<state::CheckboxState>>;
+    /// Constructs a painter for a link.
+    fn link(&self) -> Box<dyn Painter<state::LinkState>>;
     /// Constructs a painter for a text area.
     fn text_area(&self) -> Box<dyn Painter<state::TextAreaState>>;
+    /// Constructs a painter for a multi-line text edit.
+    fn text_edit(&self) -> Box<dyn Painter<state::TextEditState>>;
+    /// Constructs a painter for a numeric spin box.
+    fn spin_box(&self) -> Box<dyn Painter<state::SpinBoxState>>;
+    /// Constructs a painter for the dividers of a split pane.
+    fn split(&self) -> Box<dyn Painter<state::SplitState>>;
+    /// Constructs a painter for the header of a collapsible section.
+    fn collapsible(&self) -> Box<dyn Painter<state::CollapsibleState>>;
+    /// Constructs a painter for a color picker.
+    fn color_picker(&self) -> Box<dyn Painter<state::ColorPickerState>>;
+    /// Constructs a painter for the field of a picker widget.
+    fn picker_field(&self) -> Box<dyn Painter<state::PickerFieldState>>;
+    /// Constructs a painter for the calendar popup of a date picker.
+    fn calendar(&self) -> Box<dyn Painter<state::CalendarState>>;
+    /// Constructs a painter for the popup of a time picker.
+    fn time_popup(&self) -> Box<dyn Painter<state::TimePopupState>>;
+    /// Constructs a painter for a search field.
+    fn search_field(&self) -> Box<dyn Painter<state::SearchFieldState>>;
+    /// Constructs a painter for the suggestion popup of a search field.
+    fn suggestions(&self) -> Box<dyn Painter<state::SuggestionsState>>;
+    /// Constructs a painter for a toast notification.
+    fn toast(&self) -> Box<dyn Painter<state::ToastState>>;
+    /// Constructs a painter for a menu bar.
+    fn menu_bar(&self) -> Box<dyn Painter<state::MenuBarState>>;
+    /// Constructs a painter for a menu popup.
+    fn menu(&self) -> Box<dyn Painter<state::MenuState>>;
 
     fn data(&self) -> &ThemeData;
 }
@@ -3,7 +3,10 @@
 //! These are simply the fields relevant to rendering, existing only
 //! in the scope of the `draw` method.
 
-use crate::{geom::*, ui};
+use {
+    crate::{base, draw, geom::*, ui},
+    reclutch::display::{Color, TextDisplayItem},
+};
 
 /// Visually relevant states of a [`Button`](../ui/struct.Button.html).
 #[derive(Debug, Clone)]
@@ -22,19 +25,255 @@ bitflags::bitflags! {
 }
 
 /// Visually relevant states of a [`Checkbox`](../ui/struct.Checkbox.html).
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CheckboxState {
     pub rect: AbsoluteRect,
     pub data: ui::Checkbox,
     pub interaction: InteractionState,
 }
 
+/// Visually relevant states of a [`Link`](../ui/struct.Link.html).
+#[derive(Debug, Clone)]
+pub struct LinkState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Link,
+    pub interaction: InteractionState,
+}
+
 /// Visually relevant states of a [`TextArea`](../ui/struct.TextArea.html).
 #[derive(Debug, Clone, PartialEq)]
 pub struct TextAreaState {
     pub rect: AbsoluteRect,
     pub data: ui::TextArea,
     pub interaction: InteractionState,
+    /// Text is aligned to the right within a right-to-left layout.
+    pub direction: base::LayoutDirection,
+}
+
+/// Visually relevant states of a [`TextEdit`](../ui/struct.TextEdit.html).
+#[derive(Debug, Clone)]
+pub struct TextEditState {
+    pub rect: AbsoluteRect,
+    pub data: ui::TextEdit,
+    /// Laid out (and scrolled) visual lines of text.
+    pub lines: Vec<TextDisplayItem>,
+    /// Top and bottom of the text cursor/caret.
+    pub caret: (AbsolutePoint, AbsolutePoint),
+    pub interaction: InteractionState,
+}
+
+/// Visually relevant states of a [`SpinBox`](../ui/struct.SpinBox.html).
+///
+/// Unlike most states this doesn't store the widget data, since `SpinBox` is generic over its value type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpinBoxState {
+    pub rect: AbsoluteRect,
+    /// Displayed text, which may be mid-edit.
+    pub text: String,
+    /// Text cursor/caret position, as a character index into `text`.
+    pub cursor: usize,
+    pub typeface: draw::TypefaceStyle,
+    pub color: Color,
+    pub cursor_color: Color,
+    pub background: Color,
+    pub stepper_background: Color,
+    pub focus: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+    pub interaction: InteractionState,
+    pub increment_rect: AbsoluteRect,
+    pub decrement_rect: AbsoluteRect,
+    pub increment_interaction: InteractionState,
+    pub decrement_interaction: InteractionState,
+    /// Whether the value is below the maximum.
+    pub can_increment: bool,
+    /// Whether the value is above the minimum.
+    pub can_decrement: bool,
+}
+
+/// Visually relevant states of a [`Split`](../ui/struct.Split.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitState {
+    pub rect: AbsoluteRect,
+    pub data: ui::Split,
+    /// Rectangle and interaction state of each divider.
+    pub dividers: Vec<(AbsoluteRect, InteractionState)>,
+}
+
+/// Visually relevant states of the header of a [`Collapsible`](../ui/struct.Collapsible.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollapsibleState {
+    /// Rectangle of the header.
+    pub rect: AbsoluteRect,
+    pub data: ui::Collapsible,
+    /// How far the content is expanded, from 0 (collapsed) to 1 (expanded).
+    pub progress: f32,
+    pub interaction: InteractionState,
+}
+
+/// Visually relevant states of a [`ColorPicker`](../ui/struct.ColorPicker.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorPickerState {
+    pub rect: AbsoluteRect,
+    pub data: ui::ColorPicker,
+    /// The picked color as hue (in degrees), saturation, value and alpha.
+    pub hsva: (f32, f32, f32, f32),
+    /// Rectangle of the saturation/value square.
+    pub square: AbsoluteRect,
+    pub hue_strip: AbsoluteRect,
+    pub alpha_strip: AbsoluteRect,
+    /// Rectangle of the swatch showing the picked color.
+    pub preview: AbsoluteRect,
+    /// Rectangle of each history swatch, in the same order as `data.history`.
+    pub swatches: Vec<AbsoluteRect>,
+    /// Label and rectangle of each text input.
+    pub fields: Vec<(String, AbsoluteRect)>,
+}
+
+/// Visually relevant states of the field of a picker widget, such as a [`DatePicker`](../ui/struct.DatePicker.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickerFieldState {
+    pub rect: AbsoluteRect,
+    /// Text displaying the picked value.
+    pub text: String,
+    pub style: ui::PickerStyle,
+    /// Whether the popup of the picker is open.
+    pub open: bool,
+    pub disabled: bool,
+    pub interaction: InteractionState,
+}
+
+/// Visually relevant states of the calendar popup of a [`DatePicker`](../ui/struct.DatePicker.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarState {
+    pub rect: AbsoluteRect,
+    pub style: ui::PickerStyle,
+    /// Month and year being shown.
+    pub title: String,
+    pub title_rect: AbsoluteRect,
+    /// Rectangle of each navigation button (previous year, previous month, next month, next year) and whether it's enabled.
+    pub navigation: Vec<(AbsoluteRect, bool)>,
+    /// Index of the hovered navigation button.
+    pub hovered_navigation: Option<usize>,
+    /// Name and rectangle of each weekday column header.
+    pub weekdays: Vec<(String, AbsoluteRect)>,
+    pub days: Vec<CalendarDay>,
+}
+
+/// A single day cell within a [`CalendarState`](struct.CalendarState.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarDay {
+    pub rect: AbsoluteRect,
+    pub text: String,
+    /// Whether the day belongs to the previous or next month.
+    pub outside_month: bool,
+    /// Whether the day can be picked.
+    pub selectable: bool,
+    /// Whether the day is the picked date.
+    pub selected: bool,
+    pub today: bool,
+    /// Whether the day is highlighted by keyboard navigation.
+    pub cursor: bool,
+    pub hovered: bool,
+}
+
+/// Visually relevant states of the popup of a [`TimePicker`](../ui/struct.TimePicker.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimePopupState {
+    pub rect: AbsoluteRect,
+    pub style: ui::PickerStyle,
+    pub columns: Vec<TimeColumnState>,
+    /// Index of the column selected for keyboard input.
+    pub selected: usize,
+    /// Rectangle of the hovered step button.
+    pub hovered: Option<AbsoluteRect>,
+}
+
+/// A single column (e.g. hours) within a [`TimePopupState`](struct.TimePopupState.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeColumnState {
+    pub text: String,
+    /// Rectangle of the step up button.
+    pub up: AbsoluteRect,
+    /// Rectangle of the value.
+    pub value: AbsoluteRect,
+    /// Rectangle of the step down button.
+    pub down: AbsoluteRect,
+}
+
+/// Visually relevant states of a [`SearchField`](../ui/struct.SearchField.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchFieldState {
+    pub rect: AbsoluteRect,
+    pub style: ui::PickerStyle,
+    /// Rectangle of the area holding the magnifier icon.
+    pub icon: AbsoluteRect,
+    /// Rectangle of the clear button (only present while there is text to clear), and whether it's hovered.
+    pub clear: Option<(AbsoluteRect, bool)>,
+    /// Whether the text area within the field has keyboard focus.
+    pub focused: bool,
+    pub disabled: bool,
+}
+
+/// Visually relevant states of the suggestion popup of a [`SearchField`](../ui/struct.SearchField.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuggestionsState {
+    pub rect: AbsoluteRect,
+    pub style: ui::PickerStyle,
+    /// Text and rectangle of each suggestion.
+    pub items: Vec<(String, AbsoluteRect)>,
+    /// Index of the suggestion selected with the keyboard.
+    pub highlighted: Option<usize>,
+    /// Index of the hovered suggestion.
+    pub hovered: Option<usize>,
+}
+
+/// Visually relevant states of a single toast shown by a [`Toaster`](../ui/struct.Toaster.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToastState {
+    pub rect: AbsoluteRect,
+    pub message: String,
+    pub typeface: draw::TypefaceStyle,
+    /// Background color, decided by the kind of toast.
+    pub background: Color,
+    /// Text color, decided by the kind of toast.
+    pub foreground: Color,
+    pub contrast: draw::ThemeContrast,
+    /// Text and rectangle of the action button, and whether it's hovered.
+    pub action: Option<(String, AbsoluteRect, bool)>,
+    /// Rectangle of the dismiss button, and whether it's hovered.
+    pub close: (AbsoluteRect, bool),
+    /// Whether the toast is hovered (which pauses its timer).
+    pub hovered: bool,
+}
+
+/// Visually relevant states of a [`MenuBar`](../ui/struct.MenuBar.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuBarState {
+    pub rect: AbsoluteRect,
+    pub data: ui::MenuBar,
+    /// Rectangle of each menu title, in the same order as `data.menus`.
+    pub titles: Vec<AbsoluteRect>,
+    /// Index of the open menu.
+    pub open: Option<usize>,
+    /// Index of the menu title which is hovered or selected by the keyboard.
+    pub highlighted: Option<usize>,
+    /// Whether mnemonic characters should be underlined.
+    pub mnemonics: bool,
+}
+
+/// Visually relevant states of an open menu popup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuState {
+    pub rect: AbsoluteRect,
+    pub items: Vec<ui::MenuItem>,
+    /// Rectangle of each item, in the same order as `items`.
+    pub item_rects: Vec<AbsoluteRect>,
+    /// Index of the item which is hovered or selected by the keyboard.
+    pub highlighted: Option<usize>,
+    /// Whether mnemonic characters should be underlined.
+    pub mnemonics: bool,
+    pub style: ui::MenuStyle,
 }
 
 /// Text which can either be display normally or as placeholder.
@@ -5,10 +5,11 @@ use {
         draw::{self, state},
         error,
         geom::*,
+        ui,
     },
     reclutch::display::{
         self, Color, DisplayCommand, DisplayListBuilder, Filter, FontInfo, Gradient,
-        GraphicsDisplay, GraphicsDisplayPaint, GraphicsDisplayStroke, Rect, ResourceData,
+        GraphicsDisplay, GraphicsDisplayPaint, GraphicsDisplayStroke, Point, Rect, ResourceData,
         ResourceDescriptor, ResourceReference, SharedData, Size, StyleColor, TextDisplayItem,
         Vector, VectorPath, VectorPathBuilder,
     },
@@ -27,6 +28,78 @@ fn check_mark_icon(rect: Rect) -> VectorPath {
     builder.build()
 }
 
+/// Triangle pointing either up or down, filling `rect`.
+fn arrow_icon(rect: Rect, up: bool) -> VectorPath {
+    let mut builder = VectorPathBuilder::new();
+
+    if up {
+        builder.move_to(rect.origin + Size::new(0.0, rect.size.height));
+        builder.line_to(rect.origin + Size::new(rect.size.width / 2.0, 0.0));
+        builder.line_to(rect.origin + rect.size);
+    } else {
+        builder.move_to(rect.origin);
+        builder.line_to(rect.origin + Size::new(rect.size.width / 2.0, rect.size.height));
+        builder.line_to(rect.origin + Size::new(rect.size.width, 0.0));
+    }
+
+    builder.build()
+}
+
+/// Chevron pointing left or right, filling `rect`.
+fn chevron_icon(rect: Rect, left: bool) -> VectorPath {
+    let mut builder = VectorPathBuilder::new();
+
+    if left {
+        builder.move_to(rect.origin + Size::new(rect.size.width, 0.0));
+        builder.line_to(rect.origin + Size::new(0.0, rect.size.height / 2.0));
+        builder.line_to(rect.origin + rect.size);
+    } else {
+        builder.move_to(rect.origin);
+        builder.line_to(rect.origin + Size::new(rect.size.width, rect.size.height / 2.0));
+        builder.line_to(rect.origin + Size::new(0.0, rect.size.height));
+    }
+
+    builder.build()
+}
+
+/// Triangle pointing right, rotated to point down as `progress` goes from 0 to 1.
+fn disclosure_icon(rect: Rect, progress: f32) -> VectorPath {
+    let center = rect.center();
+    let radius = rect.size.width.min(rect.size.height) / 2.0;
+    let angle = progress * std::f32::consts::FRAC_PI_2;
+    let point = |offset: f32| {
+        let angle = angle + offset;
+        Point::new(center.x + radius * angle.cos(), center.y + radius * angle.sin())
+    };
+
+    let mut builder = VectorPathBuilder::new();
+
+    builder.move_to(point(0.0));
+    builder.line_to(point(2.0 * std::f32::consts::FRAC_PI_3));
+    builder.line_to(point(4.0 * std::f32::consts::FRAC_PI_3));
+
+    builder.build()
+}
+
+/// Circular lens of a magnifier icon, filling the top-left of `rect` (leaving room for the handle).
+fn magnifier_icon(rect: Rect) -> VectorPath {
+    let radius = rect.size.width.min(rect.size.height) * 0.35;
+    let center = rect.origin + Size::new(radius, radius);
+    let mut builder = VectorPathBuilder::new();
+
+    for i in 0..=16 {
+        let angle = i as f32 / 16.0 * 2.0 * std::f32::consts::PI;
+        let point = Point::new(center.x + radius * angle.cos(), center.y + radius * angle.sin());
+        if i == 0 {
+            builder.move_to(point);
+        } else {
+            builder.line_to(point);
+        }
+    }
+
+    builder.build()
+}
+
 impl Primer {
     /// Creates an instance of the GitHub Primer theme.
     pub fn new(display: &mut dyn GraphicsDisplay) -> Result<Self, error::ThemeError> {
@@ -103,48 +176,148 @@ impl Primer {
 
 impl draw::Theme for Primer {
     fn button(&self) -> Box<dyn draw::Painter<state::ButtonState>> {
-        Box::new(ButtonPainter)
+        Box::new(ButtonPainter { scheme: self.data.scheme })
     }
 
     fn checkbox(&self) -> Box<dyn draw::Painter<state::CheckboxState>> {
         Box::new(CheckboxPainter)
     }
 
+    fn link(&self) -> Box<dyn draw::Painter<state::LinkState>> {
+        Box::new(LinkPainter)
+    }
+
     fn text_area(&self) -> Box<dyn draw::Painter<state::TextAreaState>> {
         Box::new(TextAreaPainter)
     }
 
+    fn text_edit(&self) -> Box<dyn draw::Painter<state::TextEditState>> {
+        Box::new(TextEditPainter)
+    }
+
+    fn spin_box(&self) -> Box<dyn draw::Painter<state::SpinBoxState>> {
+        Box::new(SpinBoxPainter)
+    }
+
+    fn split(&self) -> Box<dyn draw::Painter<state::SplitState>> {
+        Box::new(SplitPainter)
+    }
+
+    fn collapsible(&self) -> Box<dyn draw::Painter<state::CollapsibleState>> {
+        Box::new(CollapsiblePainter)
+    }
+
+    fn color_picker(&self) -> Box<dyn draw::Painter<state::ColorPickerState>> {
+        Box::new(ColorPickerPainter)
+    }
+
+    fn picker_field(&self) -> Box<dyn draw::Painter<state::PickerFieldState>> {
+        Box::new(PickerFieldPainter)
+    }
+
+    fn calendar(&self) -> Box<dyn draw::Painter<state::CalendarState>> {
+        Box::new(CalendarPainter)
+    }
+
+    fn time_popup(&self) -> Box<dyn draw::Painter<state::TimePopupState>> {
+        Box::new(TimePopupPainter)
+    }
+
+    fn search_field(&self) -> Box<dyn draw::Painter<state::SearchFieldState>> {
+        Box::new(SearchFieldPainter)
+    }
+
+    fn suggestions(&self) -> Box<dyn draw::Painter<state::SuggestionsState>> {
+        Box::new(SuggestionsPainter)
+    }
+
+    fn toast(&self) -> Box<dyn draw::Painter<state::ToastState>> {
+        Box::new(ToastPainter)
+    }
+
+    fn menu_bar(&self) -> Box<dyn draw::Painter<state::MenuBarState>> {
+        Box::new(MenuBarPainter)
+    }
+
+    fn menu(&self) -> Box<dyn draw::Painter<state::MenuState>> {
+        Box::new(MenuPainter)
+    }
+
     fn data(&self) -> &draw::ThemeData {
         &self.data
     }
 }
 
-struct ButtonPainter;
+/// Space between the icons and text of a button.
+const BUTTON_ICON_SPACING: f32 = 6.0;
+
+struct ButtonPainter {
+    scheme: draw::ColorScheme,
+}
 
 impl ButtonPainter {
+    /// Returns the text item of the button (placed at the origin), unless only icons are shown.
     fn make_text_item(
         &self,
         state: &state::ButtonState,
         color: StyleColor,
-        centered: bool,
-    ) -> TextDisplayItem {
+    ) -> Option<TextDisplayItem> {
+        if state.data.icon_only
+            && (state.data.leading_icon.is_some() || state.data.trailing_icon.is_some())
+        {
+            return None;
+        }
+
         let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
-        let mut text_item = TextDisplayItem {
-            text: state.data.text.clone().into(),
+        Some(TextDisplayItem {
+            text: state.data.text.clone(),
             font: typeface.0,
             font_info: typeface.1,
             size: state.data.typeface.size,
             bottom_left: Default::default(),
             color,
-        };
+        })
+    }
 
-        text_item.set_top_left(if centered {
-            display::center(text_item.bounds().unwrap().size, state.rect.cast_unit())
-        } else {
-            state.rect.origin.cast_unit()
-        });
+    /// Returns the horizontal space taken by icons, including the spacing between them and the text.
+    fn icons_width(&self, state: &state::ButtonState, has_text: bool) -> f32 {
+        let count =
+            state.data.leading_icon.iter().count() + state.data.trailing_icon.iter().count();
+        let spacing = if has_text { count } else { count.saturating_sub(1) };
+        count as f32 * state.data.icon_size + spacing as f32 * BUTTON_ICON_SPACING
+    }
 
-        text_item
+    /// Returns the background and text color of the button, before accounting for interaction.
+    fn kind_colors(&self, state: &state::ButtonState) -> (Color, Color) {
+        match state.data.kind {
+            ui::ButtonKind::Default => (state.data.background, state.data.color),
+            ui::ButtonKind::Primary => (self.scheme.primary, self.scheme.over_primary),
+            ui::ButtonKind::Danger => (self.scheme.error, self.scheme.over_error),
+            ui::ButtonKind::Outline | ui::ButtonKind::Link => {
+                (Color::new(0.0, 0.0, 0.0, 0.0), self.scheme.primary)
+            }
+        }
+    }
+}
+
+fn push_button_icon(
+    builder: &mut DisplayListBuilder,
+    icon: &ui::ButtonIcon,
+    rect: Rect,
+    color: StyleColor,
+) {
+    match icon {
+        ui::ButtonIcon::Path(path) => builder.push_path(
+            path(rect),
+            false,
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: 1.5,
+                color,
+                ..Default::default()
+            }),
+            None,
+        ),
+        ui::ButtonIcon::Image(image) => builder.push_image(None, rect, image.clone(), None),
     }
 }
 
@@ -154,11 +327,20 @@ impl draw::Painter<state::ButtonState> for ButtonPainter {
     }
 
     fn size_hint(&self, state: state::ButtonState) -> Size {
-        self.make_text_item(&state, Color::default().into(), false)
-            .bounds()
-            .unwrap()
-            .inflate(10.0, 5.0)
-            .size
+        let text_size = self
+            .make_text_item(&state, Color::default().into())
+            .map(|text_item| text_item.bounds().unwrap().size);
+        let icons_width = self.icons_width(&state, text_size.is_some());
+        let icon_height = if icons_width > 0.0 { state.data.icon_size } else { 0.0 };
+
+        match text_size {
+            Some(text_size) => Size::new(
+                text_size.width + icons_width + 20.0,
+                text_size.height.max(icon_height) + 10.0,
+            ),
+            // icon-only buttons are padded evenly
+            None => Size::new(icons_width + 10.0, icon_height + 10.0),
+        }
     }
 
     fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
@@ -171,23 +353,59 @@ impl draw::Painter<state::ButtonState> for ButtonPainter {
     }
 
     fn draw(&mut self, state: state::ButtonState) -> Vec<DisplayCommand> {
+        let (base_background, base_color) = self.kind_colors(&state);
+        let filled = match state.data.kind {
+            ui::ButtonKind::Default | ui::ButtonKind::Primary | ui::ButtonKind::Danger => true,
+            ui::ButtonKind::Outline | ui::ButtonKind::Link => false,
+        };
+        let pressed = state.interaction.contains(state::InteractionState::PRESSED)
+            || (state.data.toggle && state.data.toggled);
+        let hovered = state.interaction.contains(state::InteractionState::HOVERED);
+
         let (background, border, text, focus) = if state.data.disabled {
             (
-                draw::strengthen(state.data.background, 0.2, state.data.contrast).into(),
-                draw::weaken(state.data.color, 0.4, state.data.contrast).into(),
-                draw::weaken(state.data.color, 0.4, state.data.contrast).into(),
+                if filled {
+                    draw::strengthen(base_background, 0.2, state.data.contrast)
+                } else {
+                    base_background
+                }
+                .into(),
+                draw::weaken(base_color, 0.4, state.data.contrast).into(),
+                draw::weaken(base_color, 0.4, state.data.contrast).into(),
                 state.data.focus.into(),
             )
-        } else if state.interaction.contains(state::InteractionState::PRESSED) {
-            let background = draw::strengthen(state.data.background, 0.2, state.data.contrast);
+        } else if !filled {
+            // Outline and link buttons are tinted with the text color when interacted with.
+            let tint = if state.data.kind == ui::ButtonKind::Link {
+                0.0
+            } else if pressed {
+                0.2
+            } else if hovered {
+                0.1
+            } else {
+                0.0
+            };
+            (
+                Color::new(base_color.red, base_color.green, base_color.blue, tint).into(),
+                base_color.into(),
+                if state.data.kind == ui::ButtonKind::Link && pressed {
+                    draw::strengthen(base_color, 0.2, state.data.contrast)
+                } else {
+                    base_color
+                }
+                .into(),
+                state.data.focus.into(),
+            )
+        } else if pressed {
+            let background = draw::strengthen(base_background, 0.2, state.data.contrast);
             (
                 background.into(),
-                draw::weaken(state.data.color, 0.3, state.data.contrast).into(),
-                state.data.color.into(),
+                draw::weaken(base_color, 0.3, state.data.contrast).into(),
+                base_color.into(),
                 state.data.focus.into(),
             )
-        } else if state.interaction.contains(state::InteractionState::HOVERED) {
-            let background = draw::strengthen(state.data.background, 0.1, state.data.contrast);
+        } else if hovered {
+            let background = draw::strengthen(base_background, 0.1, state.data.contrast);
 
             (
                 StyleColor::LinearGradient(Gradient {
@@ -198,8 +416,8 @@ impl draw::Painter<state::ButtonState> for ButtonPainter {
                         (0.9, draw::darken(background, 0.1)),
                     ],
                 }),
-                draw::weaken(state.data.color, 0.3, state.data.contrast).into(),
-                state.data.color.into(),
+                draw::weaken(base_color, 0.3, state.data.contrast).into(),
+                base_color.into(),
                 state.data.focus.into(),
             )
         } else {
@@ -208,49 +426,119 @@ impl draw::Painter<state::ButtonState> for ButtonPainter {
                     start: state.rect.origin.cast_unit(),
                     end: state.rect.origin.cast_unit() + Size::new(0.0, state.rect.size.height),
                     stops: vec![
-                        (0.0, draw::lighten(state.data.background, 0.1)),
-                        (0.9, draw::darken(state.data.background, 0.1)),
+                        (0.0, draw::lighten(base_background, 0.1)),
+                        (0.9, draw::darken(base_background, 0.1)),
                     ],
                 }),
-                draw::weaken(state.data.color, 0.4, state.data.contrast).into(),
-                state.data.color.into(),
+                draw::weaken(base_color, 0.4, state.data.contrast).into(),
+                base_color.into(),
                 state.data.focus.into(),
             )
         };
 
-        let text_item = self.make_text_item(&state, text, true);
+        let rect: Rect = state.rect.cast_unit();
+        let mut text_item = self.make_text_item(&state, text.clone());
+        let icon_size = state.data.icon_size;
+        let icons_width = self.icons_width(&state, text_item.is_some());
+        let leading_width = if state.data.leading_icon.is_some() {
+            icon_size + if text_item.is_some() { BUTTON_ICON_SPACING } else { 0.0 }
+        } else {
+            0.0
+        };
+
+        // Horizontally center the content (icons and text) as a whole.
+        let content = rect.inflate(if text_item.is_some() { -10.0 } else { -5.0 }, 0.0);
+        let text_width = text_item
+            .as_ref()
+            .map(|text_item| text_item.bounds().unwrap().size.width)
+            .unwrap_or(0.0);
+        let content_x =
+            (content.center().x - (text_width + icons_width) / 2.0).max(content.min_x());
+        let text_bounds = Rect::new(
+            Point::new(content.min_x() + leading_width, content.min_y()),
+            Size::new(content.size.width - icons_width, content.size.height),
+        );
+
+        let mut text_end = content_x + leading_width;
+        if let Some(ref mut text_item) = text_item {
+            let height = text_item.bounds().unwrap().size.height;
+            text_item.set_top_left(Point::new(
+                content_x + leading_width,
+                rect.center().y - height / 2.0,
+            ));
+            draw::fit_text(text_item, text_bounds, state.data.overflow);
+            text_end = text_item.bounds().unwrap().max_x().min(text_bounds.max_x());
+        }
 
         let mut builder = DisplayListBuilder::new();
 
-        // Background
-        builder.push_round_rectangle(
-            base::sharp_align(state.rect.cast_unit()),
-            [3.5; 4],
-            GraphicsDisplayPaint::Fill(background),
-            None,
-        );
+        if state.data.kind != ui::ButtonKind::Link {
+            // Background
+            builder.push_round_rectangle(
+                base::sharp_align(rect),
+                [3.5; 4],
+                GraphicsDisplayPaint::Fill(background),
+                None,
+            );
 
-        // Border
-        builder.push_round_rectangle(
-            base::sharp_align(state.rect.cast_unit()),
-            [3.5; 4],
-            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
-                thickness: 1.0 / 3.0,
-                color: border,
-                ..Default::default()
-            }),
-            None,
-        );
+            // Border
+            builder.push_round_rectangle(
+                base::sharp_align(rect),
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: if filled { 1.0 / 3.0 } else { 1.0 },
+                    color: border,
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        // Icons
+        let icon_y = rect.center().y - icon_size / 2.0;
+        if let Some(ref icon) = state.data.leading_icon {
+            push_button_icon(
+                &mut builder,
+                icon,
+                Rect::new(Point::new(content_x, icon_y), Size::new(icon_size, icon_size)),
+                text.clone(),
+            );
+        }
+        if let Some(ref icon) = state.data.trailing_icon {
+            let x = if text_item.is_some() { text_end + BUTTON_ICON_SPACING } else { text_end };
+            push_button_icon(
+                &mut builder,
+                icon,
+                Rect::new(Point::new(x, icon_y), Size::new(icon_size, icon_size)),
+                text.clone(),
+            );
+        }
 
         // Text
-        builder.push_text(text_item, None);
+        if let Some(text_item) = text_item {
+            // Link buttons are underlined while hovered
+            if state.data.kind == ui::ButtonKind::Link && hovered && !state.data.disabled {
+                let y = (text_item.bottom_left.y + 2.0).round() + 0.5;
+                builder.push_line(
+                    Point::new(text_item.bottom_left.x, y),
+                    Point::new(text_end, y),
+                    GraphicsDisplayStroke { thickness: 1.0, color: text, ..Default::default() },
+                    None,
+                );
+            }
+
+            builder.save();
+            builder.push_rectangle_clip(text_bounds, true);
+            builder.push_text(text_item, None);
+            builder.restore();
+        }
 
         // Focus rect
         if state.interaction.contains(state::InteractionState::FOCUSED)
             && !state.interaction.contains(state::InteractionState::PRESSED)
         {
             builder.push_round_rectangle(
-                base::sharp_align(state.rect.cast_unit()).inflate(1.5, 1.5),
+                base::sharp_align(rect).inflate(1.5, 1.5),
                 [3.5; 4],
                 GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
                     thickness: 3.5,
@@ -262,10 +550,10 @@ impl draw::Painter<state::ButtonState> for ButtonPainter {
         }
 
         // Pressed inset shadow
-        if state.interaction.contains(state::InteractionState::PRESSED) {
-            builder.push_round_rectangle_clip(base::sharp_align(state.rect.cast_unit()), [3.5; 4]);
+        if filled && pressed && !state.data.disabled {
+            builder.push_round_rectangle_clip(base::sharp_align(rect), [3.5; 4]);
             builder.push_round_rectangle(
-                state.rect.cast_unit().inflate(10.0, 10.0).translate(Vector::new(0.0, 7.0)),
+                rect.inflate(10.0, 10.0).translate(Vector::new(0.0, 7.0)),
                 [10.0; 4],
                 GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
                     thickness: 10.0,
@@ -282,13 +570,59 @@ impl draw::Painter<state::ButtonState> for ButtonPainter {
 
 struct CheckboxPainter;
 
+/// Size of the box of a checkbox.
+const CHECKBOX_SIZE: f32 = 20.0;
+/// Space between the box of a checkbox and its label.
+const CHECKBOX_LABEL_SPACING: f32 = 6.0;
+
+impl CheckboxPainter {
+    fn make_text_item(
+        &self,
+        state: &state::CheckboxState,
+        color: StyleColor,
+    ) -> Option<TextDisplayItem> {
+        if let display::DisplayText::Simple(ref text) = state.data.text {
+            if text.is_empty() {
+                return None;
+            }
+        }
+
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        let mut text_item = TextDisplayItem {
+            text: state.data.text.clone(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color,
+        };
+
+        let height = text_item.bounds().unwrap().size.height;
+        text_item.set_top_left(Point::new(
+            state.rect.origin.x + CHECKBOX_SIZE + CHECKBOX_LABEL_SPACING,
+            state.rect.origin.y + (CHECKBOX_SIZE - height) / 2.0,
+        ));
+
+        Some(text_item)
+    }
+}
+
 impl draw::Painter<state::CheckboxState> for CheckboxPainter {
     fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::CheckboxState>> {
         theme.checkbox()
     }
 
-    fn size_hint(&self, _state: state::CheckboxState) -> Size {
-        Size::new(20.0, 20.0)
+    fn size_hint(&self, state: state::CheckboxState) -> Size {
+        match self.make_text_item(&state, Color::default().into()) {
+            Some(text_item) => {
+                let bounds = text_item.bounds().unwrap();
+                Size::new(
+                    CHECKBOX_SIZE + CHECKBOX_LABEL_SPACING + bounds.size.width,
+                    CHECKBOX_SIZE.max(bounds.size.height),
+                )
+            }
+            None => Size::new(CHECKBOX_SIZE, CHECKBOX_SIZE),
+        }
     }
 
     fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
@@ -296,35 +630,39 @@ impl draw::Painter<state::CheckboxState> for CheckboxPainter {
     }
 
     fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
-        RelativeRect::new(rect.origin, Size::new(20.0, 20.0).cast_unit())
+        // includes the label
+        rect
     }
 
-    fn draw(&mut self, mut state: state::CheckboxState) -> Vec<DisplayCommand> {
-        state.rect.size = Size::new(20.0, 20.0).cast_unit();
-        state.rect = base::sharp_align(state.rect.cast_unit()).cast_unit();
+    fn draw(&mut self, state: state::CheckboxState) -> Vec<DisplayCommand> {
+        let box_rect = base::sharp_align(Rect::new(
+            state.rect.origin.cast_unit(),
+            Size::new(CHECKBOX_SIZE, CHECKBOX_SIZE),
+        ));
 
-        let (background, foreground, border, focus) = if state.data.checked {
-            (
-                state.data.background.into(),
-                draw::weaken(state.data.foreground, 0.1, state.data.contrast).into(),
-                draw::weaken(state.data.foreground, 0.4, state.data.contrast).into(),
-                state.data.focus.into(),
-            )
-        } else if state.interaction.contains(state::InteractionState::HOVERED) {
-            (
-                draw::strengthen(state.data.background, 0.05, state.data.contrast).into(),
-                base::color_from_urgba(0, 0, 0, 0.0).into(),
-                draw::weaken(state.data.foreground, 0.4, state.data.contrast).into(),
-                state.data.focus.into(),
-            )
-        } else {
-            (
-                state.data.background.into(),
-                base::color_from_urgba(0, 0, 0, 0.0).into(),
-                draw::weaken(state.data.foreground, 0.4, state.data.contrast).into(),
-                state.data.focus.into(),
-            )
-        };
+        let (background, foreground, border, focus) =
+            if state.data.state != ui::CheckState::Unchecked {
+                (
+                    state.data.background.into(),
+                    draw::weaken(state.data.foreground, 0.1, state.data.contrast).into(),
+                    draw::weaken(state.data.foreground, 0.4, state.data.contrast).into(),
+                    state.data.focus.into(),
+                )
+            } else if state.interaction.contains(state::InteractionState::HOVERED) {
+                (
+                    draw::strengthen(state.data.background, 0.05, state.data.contrast).into(),
+                    base::color_from_urgba(0, 0, 0, 0.0).into(),
+                    draw::weaken(state.data.foreground, 0.4, state.data.contrast).into(),
+                    state.data.focus.into(),
+                )
+            } else {
+                (
+                    state.data.background.into(),
+                    base::color_from_urgba(0, 0, 0, 0.0).into(),
+                    draw::weaken(state.data.foreground, 0.4, state.data.contrast).into(),
+                    state.data.focus.into(),
+                )
+            };
 
         let background = if state.interaction.contains(state::InteractionState::PRESSED) {
             draw::strengthen(background, 0.2, state.data.contrast)
@@ -337,7 +675,7 @@ impl draw::Painter<state::CheckboxState> for CheckboxPainter {
 
         // Background
         builder.push_round_rectangle(
-            state.rect.cast_unit(),
+            box_rect,
             [3.5; 4],
             GraphicsDisplayPaint::Fill(background),
             None,
@@ -345,7 +683,7 @@ impl draw::Painter<state::CheckboxState> for CheckboxPainter {
 
         // Border
         builder.push_round_rectangle(
-            state.rect.cast_unit(),
+            box_rect,
             [3.5; 4],
             GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
                 thickness: 1.0 / 3.0,
@@ -355,24 +693,42 @@ impl draw::Painter<state::CheckboxState> for CheckboxPainter {
             None,
         );
 
-        // Foreground (check mark)
-        builder.push_path(
-            check_mark_icon(state.rect.cast_unit().inflate(-4.0, -4.0)),
-            false,
-            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
-                thickness: 2.5,
-                color: foreground,
-                ..Default::default()
-            }),
-            None,
-        );
+        // Foreground (check mark or dash)
+        let stroke =
+            GraphicsDisplayStroke { thickness: 2.5, color: foreground, ..Default::default() };
+        if state.data.state == ui::CheckState::Indeterminate {
+            let y = box_rect.center().y;
+            builder.push_line(
+                Point::new(box_rect.min_x() + 5.0, y),
+                Point::new(box_rect.max_x() - 5.0, y),
+                stroke,
+                None,
+            );
+        } else {
+            builder.push_path(
+                check_mark_icon(box_rect.inflate(-4.0, -4.0)),
+                false,
+                GraphicsDisplayPaint::Stroke(stroke),
+                None,
+            );
+        }
+
+        // Label
+        let label_color = if state.data.disabled {
+            draw::weaken(state.data.color, 0.4, state.data.contrast)
+        } else {
+            state.data.color
+        };
+        if let Some(text_item) = self.make_text_item(&state, label_color.into()) {
+            builder.push_text(text_item, None);
+        }
 
         // Focus rect
         if state.interaction.contains(state::InteractionState::FOCUSED)
             && !state.interaction.contains(state::InteractionState::PRESSED)
         {
             builder.push_round_rectangle(
-                state.rect.cast_unit().inflate(1.5, 1.5),
+                box_rect.inflate(1.5, 1.5),
                 [3.5; 4],
                 GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
                     thickness: 3.5,
@@ -387,19 +743,13 @@ impl draw::Painter<state::CheckboxState> for CheckboxPainter {
     }
 }
 
-struct TextAreaPainter;
+struct LinkPainter;
 
-impl TextAreaPainter {
-    fn make_text_item(&self, state: &state::TextAreaState, color: StyleColor) -> TextDisplayItem {
+impl LinkPainter {
+    fn make_text_item(&self, state: &state::LinkState, color: StyleColor) -> TextDisplayItem {
         let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
-
         let mut text_item = TextDisplayItem {
-            text: if state.data.text.is_empty() {
-                state.data.placeholder.clone()
-            } else {
-                state.data.text.clone()
-            }
-            .into(),
+            text: state.data.text.clone(),
             font: typeface.0,
             font_info: typeface.1,
             size: state.data.typeface.size,
@@ -413,20 +763,19 @@ impl TextAreaPainter {
     }
 }
 
-impl draw::Painter<state::TextAreaState> for TextAreaPainter {
+impl draw::Painter<state::LinkState> for LinkPainter {
     #[inline]
-    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::TextAreaState>> {
-        theme.text_area()
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::LinkState>> {
+        theme.link()
     }
 
-    #[inline]
-    fn size_hint(&self, state: state::TextAreaState) -> Size {
+    fn size_hint(&self, state: state::LinkState) -> Size {
         self.make_text_item(&state, Color::default().into()).bounds().unwrap().size
     }
 
-    #[inline]
     fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
-        rect
+        // account for focus border
+        rect.inflate(3.25, 3.25)
     }
 
     #[inline]
@@ -434,41 +783,1761 @@ impl draw::Painter<state::TextAreaState> for TextAreaPainter {
         rect
     }
 
-    fn draw(&mut self, state: state::TextAreaState) -> Vec<DisplayCommand> {
-        let text = if state.data.text.is_empty() {
-            state.data.placeholder_color
+    fn draw(&mut self, state: state::LinkState) -> Vec<DisplayCommand> {
+        let color = if state.data.disabled {
+            draw::weaken(state.data.color, 0.4, state.data.contrast)
+        } else if state.data.visited {
+            state.data.visited_color
         } else {
             state.data.color
-        }
-        .into();
-
-        let text_item = self.make_text_item(&state, text);
-
-        let cursor = if state.interaction.contains(state::InteractionState::FOCUSED) {
-            let bounds = text_item.limited_bounds(state.data.cursor).unwrap();
-            Some((bounds.origin + Size::new(bounds.size.width, 0.0), bounds.origin + bounds.size))
-        } else {
-            None
         };
 
+        let text_item = self.make_text_item(&state, color.into());
+
         let mut builder = DisplayListBuilder::new();
 
-        builder.push_rectangle_clip(state.rect.cast_unit(), true);
+        builder.push_text(text_item.clone(), None);
 
-        if let Some((a, b)) = cursor {
+        // Underline, placed just below the baseline
+        if !state.data.disabled
+            && state
+                .interaction
+                .intersects(state::InteractionState::HOVERED | state::InteractionState::FOCUSED)
+        {
+            let y = (text_item.bottom_left.y + 2.0).round() + 0.5;
             builder.push_line(
-                a + Size::new(1.0, 0.0),
-                b + Size::new(1.0, 0.0),
-                GraphicsDisplayStroke {
-                    thickness: 1.0,
-                    color: state.data.cursor_color.into(),
+                Point::new(state.rect.min_x(), y),
+                Point::new(state.rect.max_x(), y),
+                GraphicsDisplayStroke { thickness: 1.0, color: color.into(), ..Default::default() },
+                None,
+            );
+        }
+
+        // Focus rect
+        if state.interaction.contains(state::InteractionState::FOCUSED)
+            && !state.interaction.contains(state::InteractionState::PRESSED)
+        {
+            builder.push_round_rectangle(
+                base::sharp_align(state.rect.cast_unit()).inflate(1.5, 1.5),
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 3.5,
+                    color: state.data.focus.into(),
                     ..Default::default()
-                },
+                }),
                 None,
             );
         }
 
-        builder.push_text(text_item, None);
+        builder.build()
+    }
+}
+
+struct TextAreaPainter;
+
+impl TextAreaPainter {
+    fn make_text_item(&self, state: &state::TextAreaState, color: StyleColor) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+
+        let mut text_item = TextDisplayItem {
+            text: if state.data.text.is_empty() {
+                state.data.placeholder.clone()
+            } else {
+                state.data.text.clone()
+            }
+            .into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color,
+        };
+
+        text_item.set_top_left(state.rect.origin.cast_unit());
+
+        text_item
+    }
+
+    /// Moves the text to the right side of the text area within a right-to-left layout.
+    fn align_text_item(&self, state: &state::TextAreaState, text_item: &mut TextDisplayItem) {
+        if state.direction == base::LayoutDirection::Rtl {
+            let width = text_item.bounds().unwrap().size.width;
+            text_item.bottom_left.x = state.rect.max_x() - width;
+        }
+    }
+}
+
+impl draw::Painter<state::TextAreaState> for TextAreaPainter {
+    #[inline]
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::TextAreaState>> {
+        theme.text_area()
+    }
+
+    #[inline]
+    fn size_hint(&self, state: state::TextAreaState) -> Size {
+        self.make_text_item(&state, Color::default().into()).bounds().unwrap().size
+    }
+
+    #[inline]
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    #[inline]
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::TextAreaState) -> Vec<DisplayCommand> {
+        let text = if state.data.text.is_empty() {
+            state.data.placeholder_color
+        } else {
+            state.data.color
+        }
+        .into();
+
+        let mut text_item = self.make_text_item(&state, text);
+        if state.data.text.is_empty() {
+            draw::fit_text(&mut text_item, state.rect.cast_unit(), state.data.placeholder_overflow);
+        }
+        self.align_text_item(&state, &mut text_item);
+
+        let cursor = if state.interaction.contains(state::InteractionState::FOCUSED) {
+            let bounds = text_item.limited_bounds(state.data.cursor).unwrap();
+            Some((bounds.origin + Size::new(bounds.size.width, 0.0), bounds.origin + bounds.size))
+        } else {
+            None
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_rectangle_clip(state.rect.cast_unit(), true);
+
+        if let Some((a, b)) = cursor {
+            builder.push_line(
+                a + Size::new(1.0, 0.0),
+                b + Size::new(1.0, 0.0),
+                GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: state.data.cursor_color.into(),
+                    ..Default::default()
+                },
+                None,
+            );
+        }
+
+        builder.push_text(text_item, None);
+
+        builder.build()
+    }
+}
+
+struct TextEditPainter;
+
+impl draw::Painter<state::TextEditState> for TextEditPainter {
+    #[inline]
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::TextEditState>> {
+        theme.text_edit()
+    }
+
+    fn size_hint(&self, state: state::TextEditState) -> Size {
+        let font = state.data.typeface.typeface.pick(state.data.typeface.style);
+        let metrics = font.1.font.metrics();
+        let line_height = (metrics.ascent - metrics.descent + metrics.line_gap)
+            / metrics.units_per_em as f32
+            * state.data.typeface.size;
+        Size::new(200.0, (line_height * 4.0).ceil())
+    }
+
+    #[inline]
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    #[inline]
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::TextEditState) -> Vec<DisplayCommand> {
+        let mut builder = DisplayListBuilder::new();
+
+        builder.push_rectangle_clip(state.rect.cast_unit(), true);
+
+        for line in state.lines {
+            builder.push_text(line, None);
+        }
+
+        if state.interaction.contains(state::InteractionState::FOCUSED) {
+            let (a, b) = state.caret;
+            builder.push_line(
+                a.cast_unit() + Size::new(1.0, 0.0),
+                b.cast_unit() + Size::new(1.0, 0.0),
+                GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: state.data.cursor_color.into(),
+                    ..Default::default()
+                },
+                None,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+struct SpinBoxPainter;
+
+impl SpinBoxPainter {
+    fn make_text_item(&self, state: &state::SpinBoxState, color: StyleColor) -> TextDisplayItem {
+        let typeface = state.typeface.typeface.pick(state.typeface.style);
+        let mut text_item = TextDisplayItem {
+            text: state.text.clone().into(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.typeface.size,
+            bottom_left: Default::default(),
+            color,
+        };
+
+        // Measure with a fixed string so that the height doesn't depend on the text.
+        let height = {
+            let mut text_item = text_item.clone();
+            text_item.text = "0".to_string().into();
+            text_item.bounds().unwrap().size.height
+        };
+        text_item.set_top_left(Point::new(
+            state.rect.origin.x + 5.0,
+            state.rect.origin.y + ((state.rect.size.height - height) / 2.0).round(),
+        ));
+
+        text_item
+    }
+
+    fn draw_stepper(
+        &self,
+        builder: &mut DisplayListBuilder,
+        state: &state::SpinBoxState,
+        rect: AbsoluteRect,
+        interaction: state::InteractionState,
+        enabled: bool,
+        up: bool,
+    ) {
+        let background = if state.disabled || !enabled {
+            state.stepper_background
+        } else if interaction.contains(state::InteractionState::PRESSED) {
+            draw::strengthen(state.stepper_background, 0.2, state.contrast)
+        } else if interaction.contains(state::InteractionState::HOVERED) {
+            draw::strengthen(state.stepper_background, 0.1, state.contrast)
+        } else {
+            state.stepper_background
+        };
+
+        let foreground = if state.disabled || !enabled {
+            draw::weaken(state.color, 0.6, state.contrast)
+        } else {
+            state.color
+        };
+
+        builder.push_rectangle(
+            base::sharp_align(rect.cast_unit()),
+            GraphicsDisplayPaint::Fill(background.into()),
+            None,
+        );
+
+        let icon_size = Size::new(7.0, 4.0);
+        builder.push_path(
+            arrow_icon(Rect::new(display::center(icon_size, rect.cast_unit()), icon_size), up),
+            true,
+            GraphicsDisplayPaint::Fill(foreground.into()),
+            None,
+        );
+    }
+}
+
+impl draw::Painter<state::SpinBoxState> for SpinBoxPainter {
+    #[inline]
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::SpinBoxState>> {
+        theme.spin_box()
+    }
+
+    fn size_hint(&self, state: state::SpinBoxState) -> Size {
+        let height =
+            self.make_text_item(&state, Color::default().into()).bounds().unwrap().size.height;
+        Size::new(100.0, height + 10.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for focus border
+        rect.inflate(3.25, 3.25)
+    }
+
+    #[inline]
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::SpinBoxState) -> Vec<DisplayCommand> {
+        let text_color = if state.disabled {
+            draw::weaken(state.color, 0.4, state.contrast)
+        } else {
+            state.color
+        };
+        let border = draw::weaken(state.color, 0.4, state.contrast);
+        let text_item = self.make_text_item(&state, text_color.into());
+        let rect = base::sharp_align(state.rect.cast_unit());
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Background
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(state.background.into()),
+            None,
+        );
+
+        // Steppers
+        builder.save();
+        builder.push_round_rectangle_clip(rect, [3.5; 4]);
+        self.draw_stepper(
+            &mut builder,
+            &state,
+            state.increment_rect,
+            state.increment_interaction,
+            state.can_increment,
+            true,
+        );
+        self.draw_stepper(
+            &mut builder,
+            &state,
+            state.decrement_rect,
+            state.decrement_interaction,
+            state.can_decrement,
+            false,
+        );
+        builder.restore();
+
+        // Stepper separator
+        builder.push_line(
+            Point::new(state.increment_rect.origin.x, rect.origin.y),
+            Point::new(state.increment_rect.origin.x, rect.max_y()),
+            GraphicsDisplayStroke {
+                thickness: 1.0 / 3.0,
+                color: border.into(),
+                ..Default::default()
+            },
+            None,
+        );
+
+        // Border
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: 1.0 / 3.0,
+                color: border.into(),
+                ..Default::default()
+            }),
+            None,
+        );
+
+        // Text and cursor, clipped to exclude the steppers
+        builder.save();
+        builder.push_rectangle_clip(
+            Rect::new(
+                rect.origin,
+                Size::new(state.increment_rect.origin.x - rect.origin.x, rect.size.height),
+            ),
+            true,
+        );
+
+        if state.interaction.contains(state::InteractionState::FOCUSED) {
+            let x = if state.cursor == 0 {
+                text_item.bottom_left.x
+            } else {
+                text_item.limited_bounds(state.cursor).unwrap().max_x()
+            };
+            builder.push_line(
+                Point::new(x + 1.0, rect.origin.y + 4.0),
+                Point::new(x + 1.0, rect.max_y() - 4.0),
+                GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: state.cursor_color.into(),
+                    ..Default::default()
+                },
+                None,
+            );
+        }
+
+        builder.push_text(text_item, None);
+        builder.restore();
+
+        // Focus rect
+        if state.interaction.contains(state::InteractionState::FOCUSED) {
+            builder.push_round_rectangle(
+                rect.inflate(1.5, 1.5),
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 3.5,
+                    color: state.focus.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+struct SplitPainter;
+
+impl draw::Painter<state::SplitState> for SplitPainter {
+    #[inline]
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::SplitState>> {
+        theme.split()
+    }
+
+    #[inline]
+    fn size_hint(&self, state: state::SplitState) -> Size {
+        state.rect.size.cast_unit()
+    }
+
+    #[inline]
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    /// Used for the grab area of each divider.
+    #[inline]
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect.inflate(2.0, 2.0)
+    }
+
+    fn draw(&mut self, state: state::SplitState) -> Vec<DisplayCommand> {
+        let mut builder = DisplayListBuilder::new();
+
+        for (divider, interaction) in state.dividers {
+            let divider: Rect = divider.cast_unit();
+
+            if interaction.contains(state::InteractionState::PRESSED) {
+                builder.push_rectangle(
+                    base::sharp_align(divider),
+                    GraphicsDisplayPaint::Fill(
+                        draw::strengthen(state.data.highlight, 0.2, state.data.contrast).into(),
+                    ),
+                    None,
+                );
+            } else if interaction.contains(state::InteractionState::HOVERED) {
+                builder.push_rectangle(
+                    base::sharp_align(divider),
+                    GraphicsDisplayPaint::Fill(state.data.highlight.into()),
+                    None,
+                );
+            }
+
+            let (a, b) = match state.data.direction {
+                ui::SplitDirection::Horizontal => {
+                    let x = (divider.origin.x + divider.size.width / 2.0).floor() + 0.5;
+                    (Point::new(x, divider.origin.y), Point::new(x, divider.max_y()))
+                }
+                ui::SplitDirection::Vertical => {
+                    let y = (divider.origin.y + divider.size.height / 2.0).floor() + 0.5;
+                    (Point::new(divider.origin.x, y), Point::new(divider.max_x(), y))
+                }
+            };
+
+            builder.push_line(
+                a,
+                b,
+                GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: state.data.color.into(),
+                    ..Default::default()
+                },
+                None,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+struct CollapsiblePainter;
+
+impl CollapsiblePainter {
+    fn make_text_item(
+        &self,
+        state: &state::CollapsibleState,
+        color: StyleColor,
+    ) -> TextDisplayItem {
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        let mut text_item = TextDisplayItem {
+            text: state.data.text.clone(),
+            font: typeface.0,
+            font_info: typeface.1,
+            size: state.data.typeface.size,
+            bottom_left: Default::default(),
+            color,
+        };
+
+        let bounds = text_item.bounds().unwrap();
+        text_item.set_top_left(Point::new(
+            state.rect.origin.x + 24.0,
+            state.rect.origin.y + ((state.rect.size.height - bounds.size.height) / 2.0).round(),
+        ));
+
+        text_item
+    }
+}
+
+impl draw::Painter<state::CollapsibleState> for CollapsiblePainter {
+    #[inline]
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::CollapsibleState>> {
+        theme.collapsible()
+    }
+
+    fn size_hint(&self, state: state::CollapsibleState) -> Size {
+        let text_size = self.make_text_item(&state, Color::default().into()).bounds().unwrap().size;
+        Size::new(text_size.width + 34.0, text_size.height + 12.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for focus border
+        rect.inflate(3.25, 3.25)
+    }
+
+    #[inline]
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::CollapsibleState) -> Vec<DisplayCommand> {
+        let (background, foreground) = if state.data.disabled {
+            (state.data.background, draw::weaken(state.data.color, 0.4, state.data.contrast))
+        } else if state.interaction.contains(state::InteractionState::PRESSED) {
+            (draw::strengthen(state.data.background, 0.2, state.data.contrast), state.data.color)
+        } else if state.interaction.contains(state::InteractionState::HOVERED) {
+            (draw::strengthen(state.data.background, 0.1, state.data.contrast), state.data.color)
+        } else {
+            (state.data.background, state.data.color)
+        };
+
+        let text_item = self.make_text_item(&state, foreground.into());
+        let rect = base::sharp_align(state.rect.cast_unit());
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Background
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(background.into()),
+            None,
+        );
+
+        // Disclosure indicator
+        let icon_size = Size::new(8.0, 8.0);
+        builder.push_path(
+            disclosure_icon(
+                Rect::new(
+                    Point::new(
+                        rect.origin.x + 8.0,
+                        rect.origin.y + ((rect.size.height - icon_size.height) / 2.0).round(),
+                    ),
+                    icon_size,
+                ),
+                state.progress,
+            ),
+            true,
+            GraphicsDisplayPaint::Fill(foreground.into()),
+            None,
+        );
+
+        // Text
+        builder.push_text(text_item, None);
+
+        // Focus rect
+        if state.interaction.contains(state::InteractionState::FOCUSED)
+            && !state.interaction.contains(state::InteractionState::PRESSED)
+        {
+            builder.push_round_rectangle(
+                rect.inflate(1.5, 1.5),
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 3.5,
+                    color: state.data.focus.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+struct ColorPickerPainter;
+
+impl draw::Painter<state::ColorPickerState> for ColorPickerPainter {
+    #[inline]
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::ColorPickerState>> {
+        theme.color_picker()
+    }
+
+    fn size_hint(&self, state: state::ColorPickerState) -> Size {
+        let mut bounds = state.square.union(&state.alpha_strip).union(&state.preview);
+        for (_, field) in &state.fields {
+            bounds = bounds.union(field);
+        }
+        Size::new(bounds.max_x() - state.rect.origin.x, bounds.max_y() - state.rect.origin.y)
+    }
+
+    #[inline]
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for markers overhanging the square and strips
+        rect.inflate(4.0, 4.0)
+    }
+
+    #[inline]
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::ColorPickerState) -> Vec<DisplayCommand> {
+        let (h, s, v, a) = state.hsva;
+        let opaque = draw::hsva(h, s, v, 1.0);
+        let border = GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+            thickness: 1.0,
+            color: state.data.border.into(),
+            ..Default::default()
+        });
+        let marker = |color: Color| {
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: 2.0,
+                color: color.into(),
+                ..Default::default()
+            })
+        };
+        let contrasting = if v > 0.5 && s < 0.5 {
+            Color::new(0.0, 0.0, 0.0, 1.0)
+        } else {
+            Color::new(1.0, 1.0, 1.0, 1.0)
+        };
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Saturation/value square
+        let square: Rect = state.square.cast_unit();
+        builder.push_rectangle(
+            square,
+            GraphicsDisplayPaint::Fill(draw::hsva(h, 1.0, 1.0, 1.0).into()),
+            None,
+        );
+        builder.push_rectangle(
+            square,
+            GraphicsDisplayPaint::Fill(StyleColor::LinearGradient(Gradient {
+                start: square.origin,
+                end: square.origin + Size::new(square.size.width, 0.0),
+                stops: vec![
+                    (0.0, Color::new(1.0, 1.0, 1.0, 1.0)),
+                    (1.0, Color::new(1.0, 1.0, 1.0, 0.0)),
+                ],
+            })),
+            None,
+        );
+        builder.push_rectangle(
+            square,
+            GraphicsDisplayPaint::Fill(StyleColor::LinearGradient(Gradient {
+                start: square.origin,
+                end: square.origin + Size::new(0.0, square.size.height),
+                stops: vec![
+                    (0.0, Color::new(0.0, 0.0, 0.0, 0.0)),
+                    (1.0, Color::new(0.0, 0.0, 0.0, 1.0)),
+                ],
+            })),
+            None,
+        );
+        builder.push_rectangle(base::sharp_align(square), border.clone(), None);
+
+        let square_marker = Point::new(
+            square.origin.x + s * square.size.width,
+            square.origin.y + (1.0 - v) * square.size.height,
+        );
+        builder.push_round_rectangle(
+            Rect::new(square_marker - Vector::new(4.0, 4.0), Size::new(8.0, 8.0)),
+            [4.0; 4],
+            marker(contrasting),
+            None,
+        );
+
+        // Hue strip
+        let hue_strip: Rect = state.hue_strip.cast_unit();
+        builder.push_rectangle(
+            hue_strip,
+            GraphicsDisplayPaint::Fill(StyleColor::LinearGradient(Gradient {
+                start: hue_strip.origin,
+                end: hue_strip.origin + Size::new(0.0, hue_strip.size.height),
+                stops: (0..=6)
+                    .map(|i| (i as f32 / 6.0, draw::hsva(i as f32 * 60.0, 1.0, 1.0, 1.0)))
+                    .collect(),
+            })),
+            None,
+        );
+        builder.push_rectangle(base::sharp_align(hue_strip), border.clone(), None);
+        push_strip_marker(&mut builder, hue_strip, h / 360.0, state.data.border);
+
+        // Alpha strip, over a checkerboard
+        let alpha_strip: Rect = state.alpha_strip.cast_unit();
+        push_checkerboard(&mut builder, alpha_strip);
+        builder.push_rectangle(
+            alpha_strip,
+            GraphicsDisplayPaint::Fill(StyleColor::LinearGradient(Gradient {
+                start: alpha_strip.origin,
+                end: alpha_strip.origin + Size::new(0.0, alpha_strip.size.height),
+                stops: vec![(0.0, opaque), (1.0, draw::hsva(h, s, v, 0.0))],
+            })),
+            None,
+        );
+        builder.push_rectangle(base::sharp_align(alpha_strip), border.clone(), None);
+        push_strip_marker(&mut builder, alpha_strip, 1.0 - a, state.data.border);
+
+        // Preview and history swatches
+        let swatches = std::iter::once((state.preview, draw::hsva(h, s, v, a)))
+            .chain(state.swatches.iter().cloned().zip(state.data.history.iter().cloned()));
+        for (rect, color) in swatches {
+            let rect: Rect = rect.cast_unit();
+            push_checkerboard(&mut builder, rect);
+            builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(color.into()), None);
+            builder.push_rectangle(base::sharp_align(rect), border.clone(), None);
+        }
+
+        // Text input backgrounds and labels
+        let text_color = if state.data.disabled {
+            draw::weaken(state.data.text_color, 0.4, state.data.contrast)
+        } else {
+            state.data.text_color
+        };
+        let typeface = state.data.typeface.typeface.pick(state.data.typeface.style);
+        for (label, rect) in state.fields {
+            let rect: Rect = rect.cast_unit();
+            builder.push_round_rectangle(
+                base::sharp_align(rect),
+                [3.5; 4],
+                GraphicsDisplayPaint::Fill(state.data.field_background.into()),
+                None,
+            );
+
+            let mut text_item = TextDisplayItem {
+                text: label.into(),
+                font: typeface.0.clone(),
+                font_info: typeface.1.clone(),
+                size: state.data.typeface.size,
+                bottom_left: Default::default(),
+                color: text_color.into(),
+            };
+            let bounds = text_item.bounds().unwrap();
+            text_item.set_top_left(Point::new(
+                rect.origin.x - bounds.size.width - 6.0,
+                rect.origin.y + ((rect.size.height - bounds.size.height) / 2.0).round(),
+            ));
+            builder.push_text(text_item, None);
+        }
+
+        builder.build()
+    }
+}
+
+/// Pushes a horizontal marker across a vertical strip at `fraction` of its height.
+fn push_strip_marker(builder: &mut DisplayListBuilder, strip: Rect, fraction: f32, color: Color) {
+    let y = (strip.origin.y + fraction * strip.size.height).round();
+    builder.push_round_rectangle(
+        Rect::new(
+            Point::new(strip.origin.x - 2.0, y - 2.0),
+            Size::new(strip.size.width + 4.0, 4.0),
+        ),
+        [1.5; 4],
+        GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+            thickness: 1.5,
+            color: color.into(),
+            ..Default::default()
+        }),
+        None,
+    );
+}
+
+/// Pushes a checkerboard filling `rect`, used to visualize transparency.
+fn push_checkerboard(builder: &mut DisplayListBuilder, rect: Rect) {
+    const CELL: f32 = 4.0;
+
+    builder.push_rectangle(
+        rect,
+        GraphicsDisplayPaint::Fill(Color::new(1.0, 1.0, 1.0, 1.0).into()),
+        None,
+    );
+
+    let columns = (rect.size.width / CELL).ceil() as usize;
+    let rows = (rect.size.height / CELL).ceil() as usize;
+    for row in 0..rows {
+        for column in (row % 2..columns).step_by(2) {
+            let origin = rect.origin + Size::new(column as f32 * CELL, row as f32 * CELL);
+            let cell = Rect::new(origin, Size::new(CELL, CELL)).intersection(&rect);
+            if let Some(cell) = cell {
+                builder.push_rectangle(
+                    cell,
+                    GraphicsDisplayPaint::Fill(Color::new(0.8, 0.8, 0.8, 1.0).into()),
+                    None,
+                );
+            }
+        }
+    }
+}
+
+/// Pushes `text` centered within `rect`.
+fn push_centered_text(
+    builder: &mut DisplayListBuilder,
+    style: &ui::PickerStyle,
+    text: &str,
+    rect: Rect,
+    color: StyleColor,
+) {
+    let mut text_item = style.text_item(text, color);
+    let bounds = text_item.bounds().unwrap();
+    text_item.set_top_left(display::center(bounds.size, rect));
+    builder.push_text(text_item, None);
+}
+
+/// Pushes the drop shadow, background and border of a picker popup.
+fn push_popup_background(builder: &mut DisplayListBuilder, style: &ui::PickerStyle, rect: Rect) {
+    builder.push_round_rectangle(
+        rect.translate(Vector::new(0.0, 3.0)),
+        [3.5; 4],
+        GraphicsDisplayPaint::Fill(Color::new(0.0, 0.0, 0.0, 0.15).into()),
+        Some(Filter::Blur(5.0, 5.0)),
+    );
+    builder.push_round_rectangle(
+        rect,
+        [3.5; 4],
+        GraphicsDisplayPaint::Fill(
+            draw::weaken(style.popup_background, 0.5, style.contrast).into(),
+        ),
+        None,
+    );
+    builder.push_round_rectangle(
+        rect,
+        [3.5; 4],
+        GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+            thickness: 1.0 / 3.0,
+            color: draw::weaken(style.color, 0.4, style.contrast).into(),
+            ..Default::default()
+        }),
+        None,
+    );
+}
+
+struct PickerFieldPainter;
+
+impl draw::Painter<state::PickerFieldState> for PickerFieldPainter {
+    #[inline]
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::PickerFieldState>> {
+        theme.picker_field()
+    }
+
+    fn size_hint(&self, state: state::PickerFieldState) -> Size {
+        state.style.field_size(&state.text)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for focus border
+        rect.inflate(3.25, 3.25)
+    }
+
+    #[inline]
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::PickerFieldState) -> Vec<DisplayCommand> {
+        let style = &state.style;
+        let color = if state.disabled { style.disabled_color } else { style.color };
+        let background = if state.disabled {
+            style.background
+        } else if state.interaction.contains(state::InteractionState::PRESSED) {
+            draw::strengthen(style.background, 0.1, style.contrast)
+        } else if state.interaction.contains(state::InteractionState::HOVERED) {
+            draw::strengthen(style.background, 0.05, style.contrast)
+        } else {
+            style.background
+        };
+        let rect = base::sharp_align(state.rect.cast_unit());
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Background
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(background.into()),
+            None,
+        );
+
+        // Border
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: 1.0 / 3.0,
+                color: draw::weaken(style.color, 0.4, style.contrast).into(),
+                ..Default::default()
+            }),
+            None,
+        );
+
+        // Text
+        let mut text_item = style.text_item(&state.text, color.into());
+        let bounds = text_item.bounds().unwrap();
+        text_item.set_top_left(Point::new(
+            rect.origin.x + style.padding,
+            rect.origin.y + ((rect.size.height - bounds.size.height) / 2.0).round(),
+        ));
+        builder.push_text(text_item, None);
+
+        // Drop-down indicator
+        let icon_size = Size::new(8.0, 5.0);
+        builder.push_path(
+            arrow_icon(
+                Rect::new(
+                    Point::new(
+                        rect.max_x() - (ui::INDICATOR_WIDTH + icon_size.width) / 2.0,
+                        rect.origin.y + ((rect.size.height - icon_size.height) / 2.0).round(),
+                    ),
+                    icon_size,
+                ),
+                state.open,
+            ),
+            true,
+            GraphicsDisplayPaint::Fill(color.into()),
+            None,
+        );
+
+        // Focus rect
+        if state.interaction.contains(state::InteractionState::FOCUSED)
+            && !state.interaction.contains(state::InteractionState::PRESSED)
+        {
+            builder.push_round_rectangle(
+                rect.inflate(1.5, 1.5),
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 3.5,
+                    color: style.focus.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+struct CalendarPainter;
+
+impl draw::Painter<state::CalendarState> for CalendarPainter {
+    #[inline]
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::CalendarState>> {
+        theme.calendar()
+    }
+
+    fn size_hint(&self, state: state::CalendarState) -> Size {
+        state.rect.size.cast_unit()
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for drop shadow
+        rect.inflate(10.0, 10.0)
+    }
+
+    #[inline]
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::CalendarState) -> Vec<DisplayCommand> {
+        let style = &state.style;
+        let rect = base::sharp_align(state.rect.cast_unit());
+        let mut builder = DisplayListBuilder::new();
+
+        push_popup_background(&mut builder, style, rect);
+
+        // Title
+        push_centered_text(
+            &mut builder,
+            style,
+            &state.title,
+            state.title_rect.cast_unit(),
+            style.color.into(),
+        );
+
+        // Navigation buttons; the outer buttons (years) show a double chevron
+        for (i, &(nav_rect, enabled)) in state.navigation.iter().enumerate() {
+            let nav_rect: Rect = nav_rect.cast_unit();
+            if state.hovered_navigation == Some(i) {
+                builder.push_round_rectangle(
+                    nav_rect.inflate(-2.0, -2.0),
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Fill(
+                        draw::strengthen(style.popup_background, 0.1, style.contrast).into(),
+                    ),
+                    None,
+                );
+            }
+
+            let color = if enabled { style.color } else { style.disabled_color };
+            let left = i < state.navigation.len() / 2;
+            let double = i == 0 || i == state.navigation.len() - 1;
+            let icon_size = Size::new(4.0, 8.0);
+            let width = if double { icon_size.width * 2.0 + 1.0 } else { icon_size.width };
+            let origin = display::center(Size::new(width, icon_size.height), nav_rect);
+            for offset in if double { vec![0.0, icon_size.width + 1.0] } else { vec![0.0] } {
+                builder.push_path(
+                    chevron_icon(Rect::new(origin + Size::new(offset, 0.0), icon_size), left),
+                    false,
+                    GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                        thickness: 1.5,
+                        color: color.into(),
+                        ..Default::default()
+                    }),
+                    None,
+                );
+            }
+        }
+
+        // Weekday names
+        for (name, weekday_rect) in &state.weekdays {
+            push_centered_text(
+                &mut builder,
+                style,
+                name,
+                weekday_rect.cast_unit(),
+                style.disabled_color.into(),
+            );
+        }
+
+        // Days
+        for day in &state.days {
+            let day_rect: Rect = day.rect.cast_unit();
+            let cell = day_rect.inflate(-1.5, -1.5);
+
+            let color = if day.selected {
+                builder.push_round_rectangle(
+                    cell,
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Fill(style.highlight.into()),
+                    None,
+                );
+                style.over_highlight
+            } else {
+                if day.hovered {
+                    builder.push_round_rectangle(
+                        cell,
+                        [3.5; 4],
+                        GraphicsDisplayPaint::Fill(
+                            draw::strengthen(style.popup_background, 0.1, style.contrast).into(),
+                        ),
+                        None,
+                    );
+                }
+
+                if !day.selectable {
+                    draw::weaken(style.disabled_color, 0.3, style.contrast)
+                } else if day.outside_month {
+                    style.disabled_color
+                } else {
+                    style.color
+                }
+            };
+
+            if day.today && !day.selected {
+                builder.push_round_rectangle(
+                    cell,
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                        thickness: 1.0,
+                        color: style.highlight.into(),
+                        ..Default::default()
+                    }),
+                    None,
+                );
+            }
+
+            if day.cursor {
+                builder.push_round_rectangle(
+                    cell.inflate(0.5, 0.5),
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                        thickness: 2.0,
+                        color: style.focus.into(),
+                        ..Default::default()
+                    }),
+                    None,
+                );
+            }
+
+            push_centered_text(&mut builder, style, &day.text, day_rect, color.into());
+
+            if !day.selectable {
+                // Strike through days which can't be picked
+                let y = day_rect.center().y.floor() + 0.5;
+                builder.push_line(
+                    Point::new(day_rect.min_x() + 8.0, y),
+                    Point::new(day_rect.max_x() - 8.0, y),
+                    GraphicsDisplayStroke {
+                        thickness: 1.0,
+                        color: color.into(),
+                        ..Default::default()
+                    },
+                    None,
+                );
+            }
+        }
+
+        builder.build()
+    }
+}
+
+struct TimePopupPainter;
+
+impl draw::Painter<state::TimePopupState> for TimePopupPainter {
+    #[inline]
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::TimePopupState>> {
+        theme.time_popup()
+    }
+
+    fn size_hint(&self, state: state::TimePopupState) -> Size {
+        state.rect.size.cast_unit()
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for drop shadow
+        rect.inflate(10.0, 10.0)
+    }
+
+    #[inline]
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::TimePopupState) -> Vec<DisplayCommand> {
+        let style = &state.style;
+        let rect = base::sharp_align(state.rect.cast_unit());
+        let mut builder = DisplayListBuilder::new();
+
+        push_popup_background(&mut builder, style, rect);
+
+        if let Some(hovered) = state.hovered {
+            builder.push_round_rectangle(
+                hovered.cast_unit().inflate(-2.0, -2.0),
+                [3.5; 4],
+                GraphicsDisplayPaint::Fill(
+                    draw::strengthen(style.popup_background, 0.1, style.contrast).into(),
+                ),
+                None,
+            );
+        }
+
+        for (i, column) in state.columns.iter().enumerate() {
+            let value: Rect = column.value.cast_unit();
+            let color = if i == state.selected {
+                builder.push_round_rectangle(
+                    value.inflate(-2.0, -2.0),
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Fill(style.highlight.into()),
+                    None,
+                );
+                style.over_highlight
+            } else {
+                style.color
+            };
+
+            push_centered_text(&mut builder, style, &column.text, value, color.into());
+
+            let icon_size = Size::new(8.0, 5.0);
+            for &(arrow_rect, up) in &[(column.up, true), (column.down, false)] {
+                builder.push_path(
+                    arrow_icon(
+                        Rect::new(display::center(icon_size, arrow_rect.cast_unit()), icon_size),
+                        up,
+                    ),
+                    true,
+                    GraphicsDisplayPaint::Fill(style.color.into()),
+                    None,
+                );
+            }
+        }
+
+        builder.build()
+    }
+}
+
+struct SearchFieldPainter;
+
+impl draw::Painter<state::SearchFieldState> for SearchFieldPainter {
+    #[inline]
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::SearchFieldState>> {
+        theme.search_field()
+    }
+
+    fn size_hint(&self, state: state::SearchFieldState) -> Size {
+        Size::new(200.0, state.style.line_height() + state.style.padding * 2.0)
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for focus border
+        rect.inflate(3.25, 3.25)
+    }
+
+    #[inline]
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::SearchFieldState) -> Vec<DisplayCommand> {
+        let style = &state.style;
+        let color = if state.disabled { style.disabled_color } else { style.color };
+        let icon_color = draw::weaken(color, 0.3, style.contrast);
+        let rect = base::sharp_align(state.rect.cast_unit());
+
+        let mut builder = DisplayListBuilder::new();
+
+        // Background
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(style.background.into()),
+            None,
+        );
+
+        // Border
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: 1.0 / 3.0,
+                color: draw::weaken(style.color, 0.4, style.contrast).into(),
+                ..Default::default()
+            }),
+            None,
+        );
+
+        // Magnifier
+        let icon_size = Size::new(12.0, 12.0);
+        let icon_rect = Rect::new(display::center(icon_size, state.icon.cast_unit()), icon_size);
+        let stroke = GraphicsDisplayStroke {
+            thickness: 1.5,
+            color: icon_color.into(),
+            ..Default::default()
+        };
+        builder.push_path(
+            magnifier_icon(icon_rect),
+            true,
+            GraphicsDisplayPaint::Stroke(stroke.clone()),
+            None,
+        );
+        builder.push_line(
+            icon_rect.origin + icon_size * 0.6,
+            icon_rect.origin + icon_size,
+            stroke,
+            None,
+        );
+
+        // Clear button
+        if let Some((clear_rect, hovered)) = state.clear {
+            let clear_rect: Rect = clear_rect.cast_unit();
+            if hovered {
+                builder.push_round_rectangle(
+                    clear_rect,
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Fill(
+                        draw::strengthen(style.background, 0.1, style.contrast).into(),
+                    ),
+                    None,
+                );
+            }
+            let cross = clear_rect.inflate(-4.5, -4.5);
+            for &(a, b) in &[
+                (cross.origin, Point::new(cross.max_x(), cross.max_y())),
+                (
+                    Point::new(cross.max_x(), cross.min_y()),
+                    Point::new(cross.min_x(), cross.max_y()),
+                ),
+            ] {
+                builder.push_line(
+                    a,
+                    b,
+                    GraphicsDisplayStroke {
+                        thickness: 1.5,
+                        color: if hovered { color.into() } else { icon_color.into() },
+                        ..Default::default()
+                    },
+                    None,
+                );
+            }
+        }
+
+        // Focus rect
+        if state.focused {
+            builder.push_round_rectangle(
+                rect.inflate(1.5, 1.5),
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 3.5,
+                    color: style.focus.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+struct SuggestionsPainter;
+
+impl draw::Painter<state::SuggestionsState> for SuggestionsPainter {
+    #[inline]
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::SuggestionsState>> {
+        theme.suggestions()
+    }
+
+    fn size_hint(&self, state: state::SuggestionsState) -> Size {
+        state.rect.size.cast_unit()
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for drop shadow
+        rect.inflate(10.0, 10.0)
+    }
+
+    #[inline]
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::SuggestionsState) -> Vec<DisplayCommand> {
+        let style = &state.style;
+        let rect = base::sharp_align(state.rect.cast_unit());
+        let mut builder = DisplayListBuilder::new();
+
+        push_popup_background(&mut builder, style, rect);
+
+        for (i, (text, item_rect)) in state.items.iter().enumerate() {
+            let item_rect: Rect = item_rect.cast_unit();
+            let color = if state.highlighted == Some(i) {
+                builder.push_round_rectangle(
+                    item_rect.inflate(-2.0, 0.0),
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Fill(style.highlight.into()),
+                    None,
+                );
+                style.over_highlight
+            } else {
+                if state.hovered == Some(i) {
+                    builder.push_round_rectangle(
+                        item_rect.inflate(-2.0, 0.0),
+                        [3.5; 4],
+                        GraphicsDisplayPaint::Fill(
+                            draw::strengthen(style.popup_background, 0.1, style.contrast).into(),
+                        ),
+                        None,
+                    );
+                }
+                style.color
+            };
+
+            let mut text_item = style.text_item(text, color.into());
+            let bounds = text_item.bounds().unwrap();
+            text_item.set_top_left(Point::new(
+                item_rect.origin.x + style.padding,
+                item_rect.origin.y + ((item_rect.size.height - bounds.size.height) / 2.0).round(),
+            ));
+            builder.save();
+            builder.push_rectangle_clip(item_rect.inflate(-style.padding, 0.0), true);
+            builder.push_text(text_item, None);
+            builder.restore();
+        }
+
+        builder.build()
+    }
+}
+
+struct ToastPainter;
+
+impl ToastPainter {
+    fn make_text_item(
+        &self,
+        typeface: &draw::TypefaceStyle,
+        text: &str,
+        color: StyleColor,
+    ) -> TextDisplayItem {
+        let font = typeface.typeface.pick(typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: font.0,
+            font_info: font.1,
+            size: typeface.size,
+            bottom_left: Default::default(),
+            color,
+        }
+    }
+}
+
+impl draw::Painter<state::ToastState> for ToastPainter {
+    #[inline]
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::ToastState>> {
+        theme.toast()
+    }
+
+    #[inline]
+    fn size_hint(&self, state: state::ToastState) -> Size {
+        state.rect.size.cast_unit()
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for drop shadow
+        rect.inflate(10.0, 10.0)
+    }
+
+    #[inline]
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::ToastState) -> Vec<DisplayCommand> {
+        let rect = base::sharp_align(state.rect.cast_unit());
+        let background = if state.hovered {
+            draw::strengthen(state.background, 0.05, state.contrast)
+        } else {
+            state.background
+        };
+        let mut builder = DisplayListBuilder::new();
+
+        // Drop shadow
+        builder.push_round_rectangle(
+            rect.translate(Vector::new(0.0, 3.0)),
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(Color::new(0.0, 0.0, 0.0, 0.2).into()),
+            Some(Filter::Blur(5.0, 5.0)),
+        );
+
+        // Background
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(background.into()),
+            None,
+        );
+
+        let hover_fill = GraphicsDisplayPaint::Fill(
+            Color::new(state.foreground.red, state.foreground.green, state.foreground.blue, 0.15)
+                .into(),
+        );
+
+        // Message, clipped to exclude the buttons
+        let text_end = match state.action {
+            Some((_, action_rect, _)) => action_rect.min_x(),
+            None => state.close.0.min_x(),
+        } - 8.0;
+        let mut text_item =
+            self.make_text_item(&state.typeface, &state.message, state.foreground.into());
+        let bounds = text_item.bounds().unwrap();
+        text_item.set_top_left(Point::new(
+            rect.origin.x + 12.0,
+            rect.origin.y + ((rect.size.height - bounds.size.height) / 2.0).round(),
+        ));
+        builder.save();
+        builder.push_rectangle_clip(
+            Rect::new(rect.origin, Size::new(text_end - rect.origin.x, rect.size.height)),
+            true,
+        );
+        builder.push_text(text_item, None);
+        builder.restore();
+
+        // Action button
+        if let Some((text, action_rect, hovered)) = state.action {
+            let action_rect: Rect = base::sharp_align(action_rect.cast_unit());
+            if hovered {
+                builder.push_round_rectangle(action_rect, [3.5; 4], hover_fill.clone(), None);
+            }
+            builder.push_round_rectangle(
+                action_rect,
+                [3.5; 4],
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    thickness: 1.0,
+                    color: state.foreground.into(),
+                    ..Default::default()
+                }),
+                None,
+            );
+
+            let mut text_item =
+                self.make_text_item(&state.typeface, &text, state.foreground.into());
+            let bounds = text_item.bounds().unwrap();
+            text_item.set_top_left(display::center(bounds.size, action_rect));
+            builder.push_text(text_item, None);
+        }
+
+        // Dismiss button
+        let (close_rect, close_hovered) = state.close;
+        let close_rect: Rect = close_rect.cast_unit();
+        if close_hovered {
+            builder.push_round_rectangle(close_rect, [3.5; 4], hover_fill, None);
+        }
+        let cross = close_rect.inflate(-4.5, -4.5);
+        for &(a, b) in &[
+            (cross.origin, Point::new(cross.max_x(), cross.max_y())),
+            (Point::new(cross.max_x(), cross.min_y()), Point::new(cross.min_x(), cross.max_y())),
+        ] {
+            builder.push_line(
+                a,
+                b,
+                GraphicsDisplayStroke {
+                    thickness: 1.5,
+                    color: state.foreground.into(),
+                    ..Default::default()
+                },
+                None,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+/// Pushes menu text (which may contain a mnemonic marker) vertically centered within `rect`,
+/// optionally underlining the mnemonic character.
+fn push_menu_text(
+    builder: &mut DisplayListBuilder,
+    style: &ui::MenuStyle,
+    text: &str,
+    rect: Rect,
+    color: StyleColor,
+    mnemonics: bool,
+) {
+    let (text, mnemonic) = ui::split_mnemonic(text);
+    let mut text_item = style.text_item(&text, color.clone());
+    let bounds = text_item.bounds().unwrap();
+    text_item.set_top_left(Point::new(
+        rect.origin.x + style.padding,
+        display::center(bounds.size, rect).y,
+    ));
+
+    if let (true, Some(index)) = (mnemonics, mnemonic) {
+        let start = text_item.limited_bounds(index).unwrap().max_x();
+        let end = text_item.limited_bounds(index + 1).unwrap().max_x();
+        let y = text_item.bottom_left.y + 2.0;
+        builder.push_line(
+            Point::new(start, y),
+            Point::new(end, y),
+            GraphicsDisplayStroke { thickness: 1.0, color, ..Default::default() },
+            None,
+        );
+    }
+
+    builder.push_text(text_item, None);
+}
+
+struct MenuBarPainter;
+
+impl draw::Painter<state::MenuBarState> for MenuBarPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::MenuBarState>> {
+        theme.menu_bar()
+    }
+
+    fn size_hint(&self, state: state::MenuBarState) -> Size {
+        let style = &state.data.style;
+        Size::new(
+            state.titles.iter().map(|title| title.size.width).sum(),
+            style.line_height() + style.padding * 2.0,
+        )
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::MenuBarState) -> Vec<DisplayCommand> {
+        let style = &state.data.style;
+        let mut builder = DisplayListBuilder::new();
+
+        // Background
+        builder.push_rectangle(
+            state.rect.cast_unit(),
+            GraphicsDisplayPaint::Fill(style.background.into()),
+            None,
+        );
+
+        // Bottom border
+        builder.push_line(
+            Point::new(state.rect.min_x(), state.rect.max_y() - 0.5),
+            Point::new(state.rect.max_x(), state.rect.max_y() - 0.5),
+            GraphicsDisplayStroke {
+                thickness: 1.0,
+                color: draw::strengthen(style.background, 0.1, style.contrast).into(),
+                ..Default::default()
+            },
+            None,
+        );
+
+        for (i, (menu, title)) in state.data.menus.iter().zip(state.titles.iter()).enumerate() {
+            let color = if state.data.disabled {
+                style.disabled_color
+            } else if state.open == Some(i) {
+                builder.push_round_rectangle(
+                    base::sharp_align(title.cast_unit().inflate(-2.0, -2.0)),
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Fill(style.highlight.into()),
+                    None,
+                );
+                style.over_highlight
+            } else if state.highlighted == Some(i) {
+                builder.push_round_rectangle(
+                    base::sharp_align(title.cast_unit().inflate(-2.0, -2.0)),
+                    [3.5; 4],
+                    GraphicsDisplayPaint::Fill(
+                        draw::strengthen(style.background, 0.1, style.contrast).into(),
+                    ),
+                    None,
+                );
+                style.color
+            } else {
+                style.color
+            };
+
+            push_menu_text(
+                &mut builder,
+                style,
+                &menu.text,
+                title.cast_unit(),
+                color.into(),
+                state.mnemonics,
+            );
+        }
+
+        builder.build()
+    }
+}
+
+struct MenuPainter;
+
+impl draw::Painter<state::MenuState> for MenuPainter {
+    fn invoke(&self, theme: &dyn draw::Theme) -> Box<dyn draw::Painter<state::MenuState>> {
+        theme.menu()
+    }
+
+    fn size_hint(&self, state: state::MenuState) -> Size {
+        state.rect.size.cast_unit()
+    }
+
+    fn paint_hint(&self, rect: RelativeRect) -> RelativeRect {
+        // account for drop shadow
+        rect.inflate(10.0, 10.0)
+    }
+
+    fn mouse_hint(&self, rect: RelativeRect) -> RelativeRect {
+        rect
+    }
+
+    fn draw(&mut self, state: state::MenuState) -> Vec<DisplayCommand> {
+        let style = &state.style;
+        let rect = base::sharp_align(state.rect.cast_unit());
+        let mut builder = DisplayListBuilder::new();
+
+        // Drop shadow
+        builder.push_round_rectangle(
+            rect.translate(Vector::new(0.0, 3.0)),
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(Color::new(0.0, 0.0, 0.0, 0.15).into()),
+            Some(Filter::Blur(5.0, 5.0)),
+        );
+
+        // Background
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Fill(draw::weaken(style.background, 0.5, style.contrast).into()),
+            None,
+        );
+
+        // Border
+        builder.push_round_rectangle(
+            rect,
+            [3.5; 4],
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: 1.0 / 3.0,
+                color: draw::weaken(style.color, 0.4, style.contrast).into(),
+                ..Default::default()
+            }),
+            None,
+        );
+
+        for (i, (item, item_rect)) in state.items.iter().zip(state.item_rects.iter()).enumerate() {
+            let item_rect: Rect = item_rect.cast_unit();
+            match item {
+                ui::MenuItem::Action(action) => {
+                    let color = if action.disabled {
+                        style.disabled_color
+                    } else if state.highlighted == Some(i) {
+                        builder.push_rectangle(
+                            item_rect,
+                            GraphicsDisplayPaint::Fill(style.highlight.into()),
+                            None,
+                        );
+                        style.over_highlight
+                    } else {
+                        style.color
+                    };
+
+                    push_menu_text(
+                        &mut builder,
+                        style,
+                        &action.text,
+                        item_rect.inflate(-style.padding, 0.0),
+                        color.into(),
+                        state.mnemonics,
+                    );
+
+                    if let Some(ref shortcut) = action.shortcut {
+                        let mut text_item = style
+                            .text_item(shortcut, draw::weaken(color, 0.3, style.contrast).into());
+                        let bounds = text_item.bounds().unwrap();
+                        text_item.set_top_left(Point::new(
+                            item_rect.max_x() - style.padding * 2.0 - bounds.size.width,
+                            display::center(bounds.size, item_rect).y,
+                        ));
+                        builder.push_text(text_item, None);
+                    }
+                }
+                ui::MenuItem::Separator => {
+                    let y = item_rect.origin.y + (item_rect.size.height / 2.0).floor() + 0.5;
+                    builder.push_line(
+                        Point::new(item_rect.min_x() + style.padding, y),
+                        Point::new(item_rect.max_x() - style.padding, y),
+                        GraphicsDisplayStroke {
+                            thickness: 1.0,
+                            color: draw::strengthen(style.background, 0.1, style.contrast).into(),
+                            ..Default::default()
+                        },
+                        None,
+                    );
+                }
+            }
+        }
 
         builder.build()
     }
@@ -0,0 +1,261 @@
+use {
+    crate::{
+        base::{self, Resizable},
+        draw,
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{DisplayCommand, Rect, Size},
+        event::{RcEventListener, RcEventQueue},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::marker::PhantomData,
+};
+
+lazy_widget! {
+    generic AccordionWidget,
+    visibility: visibility,
+    theme: themed,
+    drop_event: drop_event
+}
+
+/// Abstract widget which stacks `Collapsible` sections vertically, allowing only one section to be expanded at a time.
+///
+/// Sections are moved into the accordion with `push`.
+#[derive(LayableWidget, Movable, Resizable, OperatesVerbGraph)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+pub struct AccordionWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub data: base::Observed<Accordion>,
+
+    sections: Vec<ui::CollapsibleWidget<U, G>>,
+    listeners: Vec<RcEventListener<ui::CollapsibleEvent>>,
+    visibility: base::Visibility,
+    themed: draw::PhantomThemed,
+    drop_event: RcEventQueue<base::DropEvent>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    phantom_u: PhantomData<U>,
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Accordion {
+    /// Vertical space between sections.
+    pub spacing: f32,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Accordion
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = AccordionWidget<U, G>;
+}
+
+impl Accordion {
+    pub fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        Accordion { spacing: 0.0 }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        _theme: &dyn draw::Theme,
+        _u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> AccordionWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        AccordionWidget {
+            data,
+
+            sections: Vec::new(),
+            listeners: Vec::new(),
+            visibility: Default::default(),
+            themed: Default::default(),
+            drop_event: Default::default(),
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            layout: Default::default(),
+
+            graph: None,
+            phantom_u: Default::default(),
+            phantom_g: Default::default(),
+        }
+    }
+}
+
+impl<U, G> AccordionWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Moves a section into the accordion, returning its index.
+    ///
+    /// The section is collapsed if another section is already expanded.
+    pub fn push(&mut self, mut section: ui::CollapsibleWidget<U, G>) -> usize {
+        if section.data.expanded && self.sections.iter().any(|section| section.data.expanded) {
+            section.data.expanded = false;
+        }
+
+        section.set_parent_position(self.abs_position());
+        self.listeners.push(section.event_queue.listen());
+        self.sections.push(section);
+        self.stack_sections();
+
+        self.sections.len() - 1
+    }
+
+    /// Returns the sections, in order.
+    #[inline]
+    pub fn sections(&self) -> &[ui::CollapsibleWidget<U, G>] {
+        &self.sections
+    }
+
+    /// Returns the sections mutably, in order.
+    #[inline]
+    pub fn sections_mut(&mut self) -> &mut [ui::CollapsibleWidget<U, G>] {
+        &mut self.sections
+    }
+
+    fn on_transform(&mut self) {
+        base::LayableWidget::notify_layout(self);
+    }
+
+    /// Stacks the sections vertically and resizes to fit them.
+    fn stack_sections(&mut self) {
+        let mut size = Size::zero();
+        for (i, section) in self.sections.iter_mut().enumerate() {
+            if i > 0 {
+                size.height += self.data.spacing;
+            }
+
+            let position = RelativePoint::new(0.0, size.height);
+            if section.position() != position {
+                section.set_ctxt_position(position.into());
+            }
+
+            let section_size = section.size();
+            size.height += section_size.height;
+            size.width = size.width.max(section_size.width);
+        }
+
+        if size != self.size() {
+            self.set_size(size);
+        }
+    }
+}
+
+impl<U, G> base::WidgetChildren for AccordionWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn children(
+        &self,
+    ) -> Vec<
+        &dyn base::WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>,
+    > {
+        self.sections.iter().map(|section| section as _).collect()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut dyn base::WidgetChildren<
+            UpdateAux = U,
+            GraphicalAux = G,
+            DisplayObject = DisplayCommand,
+        >,
+    > {
+        self.sections.iter_mut().map(|section| section as _).collect()
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for AccordionWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> Widget for AccordionWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        base::invoke_update(self, aux);
+
+        let mut expanded = None;
+        for (i, listener) in self.listeners.iter().enumerate() {
+            if listener.peek().into_iter().any(|event| event == ui::CollapsibleEvent::Expand) {
+                expanded = Some(i);
+            }
+        }
+
+        if let Some(expanded) = expanded {
+            for (i, section) in self.sections.iter_mut().enumerate() {
+                if i != expanded && section.data.expanded {
+                    section.data.expanded = false;
+                }
+            }
+        }
+
+        self.stack_sections();
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<Accordion> for AccordionWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<Accordion> {
+        &mut self.data
+    }
+}
+
+impl<U, G> StoresParentPosition for AccordionWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
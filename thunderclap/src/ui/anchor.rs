@@ -0,0 +1,443 @@
+use {
+    crate::{
+        base::{self, Layout, Resizable},
+        draw,
+        geom::*,
+        ui,
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{DisplayCommand, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener, RcEventQueue},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::marker::PhantomData,
+};
+
+/// Information about how an `Anchor` child should be positioned, relative to the edges of the `Anchor`.
+///
+/// On each axis, a child anchored to both edges is stretched between them.
+/// A child anchored to a single edge keeps its size (or its percentage size), and a child anchored to neither edge is centered if it has a center offset,
+/// otherwise it's placed at the start edge.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct AnchorItem {
+    /// Distance between the left edge of the anchor and the left edge of the child.
+    pub left: Option<f32>,
+    /// Distance between the right edge of the anchor and the right edge of the child.
+    pub right: Option<f32>,
+    /// Distance between the top edge of the anchor and the top edge of the child.
+    pub top: Option<f32>,
+    /// Distance between the bottom edge of the anchor and the bottom edge of the child.
+    pub bottom: Option<f32>,
+    /// Horizontal offset of the child from being horizontally centered.
+    pub center_x: Option<f32>,
+    /// Vertical offset of the child from being vertically centered.
+    pub center_y: Option<f32>,
+    /// Width of the child as a percentage of the width of the anchor.
+    pub width_percent: Option<f32>,
+    /// Height of the child as a percentage of the height of the anchor.
+    pub height_percent: Option<f32>,
+}
+
+impl AnchorItem {
+    pub fn new() -> AnchorItem {
+        Default::default()
+    }
+
+    /// Anchors the child to all edges, inset by `inset`.
+    pub fn fill(inset: f32) -> AnchorItem {
+        AnchorItem {
+            left: Some(inset),
+            right: Some(inset),
+            top: Some(inset),
+            bottom: Some(inset),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the `left` value.
+    pub fn left(self, left: f32) -> AnchorItem {
+        AnchorItem { left: Some(left), ..self }
+    }
+
+    /// Sets the `right` value.
+    pub fn right(self, right: f32) -> AnchorItem {
+        AnchorItem { right: Some(right), ..self }
+    }
+
+    /// Sets the `top` value.
+    pub fn top(self, top: f32) -> AnchorItem {
+        AnchorItem { top: Some(top), ..self }
+    }
+
+    /// Sets the `bottom` value.
+    pub fn bottom(self, bottom: f32) -> AnchorItem {
+        AnchorItem { bottom: Some(bottom), ..self }
+    }
+
+    /// Sets the `center_x` value.
+    pub fn center_x(self, offset: f32) -> AnchorItem {
+        AnchorItem { center_x: Some(offset), ..self }
+    }
+
+    /// Sets the `center_y` value.
+    pub fn center_y(self, offset: f32) -> AnchorItem {
+        AnchorItem { center_y: Some(offset), ..self }
+    }
+
+    /// Sets the `width_percent` value.
+    pub fn width_percent(self, percent: f32) -> AnchorItem {
+        AnchorItem { width_percent: Some(percent), ..self }
+    }
+
+    /// Sets the `height_percent` value.
+    pub fn height_percent(self, percent: f32) -> AnchorItem {
+        AnchorItem { height_percent: Some(percent), ..self }
+    }
+}
+
+/// Anchoring of a child along a single axis.
+struct AxisAnchor {
+    start: Option<f32>,
+    end: Option<f32>,
+    center: Option<f32>,
+    percent: Option<f32>,
+}
+
+impl AxisAnchor {
+    /// Returns whether the length of the child along this axis is decided by the anchor rather than the child.
+    fn is_fixed(&self) -> bool {
+        (self.start.is_some() && self.end.is_some()) || self.percent.is_some()
+    }
+
+    /// Returns the position and length of a child of `measured` length within `origin..origin + length`.
+    fn resolve(&self, origin: f32, length: f32, measured: f32) -> (f32, f32) {
+        let size = self.percent.map(|percent| length * percent / 100.0).unwrap_or(measured);
+        match (self.start, self.end, self.center) {
+            (Some(start), Some(end), _) => (origin + start, (length - start - end).max(0.0)),
+            (Some(start), None, _) => (origin + start, size),
+            (None, Some(end), _) => (origin + length - end - size, size),
+            (None, None, Some(center)) => (origin + ((length - size) / 2.0).round() + center, size),
+            (None, None, None) => (origin, size),
+        }
+    }
+
+    /// Returns the largest length the child may take within `length`.
+    fn available(&self, length: f32) -> f32 {
+        (length - self.start.unwrap_or(0.0) - self.end.unwrap_or(0.0)).max(0.0)
+    }
+}
+
+impl AnchorItem {
+    fn horizontal(&self) -> AxisAnchor {
+        AxisAnchor {
+            start: self.left,
+            end: self.right,
+            center: self.center_x,
+            percent: self.width_percent,
+        }
+    }
+
+    fn vertical(&self) -> AxisAnchor {
+        AxisAnchor {
+            start: self.top,
+            end: self.bottom,
+            center: self.center_y,
+            percent: self.height_percent,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ChildData {
+    data: AnchorItem,
+    evq: BidirSingleEventQueue<base::LayoutEvent, base::LayoutEvent>,
+    node: base::LayoutNode,
+    drop_listener: RcEventListener<base::DropEvent>,
+    rect: AbsoluteRect,
+    /// Size last measured by the child.
+    size: Size,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+lazy_widget! {
+    generic AnchorWidget,
+    visibility: visibility,
+    theme: themed,
+    drop_event: drop_event
+}
+
+/// Abstract layout widget which positions each child relative to its own edges (see `AnchorItem`).
+///
+/// Unlike most layouts, the anchor doesn't size itself from its children; it keeps whichever size it's given,
+/// and the children are repositioned whenever it's resized.
+#[derive(LayableWidget, Movable, Resizable, OperatesVerbGraph)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+#[widget_measure(measure_children)]
+pub struct AnchorWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub data: base::Observed<Anchor>,
+
+    rects: IndexMap<u64, ChildData>,
+    next_rect_id: u64,
+    visibility: base::Visibility,
+    themed: draw::PhantomThemed,
+    drop_event: RcEventQueue<base::DropEvent>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    phantom_u: PhantomData<U>,
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anchor {}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Anchor
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = AnchorWidget<U, G>;
+}
+
+impl Anchor {
+    pub fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        Anchor {}
+    }
+
+    pub fn construct<U, G>(
+        self,
+        _theme: &dyn draw::Theme,
+        _u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> AnchorWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        AnchorWidget {
+            data,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            visibility: Default::default(),
+            themed: Default::default(),
+            drop_event: Default::default(),
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            layout: Default::default(),
+
+            graph: None,
+            phantom_u: Default::default(),
+            phantom_g: Default::default(),
+        }
+    }
+}
+
+impl<U, G> AnchorWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn on_transform(&mut self) {
+        self.layout.mark_dirty();
+        base::LayableWidget::notify_layout(self);
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for AnchorWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> base::Layout for AnchorWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = AnchorItem;
+
+    /// Pushes a child into the anchor.
+    /// Without an `AnchorItem`, the child is placed in the top-left corner.
+    fn push(&mut self, data: Option<AnchorItem>, child: &mut impl base::LayableWidget) {
+        self.layout.mark_dirty();
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            layout_node: self.layout.node().clone(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                data: data.unwrap_or_default(),
+                evq,
+                node: child.layout_events().node().clone(),
+                drop_listener: child.drop_event().listen(),
+                rect,
+                size: rect.size.cast_unit(),
+                original_rect: rect,
+                id,
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.layout.mark_dirty();
+            child.listen_to_layout(None);
+            if restore_original {
+                child.arrange(data.original_rect);
+            }
+        }
+    }
+
+    fn measure_children(&self, constraints: base::Constraints) -> Size {
+        constraints.constrain(self.size())
+    }
+}
+
+impl<U, G> Widget for AnchorWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+}
+
+impl<U, G> base::WidgetChildren for AnchorWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    fn relayout(&mut self) {
+        if self.layout.direction_changed() {
+            self.layout.mark_dirty();
+        }
+
+        {
+            let mut removals = Vec::new();
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    self.layout.mark_dirty();
+                    continue;
+                }
+
+                if let Some(base::LayoutEvent::Measure { rect, size }) = data.evq.retrieve_newest()
+                {
+                    if rect != data.rect || size != data.size {
+                        self.layout.mark_dirty();
+                        data.rect = rect;
+                        data.size = size;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.remove(&removal);
+            }
+        }
+
+        if self.layout.is_dirty() {
+            self.layout.invalidate();
+            let abs_rect = self.abs_rect();
+            let direction = self.layout.direction();
+            for (_, data) in &mut self.rects {
+                let (horizontal, vertical) = (data.data.horizontal(), data.data.vertical());
+                let (x, width) =
+                    horizontal.resolve(abs_rect.origin.x, abs_rect.size.width, data.size.width);
+                let (y, height) =
+                    vertical.resolve(abs_rect.origin.y, abs_rect.size.height, data.size.height);
+                let rect = AbsoluteRect::new(
+                    AbsolutePoint::new(x, y),
+                    Size::new(width, height).cast_unit(),
+                );
+
+                let max = Size::new(
+                    horizontal.available(abs_rect.size.width),
+                    vertical.available(abs_rect.size.height),
+                );
+                let constraints = base::Constraints::new(
+                    Size::new(
+                        if horizontal.is_fixed() { width } else { 0.0 },
+                        if vertical.is_fixed() { height } else { 0.0 },
+                    ),
+                    Size::new(
+                        if horizontal.is_fixed() { width } else { max.width },
+                        if vertical.is_fixed() { height } else { max.height },
+                    ),
+                );
+
+                let rect = ui::mirror(rect, abs_rect, direction);
+                data.evq.emit_owned(base::LayoutEvent::Arrange { constraints, rect, direction });
+                data.node.invalidate();
+                data.rect = rect;
+            }
+
+            self.layout.clear_dirty();
+            if self.layout.measured().is_none() {
+                base::LayableWidget::notify_layout(self);
+            }
+        }
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<Anchor> for AnchorWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<Anchor> {
+        &mut self.data
+    }
+}
+
+impl<U, G> StoresParentPosition for AnchorWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
@@ -0,0 +1,142 @@
+use {
+    crate::{
+        base::{self, Layout},
+        draw,
+        geom::*,
+        ui,
+    },
+    reclutch::{display::Size, event::RcEventQueue, prelude::*, verbgraph as vg},
+    std::marker::PhantomData,
+};
+
+lazy_widget! {
+    generic AspectRatioWidget,
+    visibility: visibility,
+    theme: themed,
+    drop_event: drop_event
+}
+
+/// Abstract layout widget which sizes a single child to a fixed ratio of width to height (e.g. 16:9 for video).
+///
+/// The widget takes up as much width as the constraints given by the parent layout allow (or the width of the child, if unbounded), and the matching height.
+/// If the parent layout gives the widget a different shape, the child is given the largest rectangle of the ratio which fits, centered within the widget.
+/// Pushing another child replaces the previous one.
+#[derive(LayableWidget, Movable, Resizable, OperatesVerbGraph)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+#[widget_measure(measure_children)]
+pub struct AspectRatioWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub data: base::Observed<AspectRatio>,
+
+    child: ui::SingleChild,
+    visibility: base::Visibility,
+    themed: draw::PhantomThemed,
+    drop_event: RcEventQueue<base::DropEvent>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    phantom_u: PhantomData<U>,
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AspectRatio {
+    /// Width divided by height (e.g. `16.0 / 9.0`); must be positive.
+    pub ratio: f32,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for AspectRatio
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = AspectRatioWidget<U, G>;
+}
+
+impl AspectRatio {
+    pub fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        AspectRatio { ratio: 1.0 }
+    }
+
+    /// Returns the largest size of the ratio which fits within `size`.
+    pub fn fit(&self, size: Size) -> Size {
+        if size.width / size.height > self.ratio {
+            Size::new(size.height * self.ratio, size.height)
+        } else {
+            Size::new(size.width, size.width / self.ratio)
+        }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        _theme: &dyn draw::Theme,
+        _u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> AspectRatioWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            AspectRatioWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.layout.mark_dirty();
+                }
+            }
+        };
+
+        AspectRatioWidget {
+            data,
+
+            child: Default::default(),
+            visibility: Default::default(),
+            themed: Default::default(),
+            drop_event: Default::default(),
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            layout: Default::default(),
+
+            graph: graph.into(),
+            phantom_u: Default::default(),
+            phantom_g: Default::default(),
+        }
+    }
+}
+
+single_child_layout!(AspectRatioWidget, AspectRatio);
+
+impl<U, G> AspectRatioWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn measure_single(&self, constraints: base::Constraints, child: Size) -> Size {
+        let width =
+            if constraints.max.width.is_finite() { constraints.max.width } else { child.width };
+
+        constraints.constrain(self.data.fit(Size::new(width, constraints.max.height)))
+    }
+
+    fn arrange_single(
+        &self,
+        rect: AbsoluteRect,
+        _child: Size,
+    ) -> (base::Constraints, AbsoluteRect) {
+        let rect = ui::center_within(self.data.fit(rect.size.cast_unit()), rect);
+        (base::Constraints::tight(rect.size.cast_unit()), rect)
+    }
+}
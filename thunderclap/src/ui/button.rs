@@ -8,12 +8,20 @@ use {
         ui,
     },
     reclutch::{
-        display::{Color, CommandGroup, DisplayCommand, DisplayText, GraphicsDisplay, Rect},
+        display::{
+            Color, CommandGroup, DisplayCommand, DisplayText, GraphicsDisplay, Rect,
+            ResourceReference, VectorPath,
+        },
         event::RcEventQueue,
         prelude::*,
         verbgraph as vg,
     },
-    std::marker::PhantomData,
+    std::{
+        fmt,
+        marker::PhantomData,
+        rc::Rc,
+        time::{Duration, Instant},
+    },
 };
 
 /// Events emitted by a button.
@@ -37,9 +45,84 @@ pub enum ButtonEvent {
     /// Emitted when focus is lost.
     #[event_key(blur)]
     Blur,
+    /// Emitted when a toggle button is toggled, carrying whether it's now toggled on.
+    #[event_key(toggled)]
+    Toggled(bool),
+    /// Emitted once the button has been held down for `long_press`.
+    #[event_key(long_press)]
+    LongPress(AbsolutePoint),
+}
+
+/// Visual variant of a button, whose colors are resolved by the theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ButtonKind {
+    /// Regular button, colored with the `color` and `background` of the button.
+    Default,
+    /// Emphasized button for the main action, colored with the primary color of the theme.
+    Primary,
+    /// Button for destructive actions, colored with the error color of the theme.
+    Danger,
+    /// Button with only a border.
+    Outline,
+    /// Button which looks like a hyperlink.
+    Link,
+}
+
+/// Icon shown within a button.
+#[derive(Clone)]
+pub enum ButtonIcon {
+    /// Vector icon, generated to fit within a given rectangle and stroked with the text color.
+    ///
+    /// Two path icons are only equal if they share the same generator (see `ButtonIcon::path`).
+    Path(Rc<dyn Fn(Rect) -> VectorPath>),
+    /// Image icon, scaled to fit the icon size.
+    Image(ResourceReference),
+}
+
+impl ButtonIcon {
+    /// Creates a vector icon from a function which generates the path within a given rectangle.
+    pub fn path(path: impl Fn(Rect) -> VectorPath + 'static) -> Self {
+        ButtonIcon::Path(Rc::new(path))
+    }
+}
+
+impl PartialEq for ButtonIcon {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (ButtonIcon::Path(a), ButtonIcon::Path(b)) => Rc::ptr_eq(a, b),
+            (ButtonIcon::Image(a), ButtonIcon::Image(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for ButtonIcon {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ButtonIcon::Path(_) => f.write_str("Path"),
+            ButtonIcon::Image(image) => f.debug_tuple("Image").field(image).finish(),
+        }
+    }
+}
+
+/// Timing of a button which is being held down.
+#[derive(Debug, Clone, Copy)]
+struct ButtonHold {
+    position: AbsolutePoint,
+    /// Time at which the button was pressed (`None` if the hold has only just begun).
+    start: Option<Instant>,
+    /// Time at which the press next repeats.
+    next_repeat: Option<Instant>,
+    long_pressed: bool,
 }
 
 /// Focus-able button widget.
+///
+/// Buttons can display icons before and after the text, or only icons (see `icon_only`).
+/// In toggle mode (see `toggle`), the button stays pressed in between clicks.
+///
+/// With `auto_repeat`, holding the button down emits `ButtonEvent::Press` repeatedly (while the cursor stays over the button),
+/// which is useful for step controls and scroll arrows.
 #[derive(
     WidgetChildren,
     LayableWidget,
@@ -63,6 +146,7 @@ where
     pub data: base::Observed<Button>,
     graph: vg::OptionVerbGraph<Self, U>,
     interaction: state::InteractionState,
+    hold: Option<ButtonHold>,
     painter: Box<dyn draw::Painter<state::ButtonState>>,
     parent_position: AbsolutePoint,
 
@@ -102,6 +186,24 @@ where
 
     fn on_interaction_event(&mut self, event: ui::InteractionEvent) {
         self.repaint();
+        match event {
+            ui::InteractionEvent::Pressed(pos) => {
+                self.hold = Some(ButtonHold {
+                    position: pos,
+                    start: None,
+                    next_repeat: None,
+                    long_pressed: false,
+                });
+            }
+            ui::InteractionEvent::Released(_) => self.hold = None,
+            _ => {}
+        }
+        if let ui::InteractionEvent::Released(pos) = event {
+            if self.data.toggle && self.abs_convert_rect(self.mouse_bounds()).contains(pos) {
+                self.data.toggled = !self.data.toggled;
+                self.event_queue.emit_owned(ButtonEvent::Toggled(self.data.toggled));
+            }
+        }
         self.event_queue.emit_owned(match event {
             ui::InteractionEvent::Pressed(pos) => ButtonEvent::Press(pos),
             ui::InteractionEvent::Released(pos) => ButtonEvent::Release(pos),
@@ -115,12 +217,36 @@ where
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Button {
+    /// Text shown in the button. For icon-only buttons, this is used as a label which isn't shown.
     pub text: DisplayText,
+    pub kind: ButtonKind,
+    /// Icon shown before the text.
+    pub leading_icon: Option<ButtonIcon>,
+    /// Icon shown after the text.
+    pub trailing_icon: Option<ButtonIcon>,
+    /// Width and height of icons.
+    pub icon_size: f32,
+    /// Whether only the icons are shown. This has no effect if the button has no icons.
+    pub icon_only: bool,
     pub typeface: draw::TypefaceStyle,
     pub color: Color,
     pub background: Color,
     pub focus: Color,
     pub contrast: draw::ThemeContrast,
+    /// How text which doesn't fit within the button is displayed.
+    pub overflow: draw::TextOverflow,
+    /// Whether the button toggles on and off when clicked.
+    pub toggle: bool,
+    /// Whether a toggle button is toggled on.
+    pub toggled: bool,
+    /// Whether holding the button down repeatedly emits `ButtonEvent::Press`.
+    pub auto_repeat: bool,
+    /// Time the button has to be held before it starts repeating.
+    pub repeat_delay: Duration,
+    /// Time between repeated presses.
+    pub repeat_interval: Duration,
+    /// Time the button has to be held to emit `ButtonEvent::LongPress`, if at all.
+    pub long_press: Option<Duration>,
     pub disabled: bool,
 }
 
@@ -137,11 +263,23 @@ impl Button {
         let data = theme.data();
         Button {
             text: "".to_string().into(),
+            kind: ButtonKind::Default,
+            leading_icon: None,
+            trailing_icon: None,
+            icon_size: 16.0,
+            icon_only: false,
             typeface: data.typography.button.clone(),
             color: data.scheme.over_control_outset,
             background: data.scheme.control_outset,
             focus: data.scheme.focus,
             contrast: data.contrast,
+            overflow: draw::TextOverflow::Ellipsis,
+            toggle: false,
+            toggled: false,
+            auto_repeat: false,
+            repeat_delay: Duration::from_millis(400),
+            repeat_interval: Duration::from_millis(50),
+            long_press: None,
             disabled: false,
         }
     }
@@ -191,6 +329,7 @@ impl Button {
             data,
             graph: graph.into(),
             interaction: state::InteractionState::empty(),
+            hold: None,
             painter,
             parent_position: Default::default(),
             rect,
@@ -210,7 +349,7 @@ where
 {
     fn on_transform(&mut self) {
         self.repaint();
-        self.layout.notify(self.abs_rect());
+        base::LayableWidget::notify_layout(self);
     }
 
     fn derive_state(&self) -> state::ButtonState {
@@ -251,9 +390,42 @@ where
         graph.update_all(self, aux);
         self.graph = Some(graph);
 
-        if let Some(rect) = self.layout.receive() {
-            self.set_ctxt_rect(rect);
-            self.command_group.repaint();
+        if let Some(mut hold) = self.hold {
+            let now = aux.now();
+            let start = *hold.start.get_or_insert(now);
+            let mut deadline: Option<Instant> = None;
+
+            if self.data.auto_repeat {
+                let next_repeat = match hold.next_repeat {
+                    Some(next_repeat) if now >= next_repeat => {
+                        if self.interaction.contains(state::InteractionState::HOVERED) {
+                            self.event_queue.emit_owned(ButtonEvent::Press(hold.position));
+                        }
+                        now + self.data.repeat_interval
+                    }
+                    Some(next_repeat) => next_repeat,
+                    None => start + self.data.repeat_delay,
+                };
+                hold.next_repeat = Some(next_repeat);
+                deadline = Some(next_repeat);
+            }
+
+            if let Some(threshold) = self.data.long_press {
+                if !hold.long_pressed {
+                    if now >= start + threshold {
+                        hold.long_pressed = true;
+                        self.event_queue.emit_owned(ButtonEvent::LongPress(hold.position));
+                    } else {
+                        let long_press = start + threshold;
+                        deadline = Some(deadline.map_or(long_press, |d| d.min(long_press)));
+                    }
+                }
+            }
+
+            if let Some(deadline) = deadline {
+                aux.request_wakeup(deadline);
+            }
+            self.hold = Some(hold);
         }
     }
 
@@ -0,0 +1,126 @@
+use {
+    crate::{
+        base::{self, Layout},
+        draw,
+        geom::*,
+        ui,
+    },
+    reclutch::{display::Size, event::RcEventQueue, prelude::*, verbgraph as vg},
+    std::marker::PhantomData,
+};
+
+lazy_widget! {
+    generic CenterWidget,
+    visibility: visibility,
+    theme: themed,
+    drop_event: drop_event
+}
+
+/// Abstract layout widget which centers a single child within itself.
+///
+/// Along axes where the constraints given by the parent layout are bounded, the center takes up as much space as it can; otherwise it fits the child.
+/// Pushing another child replaces the previous one.
+#[derive(LayableWidget, Movable, Resizable, OperatesVerbGraph)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+#[widget_measure(measure_children)]
+pub struct CenterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub data: base::Observed<Center>,
+
+    child: ui::SingleChild,
+    visibility: base::Visibility,
+    themed: draw::PhantomThemed,
+    drop_event: RcEventQueue<base::DropEvent>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    phantom_u: PhantomData<U>,
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Center {}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Center
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = CenterWidget<U, G>;
+}
+
+impl Center {
+    pub fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        Center {}
+    }
+
+    pub fn construct<U, G>(
+        self,
+        _theme: &dyn draw::Theme,
+        _u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> CenterWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            CenterWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.layout.mark_dirty();
+                }
+            }
+        };
+
+        CenterWidget {
+            data,
+
+            child: Default::default(),
+            visibility: Default::default(),
+            themed: Default::default(),
+            drop_event: Default::default(),
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            layout: Default::default(),
+
+            graph: graph.into(),
+            phantom_u: Default::default(),
+            phantom_g: Default::default(),
+        }
+    }
+}
+
+single_child_layout!(CenterWidget, Center);
+
+impl<U, G> CenterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn measure_single(&self, constraints: base::Constraints, child: Size) -> Size {
+        let fill = |max: f32, length: f32| if max.is_finite() { max } else { length };
+        constraints.constrain(Size::new(
+            fill(constraints.max.width, child.width),
+            fill(constraints.max.height, child.height),
+        ))
+    }
+
+    fn arrange_single(&self, rect: AbsoluteRect, child: Size) -> (base::Constraints, AbsoluteRect) {
+        let size = Size::new(child.width.min(rect.size.width), child.height.min(rect.size.height));
+        (base::Constraints::loose(rect.size.cast_unit()), ui::center_within(size, rect))
+    }
+}
@@ -1,12 +1,12 @@
 use {
     crate::{
         base::{self, Repaintable, Resizable},
-        draw::{self, state},
+        draw::{self, state, HasTheme},
         geom::*,
         ui,
     },
     reclutch::{
-        display::{Color, CommandGroup, DisplayCommand, GraphicsDisplay, Rect},
+        display::{Color, CommandGroup, DisplayCommand, DisplayText, GraphicsDisplay, Rect},
         event::RcEventQueue,
         prelude::*,
         verbgraph as vg,
@@ -29,6 +29,9 @@ pub enum CheckboxEvent {
     /// Emitted when the button is checked.
     #[event_key(uncheck)]
     Uncheck(AbsolutePoint),
+    /// Emitted when the user changes the state of the checkbox, carrying the new state.
+    #[event_key(state_changed)]
+    StateChanged(CheckState),
     /// Emitted when the mouse enters the checkbox boundaries.
     #[event_key(begin_hover)]
     BeginHover(AbsolutePoint),
@@ -43,7 +46,39 @@ pub enum CheckboxEvent {
     Blur,
 }
 
-/// Checkbox widget; useful for boolean input.
+/// State of a checkbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CheckState {
+    Unchecked,
+    Checked,
+    /// Neither checked nor unchecked, e.g. a "select all" checkbox when only some items are selected.
+    /// This state can only be set programmatically; toggling it checks the checkbox.
+    Indeterminate,
+}
+
+impl CheckState {
+    /// Returns the state which the user toggles to from `self`.
+    pub fn toggled(self) -> Self {
+        match self {
+            CheckState::Checked => CheckState::Unchecked,
+            CheckState::Unchecked | CheckState::Indeterminate => CheckState::Checked,
+        }
+    }
+}
+
+impl From<bool> for CheckState {
+    fn from(checked: bool) -> Self {
+        if checked {
+            CheckState::Checked
+        } else {
+            CheckState::Unchecked
+        }
+    }
+}
+
+/// Checkbox widget with an optional label; useful for boolean input.
+///
+/// Clicking the label toggles the checkbox just as clicking the box itself does.
 #[derive(
     WidgetChildren,
     LayableWidget,
@@ -111,12 +146,14 @@ where
                 self.event_queue.emit_owned(CheckboxEvent::Press(pos));
             }
             ui::InteractionEvent::Released(pos) => {
-                self.data.checked = !self.data.checked;
-                self.event_queue.emit_owned(if self.data.checked {
+                let check_state = self.data.state.toggled();
+                self.data.state = check_state;
+                self.event_queue.emit_owned(if check_state == CheckState::Checked {
                     CheckboxEvent::Check(pos)
                 } else {
                     CheckboxEvent::Uncheck(pos)
                 });
+                self.event_queue.emit_owned(CheckboxEvent::StateChanged(check_state));
                 self.event_queue.emit_owned(CheckboxEvent::Release(pos));
             }
             ui::InteractionEvent::BeginHover(pos) => {
@@ -135,13 +172,18 @@ where
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Checkbox {
+    /// Text of the label shown beside the box. The label is omitted if this is empty.
+    pub text: DisplayText,
+    pub typeface: draw::TypefaceStyle,
+    /// Color of the label text.
+    pub color: Color,
     pub foreground: Color,
     pub background: Color,
     pub focus: Color,
     pub contrast: draw::ThemeContrast,
-    pub checked: bool,
+    pub state: CheckState,
     pub disabled: bool,
 }
 
@@ -149,11 +191,14 @@ impl Checkbox {
     pub fn from_theme(theme: &dyn draw::Theme) -> Self {
         let data = theme.data();
         Checkbox {
+            text: "".to_string().into(),
+            typeface: data.typography.body.clone(),
+            color: data.scheme.over_control_outset,
             foreground: data.scheme.over_control_inset,
             background: data.scheme.control_inset,
             focus: data.scheme.focus,
             contrast: data.contrast,
-            checked: false,
+            state: CheckState::Unchecked,
             disabled: false,
         }
     }
@@ -173,7 +218,12 @@ impl Checkbox {
         let mut graph = vg::verbgraph! {
             CheckboxWidget<U, G> as obj,
             U as _aux,
-            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.resize_from_theme();
+                    obj.command_group.repaint();
+                }
+            }
         };
 
         graph = graph.add(
@@ -220,7 +270,7 @@ where
 {
     fn on_transform(&mut self) {
         self.repaint();
-        self.layout.notify(self.abs_rect());
+        base::LayableWidget::notify_layout(self);
     }
 
     fn derive_state(&self) -> state::CheckboxState {
@@ -270,11 +320,6 @@ where
                 CheckboxEvent::Blur
             });
         }
-
-        if let Some(rect) = self.layout.receive() {
-            self.set_ctxt_rect(rect);
-            self.command_group.repaint();
-        }
     }
 
     fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
@@ -305,7 +350,7 @@ where
     }
 }
 
-impl<U, G> draw::HasTheme for CheckboxWidget<U, G>
+impl<U, G> HasTheme for CheckboxWidget<U, G>
 where
     U: base::UpdateAuxiliary + 'static,
     G: base::GraphicalAuxiliary + 'static,
@@ -0,0 +1,494 @@
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, CommandGroup, DisplayCommand, DisplayText, GraphicsDisplay, Rect, Size},
+        event::RcEventQueue,
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{
+        marker::PhantomData,
+        time::{Duration, Instant},
+    },
+};
+
+/// Duration of the expand/collapse animation.
+const ANIMATION_DURATION: Duration = Duration::from_millis(150);
+/// Interval between animation frames.
+const ANIMATION_FRAME: Duration = Duration::from_millis(16);
+
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum CollapsibleEvent {
+    /// The user expanded the section.
+    #[event_key(expand)]
+    Expand,
+    /// The user collapsed the section.
+    #[event_key(collapse)]
+    Collapse,
+}
+
+pub fn collapsible_handler<U, G>(
+) -> vg::UnboundQueueHandler<CollapsibleWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        CollapsibleWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        key_press => {
+            if event
+                .with(|(key, _)| {
+                    obj.interaction.contains(state::InteractionState::FOCUSED)
+                        && (*key == base::KeyInput::Space || *key == base::KeyInput::Return)
+                })
+                .is_some()
+            {
+                obj.toggle();
+            }
+        }
+    }
+}
+
+type DynWidget<U, G> =
+    Box<dyn base::WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>>;
+
+/// A header row with a disclosure indicator, which expands or collapses a content widget below it.
+///
+/// The content is moved into the collapsible (see `set_content`) and is hidden entirely while collapsed.
+/// The rectangle of the collapsible covers both the header and the visible content, so enclosing layouts reflow as it expands and collapses.
+#[derive(
+    LayableWidget, DropNotifier, HasVisibility, Repaintable, Movable, Resizable, OperatesVerbGraph,
+)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+pub struct CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub event_queue: RcEventQueue<CollapsibleEvent>,
+    pub data: base::Observed<Collapsible>,
+
+    content: Option<DynWidget<U, G>>,
+    graph: vg::OptionVerbGraph<Self, U>,
+    painter: Box<dyn draw::Painter<state::CollapsibleState>>,
+    interaction: state::InteractionState,
+    header_height: f32,
+    /// How far the content is expanded, from 0 (collapsed) to 1 (expanded).
+    progress: f32,
+    /// Start time, starting progress and target progress of the running animation.
+    animation: Option<(Instant, f32, f32)>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_visibility]
+    visibility: base::Visibility,
+    #[repaint_target]
+    command_group: CommandGroup,
+    #[widget_drop_event]
+    drop_event: RcEventQueue<base::DropEvent>,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+
+    phantom_g: PhantomData<G>,
+}
+
+impl<U, G> ui::InteractiveWidget for CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline(always)]
+    fn interaction(&mut self) -> &mut state::InteractionState {
+        &mut self.interaction
+    }
+
+    #[inline]
+    fn mouse_bounds(&self) -> RelativeRect {
+        self.painter.mouse_hint(self.header_rect())
+    }
+
+    #[inline]
+    fn disabled(&self) -> bool {
+        self.data.disabled
+    }
+
+    fn on_interaction_event(&mut self, event: ui::InteractionEvent) {
+        self.repaint();
+        if let ui::InteractionEvent::Released(pos) = event {
+            if self.abs_convert_rect(self.mouse_bounds()).contains(pos) {
+                self.toggle();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collapsible {
+    /// Text shown in the header.
+    pub text: DisplayText,
+    pub typeface: draw::TypefaceStyle,
+    pub color: Color,
+    /// Background color of the header.
+    pub background: Color,
+    pub focus: Color,
+    pub contrast: draw::ThemeContrast,
+    /// Whether the content is shown.
+    pub expanded: bool,
+    /// Whether the height change is animated when expanding/collapsing.
+    pub animate: bool,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Collapsible
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = CollapsibleWidget<U, G>;
+}
+
+impl Collapsible {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Collapsible {
+            text: "".to_string().into(),
+            typeface: data.typography.body.clone(),
+            color: data.scheme.over_control_outset,
+            background: data.scheme.control_outset,
+            focus: data.scheme.focus,
+            contrast: data.contrast,
+            expanded: true,
+            animate: true,
+            disabled: false,
+        }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        theme: &dyn draw::Theme,
+        u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> CollapsibleWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            CollapsibleWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+        };
+
+        graph = graph.add(
+            "interaction",
+            ui::basic_interaction_handler::<CollapsibleWidget<U, G>, U>()
+                .bind(u_aux.window_queue()),
+        );
+        graph = graph.add("collapsible", collapsible_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let progress = if data.expanded { 1.0 } else { 0.0 };
+
+        let mut collapsible = CollapsibleWidget {
+            event_queue: Default::default(),
+            data,
+
+            content: None,
+            graph: graph.into(),
+            painter: theme.collapsible(),
+            interaction: state::InteractionState::empty(),
+            header_height: 0.0,
+            progress,
+            animation: None,
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            visibility: Default::default(),
+            command_group: Default::default(),
+            drop_event: Default::default(),
+            layout: Default::default(),
+
+            phantom_g: Default::default(),
+        };
+
+        draw::HasTheme::resize_from_theme(&mut collapsible);
+
+        collapsible
+    }
+}
+
+impl<U, G> CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Moves a widget into the collapsible to be shown below the header, replacing (and returning) any previous content.
+    pub fn set_content(
+        &mut self,
+        content: impl base::WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>
+            + 'static,
+    ) -> Option<DynWidget<U, G>> {
+        let mut content: DynWidget<U, G> = Box::new(content);
+        content.set_parent_position(self.abs_position());
+        content.set_ctxt_position(RelativePoint::new(0.0, self.header_height).into());
+
+        let width = self.rect.size.width.max(content.bounds().size.width);
+        self.set_size(Size::new(width, self.rect.size.height));
+
+        let previous = self.content.replace(content);
+        self.update_content_visibility();
+        self.fit_height();
+        previous
+    }
+
+    /// Returns the content widget, if any.
+    pub fn content(
+        &self,
+    ) -> Option<
+        &dyn base::WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>,
+    > {
+        self.content.as_ref().map(|content| content.as_ref() as _)
+    }
+
+    /// Returns the content widget mutably, if any.
+    pub fn content_mut(
+        &mut self,
+    ) -> Option<
+        &mut dyn base::WidgetChildren<
+            UpdateAux = U,
+            GraphicalAux = G,
+            DisplayObject = DisplayCommand,
+        >,
+    > {
+        self.content.as_mut().map(|content| content.as_mut() as _)
+    }
+
+    /// Expands or collapses the content on behalf of the user.
+    fn toggle(&mut self) {
+        let expanded = !self.data.expanded;
+        self.data.expanded = expanded;
+        self.event_queue.emit_owned(if expanded {
+            CollapsibleEvent::Expand
+        } else {
+            CollapsibleEvent::Collapse
+        });
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        base::LayableWidget::notify_layout(self);
+    }
+
+    fn header_rect(&self) -> RelativeRect {
+        RelativeRect::new(self.rect.origin, Size::new(self.rect.size.width, self.header_height))
+    }
+
+    /// Content is only shown once fully expanded, since it can't be clipped to the animated height.
+    fn update_content_visibility(&mut self) {
+        let visibility =
+            if self.progress >= 1.0 { base::Visibility::Normal } else { base::Visibility::None };
+        if let Some(ref mut content) = self.content {
+            if content.visibility() != visibility {
+                content.set_visibility(visibility);
+                content.repaint();
+            }
+        }
+    }
+
+    /// Resizes to fit the header and the expanded portion of the content.
+    fn fit_height(&mut self) {
+        let content_height =
+            self.content.as_ref().map(|content| content.bounds().size.height).unwrap_or(0.0);
+        let height = (self.header_height + content_height * self.progress).round();
+        if (height - self.rect.size.height).abs() > std::f32::EPSILON {
+            self.set_size(Size::new(self.rect.size.width, height));
+        }
+    }
+
+    /// Advances the expand/collapse transition.
+    fn update_progress(&mut self, aux: &mut U) {
+        let target = if self.data.expanded { 1.0 } else { 0.0 };
+
+        if !self.data.animate {
+            self.progress = target;
+            self.animation = None;
+            return;
+        }
+
+        let now = aux.now();
+        let (start, from) = match self.animation {
+            Some((start, from, animation_target)) if animation_target == target => (start, from),
+            _ if self.progress == target => return,
+            _ => (now, self.progress),
+        };
+
+        let t =
+            (now.duration_since(start).as_secs_f32() / ANIMATION_DURATION.as_secs_f32()).min(1.0);
+        self.progress = from + (target - from) * t;
+
+        if t < 1.0 {
+            self.animation = Some((start, from, target));
+            aux.request_wakeup(now + ANIMATION_FRAME);
+        } else {
+            self.animation = None;
+        }
+        self.command_group.repaint();
+    }
+
+    fn derive_state(&self) -> state::CollapsibleState {
+        state::CollapsibleState {
+            rect: self.abs_convert_rect(self.header_rect()),
+            data: self.data.clone(),
+            progress: self.progress,
+            interaction: self.interaction,
+        }
+    }
+}
+
+impl<U, G> base::WidgetChildren for CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn children(
+        &self,
+    ) -> Vec<
+        &dyn base::WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>,
+    > {
+        self.content().into_iter().collect()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut dyn base::WidgetChildren<
+            UpdateAux = U,
+            GraphicalAux = G,
+            DisplayObject = DisplayCommand,
+        >,
+    > {
+        self.content_mut().into_iter().collect()
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> Widget for CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        base::invoke_update(self, aux);
+
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        self.update_progress(aux);
+        self.update_content_visibility();
+        self.fit_height();
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
+
+impl<U, G> StoresParentPosition for CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
+
+impl<U, G> draw::HasTheme for CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn theme(&mut self) -> &mut dyn draw::Themed {
+        &mut self.painter
+    }
+
+    fn resize_from_theme(&mut self) {
+        let header = self.painter.size_hint(self.derive_state());
+        self.header_height = header.height;
+        if let Some(ref mut content) = self.content {
+            content.set_ctxt_position(RelativePoint::new(0.0, header.height).into());
+        }
+        self.set_size(Size::new(self.rect.size.width.max(header.width), self.rect.size.height));
+        self.fit_height();
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<Collapsible> for CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<Collapsible> {
+        &mut self.data
+    }
+}
+
+impl<U, G> ui::DefaultEventQueue<CollapsibleEvent> for CollapsibleWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_event_queue(&self) -> &RcEventQueue<CollapsibleEvent> {
+        &self.event_queue
+    }
+}
+
+impl<U: base::UpdateAuxiliary, G: base::GraphicalAuxiliary> Drop for CollapsibleWidget<U, G> {
+    fn drop(&mut self) {
+        self.drop_event.emit_owned(base::DropEvent);
+    }
+}
@@ -0,0 +1,689 @@
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, CommandGroup, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::{RcEventListener, RcEventQueue},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::marker::PhantomData,
+};
+
+/// Side length of the saturation/value square.
+const SQUARE_SIZE: f32 = 160.0;
+/// Width of the hue and alpha strips.
+const STRIP_WIDTH: f32 = 16.0;
+/// Space between the parts of the picker.
+const SPACING: f32 = 8.0;
+/// Width reserved for the label of a text input.
+const LABEL_WIDTH: f32 = 28.0;
+/// Width of a text input.
+const FIELD_WIDTH: f32 = 72.0;
+/// Height of a text input row.
+const FIELD_HEIGHT: f32 = 22.0;
+/// Side length of a swatch.
+const SWATCH_SIZE: f32 = 20.0;
+
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum ColorPickerEvent {
+    /// The user changed the color.
+    #[event_key(changed)]
+    Changed(Color),
+}
+
+/// Text inputs of a color picker, in display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Hex,
+    Red,
+    Green,
+    Blue,
+    Hue,
+    Saturation,
+    Value,
+}
+
+const FIELDS: [Field; 7] = [
+    Field::Hex,
+    Field::Red,
+    Field::Green,
+    Field::Blue,
+    Field::Hue,
+    Field::Saturation,
+    Field::Value,
+];
+
+impl Field {
+    fn label(self) -> &'static str {
+        match self {
+            Field::Hex => "Hex",
+            Field::Red => "R",
+            Field::Green => "G",
+            Field::Blue => "B",
+            Field::Hue => "H",
+            Field::Saturation => "S",
+            Field::Value => "V",
+        }
+    }
+
+    /// Formats the relevant component of a color.
+    fn format(self, (h, s, v, a): (f32, f32, f32, f32)) -> String {
+        let color = draw::hsva(h, s, v, a);
+        let byte = |x: f32| (x * 255.0).round() as u8;
+        match self {
+            Field::Hex if a < 1.0 => format!(
+                "#{:02X}{:02X}{:02X}{:02X}",
+                byte(color.red),
+                byte(color.green),
+                byte(color.blue),
+                byte(a)
+            ),
+            Field::Hex => {
+                format!("#{:02X}{:02X}{:02X}", byte(color.red), byte(color.green), byte(color.blue))
+            }
+            Field::Red => byte(color.red).to_string(),
+            Field::Green => byte(color.green).to_string(),
+            Field::Blue => byte(color.blue).to_string(),
+            Field::Hue => (h.round() as u32 % 360).to_string(),
+            Field::Saturation => ((s * 100.0).round() as u32).to_string(),
+            Field::Value => ((v * 100.0).round() as u32).to_string(),
+        }
+    }
+
+    /// Applies text typed into this field to a color, returning `None` if the text is invalid.
+    fn parse(self, text: &str, (h, s, v, a): (f32, f32, f32, f32)) -> Option<(f32, f32, f32, f32)> {
+        let color = draw::hsva(h, s, v, a);
+        let component = |max: u32| {
+            text.trim().parse::<u32>().ok().filter(|&x| x <= max).map(|x| x as f32 / max as f32)
+        };
+        let rgb = |r, g, b| {
+            let (new_h, new_s, new_v, _) = draw::to_hsva(Color::new(r, g, b, a));
+            // Preserve the hue (and saturation) when they're undefined for the new color.
+            let new_h = if new_s == 0.0 { h } else { new_h };
+            let new_s = if new_v == 0.0 { s } else { new_s };
+            (new_h, new_s, new_v, a)
+        };
+
+        match self {
+            Field::Hex => {
+                let (color, alpha) = parse_hex(text)?;
+                let (h, s, v, _) = rgb(color.red, color.green, color.blue);
+                Some((h, s, v, alpha))
+            }
+            Field::Red => Some(rgb(component(255)?, color.green, color.blue)),
+            Field::Green => Some(rgb(color.red, component(255)?, color.blue)),
+            Field::Blue => Some(rgb(color.red, color.green, component(255)?)),
+            Field::Hue => Some((component(360)? * 360.0, s, v, a)),
+            Field::Saturation => Some((h, component(100)?, v, a)),
+            Field::Value => Some((h, s, component(100)?, a)),
+        }
+    }
+}
+
+/// Parses a `#RGB`, `#RRGGBB` or `#RRGGBBAA` color (the `#` is optional), returning the color and its alpha.
+fn parse_hex(text: &str) -> Option<(Color, f32)> {
+    let text = text.trim();
+    let text = if text.starts_with('#') { &text[1..] } else { text };
+    if !text.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let byte = |i: usize| u8::from_str_radix(&text[i..i + 2], 16).ok().map(|x| x as f32 / 255.0);
+    let nibble =
+        |i: usize| u8::from_str_radix(&text[i..i + 1], 16).ok().map(|x| (x * 17) as f32 / 255.0);
+
+    match text.len() {
+        3 => Some((Color::new(nibble(0)?, nibble(1)?, nibble(2)?, 1.0), 1.0)),
+        6 => Some((Color::new(byte(0)?, byte(2)?, byte(4)?, 1.0), 1.0)),
+        8 => Some((Color::new(byte(0)?, byte(2)?, byte(4)?, 1.0), byte(6)?)),
+        _ => None,
+    }
+}
+
+/// Part of the picker being dragged with the mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DragTarget {
+    Square,
+    Hue,
+    Alpha,
+}
+
+pub fn color_picker_handler<U, G>(
+) -> vg::UnboundQueueHandler<ColorPickerWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        ColorPickerWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled
+                    && *button == base::MouseButton::Left
+                    && (obj.drag_target_at(*pos).is_some() || obj.swatch_at(*pos).is_some())
+            }) {
+                if let Some(target) = obj.drag_target_at(*pos) {
+                    obj.drag = Some(target);
+                    obj.drag_to(*pos);
+                } else if let Some(swatch) = obj.swatch_at(*pos) {
+                    let color = obj.data.history[swatch];
+                    obj.set_hsva(draw::to_hsva(color), None);
+                }
+            }
+        }
+
+        mouse_move => {
+            if let Some((pos, _)) = event.with(|_| obj.drag.is_some()) {
+                obj.drag_to(*pos);
+            }
+        }
+
+        mouse_release => {
+            if event.with(|(_, button, _)| *button == base::MouseButton::Left && obj.drag.is_some()).is_some() {
+                obj.drag = None;
+                obj.push_history();
+            }
+        }
+    }
+}
+
+/// Color picker with a saturation/value square, hue and alpha strips, hex/RGB/HSV text inputs and a history of recently picked colors.
+#[derive(
+    LayableWidget, DropNotifier, HasVisibility, Repaintable, Movable, Resizable, OperatesVerbGraph,
+)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+pub struct ColorPickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub event_queue: RcEventQueue<ColorPickerEvent>,
+    pub data: base::Observed<ColorPicker>,
+
+    /// The picked color as hue, saturation, value and alpha.
+    /// This is kept separately from `data.color` so that hue and saturation survive passing through grays.
+    hsva: (f32, f32, f32, f32),
+    fields: Vec<ui::TextAreaWidget<U, G>>,
+    field_listeners: Vec<RcEventListener<ui::TextAreaEvent>>,
+    drag: Option<DragTarget>,
+    graph: vg::OptionVerbGraph<Self, U>,
+    painter: Box<dyn draw::Painter<state::ColorPickerState>>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_visibility]
+    visibility: base::Visibility,
+    #[repaint_target]
+    command_group: CommandGroup,
+    #[widget_drop_event]
+    drop_event: RcEventQueue<base::DropEvent>,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorPicker {
+    pub color: Color,
+    /// Recently picked colors, most recent first.
+    pub history: Vec<Color>,
+    /// Maximum amount of colors kept in `history`.
+    pub history_size: usize,
+    /// Typeface used for the text inputs and their labels.
+    pub typeface: draw::TypefaceStyle,
+    /// Color of label text.
+    pub text_color: Color,
+    /// Background color of the text inputs.
+    pub field_background: Color,
+    /// Color of borders and markers.
+    pub border: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for ColorPicker
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = ColorPickerWidget<U, G>;
+}
+
+impl ColorPicker {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        ColorPicker {
+            color: data.scheme.primary,
+            history: Vec::new(),
+            history_size: 8,
+            typeface: draw::TypefaceStyle { size: 12.0, ..data.typography.body.clone() },
+            text_color: data.scheme.over_control_outset,
+            field_background: data.scheme.control_inset,
+            border: draw::weaken(data.scheme.over_control_outset, 0.4, data.contrast),
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        theme: &dyn draw::Theme,
+        u_aux: &mut U,
+        g_aux: &mut G,
+    ) -> ColorPickerWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+        let hsva = draw::to_hsva(data.color);
+
+        let mut graph = vg::verbgraph! {
+            ColorPickerWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    let (h, s, v, a) = obj.hsva;
+                    if obj.data.color != draw::hsva(h, s, v, a) {
+                        obj.hsva = draw::to_hsva(obj.data.color);
+                        obj.update_fields(None);
+                    }
+                    obj.sync_fields();
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph =
+            graph.add("color_picker", color_picker_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let fields: Vec<_> = FIELDS
+            .iter()
+            .map(|field| {
+                ui::TextArea {
+                    text: field.format(hsva),
+                    typeface: data.typeface.clone(),
+                    disabled: data.disabled,
+                    ..ui::TextArea::from_theme(theme)
+                }
+                .construct(theme, u_aux, g_aux)
+            })
+            .collect();
+        let field_listeners = fields.iter().map(|field| field.event_queue.listen()).collect();
+
+        let mut color_picker = ColorPickerWidget {
+            event_queue: Default::default(),
+            data,
+
+            hsva,
+            fields,
+            field_listeners,
+            drag: None,
+            graph: graph.into(),
+            painter: theme.color_picker(),
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            visibility: Default::default(),
+            command_group: Default::default(),
+            drop_event: Default::default(),
+            layout: Default::default(),
+
+            phantom_g: Default::default(),
+        };
+
+        color_picker.layout_fields();
+        draw::HasTheme::resize_from_theme(&mut color_picker);
+
+        color_picker
+    }
+}
+
+impl<U, G> ColorPickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout_fields();
+        base::LayableWidget::notify_layout(self);
+    }
+
+    /// Converts a rectangle relative to the top-left of the picker to absolute coordinates.
+    fn local_rect(&self, x: f32, y: f32, width: f32, height: f32) -> AbsoluteRect {
+        let origin = self.abs_rect().origin;
+        AbsoluteRect::new(
+            AbsolutePoint::new(origin.x + x, origin.y + y),
+            Size::new(width, height).cast_unit(),
+        )
+    }
+
+    fn square_rect(&self) -> AbsoluteRect {
+        self.local_rect(0.0, 0.0, SQUARE_SIZE, SQUARE_SIZE)
+    }
+
+    fn hue_rect(&self) -> AbsoluteRect {
+        self.local_rect(SQUARE_SIZE + SPACING, 0.0, STRIP_WIDTH, SQUARE_SIZE)
+    }
+
+    fn alpha_rect(&self) -> AbsoluteRect {
+        self.local_rect(SQUARE_SIZE + STRIP_WIDTH + SPACING * 2.0, 0.0, STRIP_WIDTH, SQUARE_SIZE)
+    }
+
+    /// Returns the rectangle of the swatch showing the current color.
+    fn preview_rect(&self) -> AbsoluteRect {
+        self.local_rect(0.0, SQUARE_SIZE + SPACING, SWATCH_SIZE * 2.0, SWATCH_SIZE)
+    }
+
+    fn swatch_rects(&self) -> Vec<AbsoluteRect> {
+        (0..self.data.history.len().min(self.data.history_size))
+            .map(|i| {
+                self.local_rect(
+                    SWATCH_SIZE * 2.0 + SPACING + i as f32 * (SWATCH_SIZE + SPACING / 2.0),
+                    SQUARE_SIZE + SPACING,
+                    SWATCH_SIZE,
+                    SWATCH_SIZE,
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the rectangle of the text input row at `index` (excluding the label).
+    fn field_rect(&self, index: usize) -> AbsoluteRect {
+        self.local_rect(
+            SQUARE_SIZE + STRIP_WIDTH * 2.0 + SPACING * 3.0 + LABEL_WIDTH,
+            index as f32 * FIELD_HEIGHT,
+            FIELD_WIDTH,
+            FIELD_HEIGHT - 2.0,
+        )
+    }
+
+    /// Positions the text inputs within their rows.
+    fn layout_fields(&mut self) {
+        let origin = self.abs_position();
+        let rects: Vec<_> = (0..self.fields.len()).map(|i| self.field_rect(i)).collect();
+        for (field, rect) in self.fields.iter_mut().zip(rects) {
+            let height = field.size().height;
+            field.set_parent_position(origin);
+            field.set_ctxt_rect(AbsoluteRect::new(
+                AbsolutePoint::new(
+                    rect.origin.x + 4.0,
+                    rect.origin.y + ((rect.size.height - height) / 2.0).round(),
+                ),
+                Size::new(rect.size.width - 8.0, height).cast_unit(),
+            ));
+        }
+    }
+
+    fn drag_target_at(&self, pos: AbsolutePoint) -> Option<DragTarget> {
+        if self.square_rect().contains(pos) {
+            Some(DragTarget::Square)
+        } else if self.hue_rect().contains(pos) {
+            Some(DragTarget::Hue)
+        } else if self.alpha_rect().contains(pos) {
+            Some(DragTarget::Alpha)
+        } else {
+            None
+        }
+    }
+
+    fn swatch_at(&self, pos: AbsolutePoint) -> Option<usize> {
+        self.swatch_rects().iter().position(|rect| rect.contains(pos))
+    }
+
+    /// Updates the color from the cursor position within the dragged part.
+    fn drag_to(&mut self, pos: AbsolutePoint) {
+        let fraction = |rect: AbsoluteRect| {
+            (
+                ((pos.x - rect.origin.x) / rect.size.width).max(0.0).min(1.0),
+                ((pos.y - rect.origin.y) / rect.size.height).max(0.0).min(1.0),
+            )
+        };
+
+        let (h, s, v, a) = self.hsva;
+        let hsva = match self.drag {
+            Some(DragTarget::Square) => {
+                let (x, y) = fraction(self.square_rect());
+                (h, x, 1.0 - y, a)
+            }
+            Some(DragTarget::Hue) => (fraction(self.hue_rect()).1 * 360.0, s, v, a),
+            Some(DragTarget::Alpha) => (h, s, v, 1.0 - fraction(self.alpha_rect()).1),
+            None => return,
+        };
+
+        self.set_hsva(hsva, None);
+    }
+
+    /// Changes the color on behalf of the user, updating every text input except `editing`.
+    fn set_hsva(&mut self, hsva: (f32, f32, f32, f32), editing: Option<usize>) {
+        if hsva == self.hsva {
+            return;
+        }
+
+        self.hsva = hsva;
+        let (h, s, v, a) = hsva;
+        let color = draw::hsva(h, s, v, a);
+        self.data.color = color;
+        self.update_fields(editing);
+        self.event_queue.emit_owned(ColorPickerEvent::Changed(color));
+    }
+
+    /// Re-formats the text of every text input except `except`.
+    fn update_fields(&mut self, except: Option<usize>) {
+        let hsva = self.hsva;
+        for (i, field) in self.fields.iter_mut().enumerate() {
+            if Some(i) != except {
+                let text = FIELDS[i].format(hsva);
+                if field.data.text != text {
+                    field.data.cursor = field.data.cursor.min(text.len());
+                    field.data.text = text;
+                }
+            }
+        }
+    }
+
+    /// Copies the relevant properties of the color picker to the text inputs.
+    fn sync_fields(&mut self) {
+        let data = &self.data;
+        for field in &mut self.fields {
+            if field.data.typeface != data.typeface {
+                field.data.typeface = data.typeface.clone();
+            }
+            if field.data.disabled != data.disabled {
+                field.data.disabled = data.disabled;
+            }
+        }
+    }
+
+    /// Records the current color at the front of the history.
+    fn push_history(&mut self) {
+        let color = self.data.color;
+        if self.data.history.first() == Some(&color) {
+            return;
+        }
+
+        let history_size = self.data.history_size;
+        let history = &mut self.data.history;
+        history.retain(|&previous| previous != color);
+        history.insert(0, color);
+        history.truncate(history_size);
+    }
+
+    fn derive_state(&self) -> state::ColorPickerState {
+        state::ColorPickerState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            hsva: self.hsva,
+            square: self.square_rect(),
+            hue_strip: self.hue_rect(),
+            alpha_strip: self.alpha_rect(),
+            preview: self.preview_rect(),
+            swatches: self.swatch_rects(),
+            fields: FIELDS
+                .iter()
+                .enumerate()
+                .map(|(i, field)| (field.label().to_string(), self.field_rect(i)))
+                .collect(),
+        }
+    }
+}
+
+impl<U, G> base::WidgetChildren for ColorPickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn children(
+        &self,
+    ) -> Vec<
+        &dyn base::WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>,
+    > {
+        self.fields.iter().map(|field| field as _).collect()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut dyn base::WidgetChildren<
+            UpdateAux = U,
+            GraphicalAux = G,
+            DisplayObject = DisplayCommand,
+        >,
+    > {
+        self.fields.iter_mut().map(|field| field as _).collect()
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for ColorPickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> Widget for ColorPickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        base::invoke_update(self, aux);
+
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        for i in 0..self.fields.len() {
+            for event in self.field_listeners[i].peek() {
+                match event {
+                    ui::TextAreaEvent::UserModify(text) => {
+                        if self.data.disabled {
+                            continue;
+                        }
+                        if let Some(hsva) = FIELDS[i].parse(&text, self.hsva) {
+                            self.set_hsva(hsva, Some(i));
+                        }
+                    }
+                    ui::TextAreaEvent::Blur => {
+                        // Discard invalid input and normalize formatting.
+                        self.update_fields(None);
+                        self.push_history();
+                    }
+                    ui::TextAreaEvent::Focus => {}
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
+
+impl<U, G> StoresParentPosition for ColorPickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
+
+impl<U, G> draw::HasTheme for ColorPickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn theme(&mut self) -> &mut dyn draw::Themed {
+        &mut self.painter
+    }
+
+    fn resize_from_theme(&mut self) {
+        self.set_size(self.painter.size_hint(self.derive_state()));
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<ColorPicker> for ColorPickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<ColorPicker> {
+        &mut self.data
+    }
+}
+
+impl<U, G> ui::DefaultEventQueue<ColorPickerEvent> for ColorPickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_event_queue(&self) -> &RcEventQueue<ColorPickerEvent> {
+        &self.event_queue
+    }
+}
+
+impl<U: base::UpdateAuxiliary, G: base::GraphicalAuxiliary> Drop for ColorPickerWidget<U, G> {
+    fn drop(&mut self) {
+        self.drop_event.emit_owned(base::DropEvent);
+    }
+}
@@ -0,0 +1,925 @@
+//! Date picker widget, along with the calendar date math it relies on.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state, HasTheme},
+        geom::*,
+        ui::{self, picker},
+    },
+    reclutch::{
+        display::{CommandGroup, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::RcEventQueue,
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{
+        fmt,
+        marker::PhantomData,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// Width of a day cell in the calendar popup.
+const CELL_WIDTH: f32 = 32.0;
+/// Height of a day cell in the calendar popup.
+const CELL_HEIGHT: f32 = 26.0;
+/// Height of the row containing the month title and navigation buttons.
+const HEADER_HEIGHT: f32 = 30.0;
+/// Height of the row of weekday names.
+const WEEKDAY_HEIGHT: f32 = 20.0;
+/// Width of a navigation button.
+const NAV_WIDTH: f32 = 24.0;
+/// Padding around the contents of the calendar popup.
+const PADDING: f32 = 6.0;
+
+/// A day of the week.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    /// Returns the weekday `index` days after Monday (wrapping around every week).
+    pub fn from_index(index: usize) -> Self {
+        match index % 7 {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+
+    /// Returns the amount of days since Monday.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Returns `true` if `year` is a leap year in the proleptic Gregorian calendar.
+pub fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Returns the amount of days in a month (`month` is between 1 and 12).
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Offsets a year and month (between 1 and 12) by `offset` months.
+fn offset_month(year: i32, month: u32, offset: i32) -> (i32, u32) {
+    let total = year as i64 * 12 + month as i64 - 1 + offset as i64;
+    (total.div_euclid(12) as i32, total.rem_euclid(12) as u32 + 1)
+}
+
+/// A date in the proleptic Gregorian calendar.
+///
+/// Dates are ordered chronologically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    /// Creates a date, returning `None` if the month or day is out of range.
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        if (1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month) {
+            Some(Date { year, month, day })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the current date in UTC.
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or(0);
+        Date::from_days_since_epoch(seconds.div_euclid(86_400))
+    }
+
+    #[inline]
+    pub fn year(self) -> i32 {
+        self.year
+    }
+
+    /// Returns the month, between 1 and 12.
+    #[inline]
+    pub fn month(self) -> u32 {
+        self.month
+    }
+
+    /// Returns the day of the month, starting at 1.
+    #[inline]
+    pub fn day(self) -> u32 {
+        self.day
+    }
+
+    /// Returns the amount of days since 1970-01-01 (negative for earlier dates).
+    pub fn days_since_epoch(self) -> i64 {
+        let (year, month, day) = (self.year as i64, self.month as i64, self.day as i64);
+        // Years are counted from March so that the leap day is at the end of the year.
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// Creates a date from the amount of days since 1970-01-01.
+    pub fn from_days_since_epoch(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Date { year: year as i32, month: month as u32, day: day as u32 }
+    }
+
+    pub fn weekday(self) -> Weekday {
+        // 1970-01-01 was a Thursday.
+        Weekday::from_index((self.days_since_epoch() + 3).rem_euclid(7) as usize)
+    }
+
+    /// Returns the date `days` days later (or earlier, if negative).
+    pub fn add_days(self, days: i64) -> Self {
+        Date::from_days_since_epoch(self.days_since_epoch() + days)
+    }
+
+    /// Returns the date `months` months later (or earlier, if negative).
+    ///
+    /// The day is clamped to the length of the resulting month.
+    pub fn add_months(self, months: i32) -> Self {
+        let (year, month) = offset_month(self.year, self.month, months);
+        Date { year, month, day: self.day.min(days_in_month(year, month)) }
+    }
+
+    /// Returns the first day of the month.
+    pub fn first_of_month(self) -> Self {
+        Date { day: 1, ..self }
+    }
+
+    /// Returns the last day of the month.
+    pub fn last_of_month(self) -> Self {
+        Date { day: days_in_month(self.year, self.month), ..self }
+    }
+
+    /// Returns the amount of days since the start of the week, where weeks start on `first_day`.
+    pub fn days_into_week(self, first_day: Weekday) -> usize {
+        (self.weekday().index() + 7 - first_day.index()) % 7
+    }
+}
+
+/// Formats the date as `YYYY-MM-DD` (ISO 8601).
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Month and weekday names displayed by a date picker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateNames {
+    /// Month names, starting with January.
+    pub months: [String; 12],
+    /// Abbreviated weekday names, starting with Monday.
+    pub weekdays: [String; 7],
+}
+
+impl Default for DateNames {
+    fn default() -> Self {
+        let string = |s: &str| s.to_string();
+        DateNames {
+            months: [
+                string("January"),
+                string("February"),
+                string("March"),
+                string("April"),
+                string("May"),
+                string("June"),
+                string("July"),
+                string("August"),
+                string("September"),
+                string("October"),
+                string("November"),
+                string("December"),
+            ],
+            weekdays: [
+                string("Mo"),
+                string("Tu"),
+                string("We"),
+                string("Th"),
+                string("Fr"),
+                string("Sa"),
+                string("Su"),
+            ],
+        }
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum DatePickerEvent {
+    /// The calendar popup was opened.
+    #[event_key(open)]
+    Open,
+    /// The calendar popup was closed.
+    #[event_key(close)]
+    Close,
+    /// The user picked a date.
+    #[event_key(changed)]
+    Changed(Date),
+}
+
+/// Part of the calendar popup under the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CalendarHit {
+    /// Navigation button, indexed in the order previous year, previous month, next month, next year.
+    Navigation(usize),
+    Day(Date),
+}
+
+/// Month offsets of the navigation buttons, in display order.
+const NAVIGATION_OFFSETS: [i32; 4] = [-12, -1, 1, 12];
+
+pub fn date_picker_handler<U, G>(
+) -> vg::UnboundQueueHandler<DatePickerWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        DatePickerWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let pos = event.get().0;
+            let in_field = obj.abs_convert_rect(ui::InteractiveWidget::mouse_bounds(obj)).contains(pos);
+            if obj.open && !in_field {
+                if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                    *button == base::MouseButton::Left && obj.popup_rect().contains(*pos)
+                }) {
+                    // Clicking the popup shouldn't take keyboard focus away from the picker.
+                    obj.interaction.insert(state::InteractionState::FOCUSED);
+                    if let Some(hit) = obj.hit_at(*pos) {
+                        obj.activate(hit);
+                    }
+                } else {
+                    // Clicking anywhere else dismisses the popup.
+                    obj.close_popup();
+                }
+            }
+        }
+
+        mouse_move => {
+            if obj.open {
+                let pos = event.get().0;
+                let hovered = obj.hit_at(pos);
+                if hovered != obj.hovered {
+                    obj.hovered = hovered;
+                    obj.repaint();
+                }
+                event.with(|(pos, _)| obj.popup_rect().contains(*pos));
+            }
+        }
+
+        mouse_scroll => {
+            if let Some((_, delta, _)) = event.with(|(pos, _, _)| obj.open && obj.popup_rect().contains(*pos)) {
+                let y = delta.to_pixels(1.0).y;
+                if y > 0.0 {
+                    obj.show_month(-1);
+                } else if y < 0.0 {
+                    obj.show_month(1);
+                }
+            }
+        }
+
+        key_press => {
+            if obj.interaction.contains(state::InteractionState::FOCUSED) && !obj.data.disabled {
+                event.with(|&(key, modifiers)| obj.handle_key(key, modifiers));
+            }
+        }
+    }
+}
+
+/// Bundles the painters for the field and the calendar popup so that both follow theme changes.
+struct DatePickerPainters {
+    field: Box<dyn draw::Painter<state::PickerFieldState>>,
+    calendar: Box<dyn draw::Painter<state::CalendarState>>,
+}
+
+impl draw::Themed for DatePickerPainters {
+    fn load_theme(&mut self, theme: &dyn draw::Theme, aux: &dyn base::GraphicalAuxiliary) {
+        self.field.load_theme(theme, aux);
+        self.calendar.load_theme(theme, aux);
+    }
+}
+
+/// Compact field showing a date, which pops up a calendar month grid to pick a different date.
+///
+/// While focused, the popup opens with `Down`, `Space` or `Return`.
+/// Within the popup, the arrow keys move between days, `PageUp`/`PageDown` move between months (or years, with `Shift`),
+/// `Home`/`End` move to the start/end of the week, `Return`/`Space` pick the highlighted day and `Escape` closes the popup.
+#[derive(
+    WidgetChildren,
+    LayableWidget,
+    DropNotifier,
+    HasVisibility,
+    Repaintable,
+    Movable,
+    Resizable,
+    OperatesVerbGraph,
+)]
+#[widget_children_trait(base::WidgetChildren)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+pub struct DatePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub event_queue: RcEventQueue<DatePickerEvent>,
+    pub data: base::Observed<DatePicker>,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    painters: DatePickerPainters,
+    interaction: state::InteractionState,
+    open: bool,
+    /// Year and month shown by the calendar popup.
+    view: (i32, u32),
+    /// Day highlighted by keyboard navigation.
+    cursor: Date,
+    hovered: Option<CalendarHit>,
+    today: Date,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_visibility]
+    visibility: base::Visibility,
+    #[repaint_target]
+    command_group: CommandGroup,
+    #[repaint_target]
+    popup_command_group: CommandGroup,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+    #[widget_drop_event]
+    drop_event: RcEventQueue<base::DropEvent>,
+
+    phantom_g: PhantomData<G>,
+}
+
+impl<U, G> ui::InteractiveWidget for DatePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline(always)]
+    fn interaction(&mut self) -> &mut state::InteractionState {
+        &mut self.interaction
+    }
+
+    #[inline]
+    fn mouse_bounds(&self) -> RelativeRect {
+        self.painters.field.mouse_hint(self.rect)
+    }
+
+    #[inline]
+    fn disabled(&self) -> bool {
+        self.data.disabled
+    }
+
+    fn on_interaction_event(&mut self, event: ui::InteractionEvent) {
+        self.repaint();
+        if let ui::InteractionEvent::Released(pos) = event {
+            if self.abs_convert_rect(self.mouse_bounds()).contains(pos) {
+                if self.open {
+                    self.close_popup();
+                } else {
+                    self.open_popup();
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatePicker {
+    pub date: Date,
+    /// Earliest date which can be picked.
+    pub min: Option<Date>,
+    /// Latest date which can be picked.
+    pub max: Option<Date>,
+    /// Specific dates which can't be picked.
+    pub disabled_dates: Vec<Date>,
+    /// Days of the week which can't be picked.
+    pub disabled_weekdays: Vec<Weekday>,
+    /// Day shown in the first column of the calendar.
+    pub first_day_of_week: Weekday,
+    pub names: DateNames,
+    pub style: ui::PickerStyle,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for DatePicker
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = DatePickerWidget<U, G>;
+}
+
+impl DatePicker {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        DatePicker {
+            date: Date::today(),
+            min: None,
+            max: None,
+            disabled_dates: Vec::new(),
+            disabled_weekdays: Vec::new(),
+            first_day_of_week: Weekday::Monday,
+            names: Default::default(),
+            style: ui::PickerStyle::from_theme(theme),
+            disabled: false,
+        }
+    }
+
+    /// Returns `true` if `date` is within `min`/`max` and isn't disabled.
+    pub fn is_selectable(&self, date: Date) -> bool {
+        self.min.map(|min| date >= min).unwrap_or(true)
+            && self.max.map(|max| date <= max).unwrap_or(true)
+            && !self.disabled_dates.contains(&date)
+            && !self.disabled_weekdays.contains(&date.weekday())
+    }
+
+    /// Clamps `date` within `min`/`max`.
+    pub fn clamp(&self, date: Date) -> Date {
+        let date = self.min.map(|min| date.max(min)).unwrap_or(date);
+        self.max.map(|max| date.min(max)).unwrap_or(date)
+    }
+
+    pub fn construct<U, G>(
+        self,
+        theme: &dyn draw::Theme,
+        u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> DatePickerWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            DatePickerWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.resize_from_theme();
+                    obj.repaint();
+                }
+            }
+        };
+
+        graph = graph.add(
+            "interaction",
+            ui::basic_interaction_handler::<DatePickerWidget<U, G>, U>().bind(u_aux.window_queue()),
+        );
+        graph = graph.add("date_picker", date_picker_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let cursor = data.date;
+
+        let mut date_picker = DatePickerWidget {
+            event_queue: Default::default(),
+            data,
+
+            graph: graph.into(),
+            painters: DatePickerPainters {
+                field: theme.picker_field(),
+                calendar: theme.calendar(),
+            },
+            interaction: state::InteractionState::empty(),
+            open: false,
+            view: (cursor.year(), cursor.month()),
+            cursor,
+            hovered: None,
+            today: Date::today(),
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            visibility: Default::default(),
+            command_group: Default::default(),
+            popup_command_group: Default::default(),
+            layout: Default::default(),
+            drop_event: Default::default(),
+
+            phantom_g: Default::default(),
+        };
+
+        date_picker.resize_from_theme();
+
+        date_picker
+    }
+}
+
+impl<U, G> DatePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn on_transform(&mut self) {
+        self.repaint();
+        base::LayableWidget::notify_layout(self);
+    }
+
+    /// Returns `true` if the calendar popup is open.
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn open_popup(&mut self) {
+        if self.open || self.data.disabled {
+            return;
+        }
+
+        self.open = true;
+        self.today = Date::today();
+        self.cursor = self.data.date;
+        self.view = (self.cursor.year(), self.cursor.month());
+        self.hovered = None;
+        self.repaint();
+        self.event_queue.emit_owned(DatePickerEvent::Open);
+    }
+
+    fn close_popup(&mut self) {
+        if self.open {
+            self.open = false;
+            self.hovered = None;
+            self.repaint();
+            self.event_queue.emit_owned(DatePickerEvent::Close);
+        }
+    }
+
+    /// Picks `date` (if it's selectable) and closes the popup.
+    fn select(&mut self, date: Date) {
+        if !self.data.is_selectable(date) {
+            return;
+        }
+
+        if self.data.date != date {
+            self.data.date = date;
+            self.event_queue.emit_owned(DatePickerEvent::Changed(date));
+        }
+        self.close_popup();
+    }
+
+    /// Returns `true` if any date in the given month is within `min`/`max`.
+    fn can_show(&self, (year, month): (i32, u32)) -> bool {
+        let first = Date { year, month, day: 1 };
+        self.data.min.map(|min| first.last_of_month() >= min).unwrap_or(true)
+            && self.data.max.map(|max| first <= max).unwrap_or(true)
+    }
+
+    /// Moves the calendar view by `offset` months, if the resulting month can be shown.
+    fn show_month(&mut self, offset: i32) {
+        let view = offset_month(self.view.0, self.view.1, offset);
+        if self.can_show(view) {
+            self.view = view;
+            self.repaint();
+        }
+    }
+
+    /// Moves the keyboard cursor to `date` (clamped within `min`/`max`), showing its month.
+    fn move_cursor(&mut self, date: Date) {
+        self.cursor = self.data.clamp(date);
+        self.view = (self.cursor.year(), self.cursor.month());
+        self.repaint();
+    }
+
+    fn activate(&mut self, hit: CalendarHit) {
+        match hit {
+            CalendarHit::Navigation(index) => self.show_month(NAVIGATION_OFFSETS[index]),
+            CalendarHit::Day(date) => self.select(date),
+        }
+    }
+
+    /// Handles a key press, returning `true` if the key was consumed.
+    fn handle_key(&mut self, key: base::KeyInput, modifiers: base::KeyModifiers) -> bool {
+        if !self.open {
+            return match key {
+                base::KeyInput::Down
+                | base::KeyInput::Space
+                | base::KeyInput::Return
+                | base::KeyInput::NumpadEnter => {
+                    self.open_popup();
+                    true
+                }
+                _ => false,
+            };
+        }
+
+        let cursor = self.cursor;
+        let first_day = self.data.first_day_of_week;
+        match key {
+            base::KeyInput::Left => self.move_cursor(cursor.add_days(-1)),
+            base::KeyInput::Right => self.move_cursor(cursor.add_days(1)),
+            base::KeyInput::Up => self.move_cursor(cursor.add_days(-7)),
+            base::KeyInput::Down => self.move_cursor(cursor.add_days(7)),
+            base::KeyInput::PageUp => {
+                self.move_cursor(cursor.add_months(if modifiers.shift { -12 } else { -1 }))
+            }
+            base::KeyInput::PageDown => {
+                self.move_cursor(cursor.add_months(if modifiers.shift { 12 } else { 1 }))
+            }
+            base::KeyInput::Home => {
+                self.move_cursor(cursor.add_days(-(cursor.days_into_week(first_day) as i64)))
+            }
+            base::KeyInput::End => {
+                self.move_cursor(cursor.add_days(6 - cursor.days_into_week(first_day) as i64))
+            }
+            base::KeyInput::Return | base::KeyInput::NumpadEnter | base::KeyInput::Space => {
+                self.select(cursor)
+            }
+            base::KeyInput::Escape => self.close_popup(),
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn popup_rect(&self) -> AbsoluteRect {
+        AbsoluteRect::new(
+            picker::popup_origin(self.abs_rect()),
+            Size::new(
+                CELL_WIDTH * 7.0 + PADDING * 2.0,
+                HEADER_HEIGHT + WEEKDAY_HEIGHT + CELL_HEIGHT * 6.0 + PADDING * 2.0,
+            )
+            .cast_unit(),
+        )
+    }
+
+    /// Returns the rectangle of each navigation button, in the same order as `NAVIGATION_OFFSETS`.
+    fn navigation_rects(&self) -> Vec<AbsoluteRect> {
+        let popup = self.popup_rect();
+        let y = popup.min_y() + PADDING;
+        let size = Size::new(NAV_WIDTH, HEADER_HEIGHT - PADDING).cast_unit();
+        let left = popup.min_x() + PADDING;
+        let right = popup.max_x() - PADDING - NAV_WIDTH * 2.0;
+        [left, left + NAV_WIDTH, right, right + NAV_WIDTH]
+            .iter()
+            .map(|&x| AbsoluteRect::new(AbsolutePoint::new(x, y), size))
+            .collect()
+    }
+
+    fn title_rect(&self) -> AbsoluteRect {
+        let popup = self.popup_rect();
+        AbsoluteRect::new(
+            AbsolutePoint::new(popup.min_x() + PADDING + NAV_WIDTH * 2.0, popup.min_y() + PADDING),
+            Size::new(CELL_WIDTH * 7.0 - NAV_WIDTH * 4.0, HEADER_HEIGHT - PADDING).cast_unit(),
+        )
+    }
+
+    /// Returns the rectangle of the cell at `column` within the row starting at `y`.
+    fn cell_rect(&self, column: usize, y: f32, height: f32) -> AbsoluteRect {
+        let popup = self.popup_rect();
+        AbsoluteRect::new(
+            AbsolutePoint::new(popup.min_x() + PADDING + column as f32 * CELL_WIDTH, y),
+            Size::new(CELL_WIDTH, height).cast_unit(),
+        )
+    }
+
+    /// Returns the date shown in the top-left cell of the calendar.
+    fn first_visible(&self) -> Date {
+        let first = Date { year: self.view.0, month: self.view.1, day: 1 };
+        first.add_days(-(first.days_into_week(self.data.first_day_of_week) as i64))
+    }
+
+    /// Returns each of the 42 visible days (six weeks) and its cell rectangle.
+    fn day_cells(&self) -> Vec<(Date, AbsoluteRect)> {
+        let first = self.first_visible();
+        let top = self.popup_rect().min_y() + PADDING + HEADER_HEIGHT + WEEKDAY_HEIGHT;
+        (0..42)
+            .map(|i| {
+                (
+                    first.add_days(i as i64),
+                    self.cell_rect(i % 7, top + (i / 7) as f32 * CELL_HEIGHT, CELL_HEIGHT),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the enabled part of the popup at `pos`.
+    fn hit_at(&self, pos: AbsolutePoint) -> Option<CalendarHit> {
+        if let Some(index) = self.navigation_rects().iter().position(|rect| rect.contains(pos)) {
+            let view = offset_month(self.view.0, self.view.1, NAVIGATION_OFFSETS[index]);
+            return if self.can_show(view) { Some(CalendarHit::Navigation(index)) } else { None };
+        }
+
+        self.day_cells()
+            .into_iter()
+            .find(|(_, rect)| rect.contains(pos))
+            .map(|(date, _)| date)
+            .filter(|&date| self.data.is_selectable(date))
+            .map(CalendarHit::Day)
+    }
+
+    fn derive_field_state(&self) -> state::PickerFieldState {
+        state::PickerFieldState {
+            rect: self.abs_rect(),
+            text: self.data.date.to_string(),
+            style: self.data.style.clone(),
+            open: self.open,
+            disabled: self.data.disabled,
+            interaction: self.interaction,
+        }
+    }
+
+    fn derive_calendar_state(&self) -> Option<state::CalendarState> {
+        if !self.open {
+            return None;
+        }
+
+        let weekday_top = self.popup_rect().min_y() + PADDING + HEADER_HEIGHT;
+        let first_day = self.data.first_day_of_week.index();
+
+        Some(state::CalendarState {
+            rect: self.popup_rect(),
+            style: self.data.style.clone(),
+            title: format!("{} {}", self.data.names.months[self.view.1 as usize - 1], self.view.0),
+            title_rect: self.title_rect(),
+            navigation: self
+                .navigation_rects()
+                .into_iter()
+                .zip(NAVIGATION_OFFSETS.iter())
+                .map(|(rect, &offset)| {
+                    (rect, self.can_show(offset_month(self.view.0, self.view.1, offset)))
+                })
+                .collect(),
+            hovered_navigation: match self.hovered {
+                Some(CalendarHit::Navigation(index)) => Some(index),
+                _ => None,
+            },
+            weekdays: (0..7)
+                .map(|column| {
+                    (
+                        self.data.names.weekdays[(first_day + column) % 7].clone(),
+                        self.cell_rect(column, weekday_top, WEEKDAY_HEIGHT),
+                    )
+                })
+                .collect(),
+            days: self
+                .day_cells()
+                .into_iter()
+                .map(|(date, rect)| state::CalendarDay {
+                    rect,
+                    text: date.day().to_string(),
+                    outside_month: (date.year(), date.month()) != self.view,
+                    selectable: self.data.is_selectable(date),
+                    selected: date == self.data.date,
+                    today: date == self.today,
+                    cursor: date == self.cursor,
+                    hovered: self.hovered == Some(CalendarHit::Day(date)),
+                })
+                .collect(),
+        })
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for DatePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> Widget for DatePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painters.field.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let field_state = self.derive_field_state();
+        let calendar_state = self.derive_calendar_state();
+
+        let painters = &mut self.painters;
+        self.command_group.push_with(
+            display,
+            || painters.field.draw(field_state),
+            Default::default(),
+            None,
+            None,
+        );
+        self.popup_command_group.push_with(
+            display,
+            || calendar_state.map(|state| painters.calendar.draw(state)).unwrap_or_default(),
+            ui::OVERLAY_Z_ORDER,
+            None,
+            None,
+        );
+    }
+}
+
+impl<U, G> StoresParentPosition for DatePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
+
+impl<U, G> HasTheme for DatePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn theme(&mut self) -> &mut dyn draw::Themed {
+        &mut self.painters
+    }
+
+    fn resize_from_theme(&mut self) {
+        self.set_size(self.painters.field.size_hint(self.derive_field_state()));
+    }
+}
+
+impl<U, G> ui::DefaultEventQueue<DatePickerEvent> for DatePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_event_queue(&self) -> &RcEventQueue<DatePickerEvent> {
+        &self.event_queue
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<DatePicker> for DatePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<DatePicker> {
+        &mut self.data
+    }
+}
+
+impl<U, G> Drop for DatePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn drop(&mut self) {
+        self.drop_event.emit_owned(base::DropEvent);
+    }
+}
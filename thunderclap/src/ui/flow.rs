@@ -0,0 +1,389 @@
+use {
+    super::{align_within, Align, Justify},
+    crate::{
+        base::{self, Layout, Resizable},
+        draw,
+        geom::*,
+        ui,
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{DisplayCommand, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener, RcEventQueue},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::marker::PhantomData,
+};
+
+#[derive(Debug)]
+struct ChildData {
+    evq: BidirSingleEventQueue<base::LayoutEvent, base::LayoutEvent>,
+    node: base::LayoutNode,
+    drop_listener: RcEventListener<base::DropEvent>,
+    rect: AbsoluteRect,
+    /// Size last measured by the child.
+    size: Size,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+/// A single row of children in a `Flow`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Line {
+    /// Index of the first child in the line.
+    start: usize,
+    /// Index after the last child in the line.
+    end: usize,
+    /// Width of the children in the line, including spacing.
+    width: f32,
+    /// Height of the tallest child in the line.
+    height: f32,
+}
+
+/// Breaks children of `sizes` into lines no wider than `width`.
+/// A child wider than `width` is given a line of its own.
+fn break_lines(sizes: &[Size], width: f32, spacing: f32) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+    for (i, size) in sizes.iter().enumerate() {
+        match lines.last_mut() {
+            Some(line) if line.width + spacing + size.width <= width => {
+                line.end = i + 1;
+                line.width += spacing + size.width;
+                line.height = line.height.max(size.height);
+            }
+            _ => lines.push(Line { start: i, end: i + 1, width: size.width, height: size.height }),
+        }
+    }
+    lines
+}
+
+/// Returns the size taken up by `lines`.
+fn lines_size(lines: &[Line], line_spacing: f32) -> Size {
+    Size::new(
+        lines.iter().map(|line| line.width).fold(0.0, f32::max),
+        lines.iter().map(|line| line.height).sum::<f32>()
+            + line_spacing * lines.len().saturating_sub(1) as f32,
+    )
+}
+
+lazy_widget! {
+    generic FlowWidget,
+    visibility: visibility,
+    theme: themed,
+    drop_event: drop_event
+}
+
+/// Abstract layout widget which arranges children left-to-right, wrapping onto a new line when a line is full.
+///
+/// The width of the flow is decided from the outside (such as by a parent layout); the height fits the lines.
+/// Children are reflowed whenever the width changes.
+#[derive(LayableWidget, Movable, Resizable, OperatesVerbGraph)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+#[widget_measure(measure_children)]
+pub struct FlowWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub data: base::Observed<Flow>,
+
+    rects: IndexMap<u64, ChildData>,
+    next_rect_id: u64,
+    visibility: base::Visibility,
+    themed: draw::PhantomThemed,
+    drop_event: RcEventQueue<base::DropEvent>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    phantom_u: PhantomData<U>,
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flow {
+    /// Horizontal space between children within a line.
+    pub spacing: f32,
+    /// Vertical space between lines.
+    pub line_spacing: f32,
+    /// How leftover horizontal space within each line is distributed.
+    pub justify: Justify,
+    /// How children are vertically aligned within their line.
+    pub alignment: Align,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Flow
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = FlowWidget<U, G>;
+}
+
+impl Flow {
+    pub fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        Flow { spacing: 0.0, line_spacing: 0.0, justify: Justify::Start, alignment: Align::Begin }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        _theme: &dyn draw::Theme,
+        _u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> FlowWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            FlowWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.layout.mark_dirty();
+                }
+            }
+        };
+
+        FlowWidget {
+            data,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            visibility: Default::default(),
+            themed: Default::default(),
+            drop_event: Default::default(),
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            layout: Default::default(),
+
+            graph: graph.into(),
+            phantom_u: Default::default(),
+            phantom_g: Default::default(),
+        }
+    }
+}
+
+impl<U, G> FlowWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn sizes(&self) -> Vec<Size> {
+        self.rects.values().map(|child| child.size).collect()
+    }
+
+    fn resize_to_fit(&mut self) {
+        let size = self.size();
+        let lines = break_lines(&self.sizes(), size.width, self.data.spacing);
+        self.set_size(Size::new(size.width, lines_size(&lines, self.data.line_spacing).height));
+    }
+
+    fn on_transform(&mut self) {
+        self.layout.mark_dirty();
+        base::LayableWidget::notify_layout(self);
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for FlowWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> base::Layout for FlowWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = ();
+
+    fn push(&mut self, _data: Option<()>, child: &mut impl base::LayableWidget) {
+        self.layout.mark_dirty();
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            layout_node: self.layout.node().clone(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                evq,
+                node: child.layout_events().node().clone(),
+                drop_listener: child.drop_event().listen(),
+                rect,
+                size: rect.size.cast_unit(),
+                original_rect: rect,
+                id,
+            },
+        );
+
+        self.resize_to_fit();
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.layout.mark_dirty();
+            child.listen_to_layout(None);
+            if restore_original {
+                child.arrange(data.original_rect);
+            }
+        }
+    }
+
+    /// Wraps the children within the maximum width of `constraints` (or lays them out in a single line if unbounded).
+    fn measure_children(&self, constraints: base::Constraints) -> Size {
+        let lines = break_lines(&self.sizes(), constraints.max.width, self.data.spacing);
+        constraints.constrain(lines_size(&lines, self.data.line_spacing))
+    }
+}
+
+impl<U, G> Widget for FlowWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+    }
+}
+
+impl<U, G> base::WidgetChildren for FlowWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    fn relayout(&mut self) {
+        if self.layout.direction_changed() {
+            self.layout.mark_dirty();
+        }
+
+        {
+            let mut removals = Vec::new();
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    self.layout.mark_dirty();
+                    continue;
+                }
+
+                if let Some(base::LayoutEvent::Measure { rect, size }) = data.evq.retrieve_newest()
+                {
+                    if rect != data.rect || size != data.size {
+                        self.layout.mark_dirty();
+                        data.rect = rect;
+                        data.size = size;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.remove(&removal);
+            }
+        }
+
+        if self.layout.is_dirty() {
+            self.layout.invalidate();
+            self.resize_to_fit();
+            let abs_rect = self.abs_rect();
+            let direction = self.layout.direction();
+            let (spacing, line_spacing) = (self.data.spacing, self.data.line_spacing);
+            let (justify, alignment) = (self.data.justify, self.data.alignment);
+            let lines = break_lines(&self.sizes(), abs_rect.size.width, spacing);
+            let constraints = base::Constraints::loose(Size::new(
+                abs_rect.size.width,
+                self.layout.constraints().max.height,
+            ));
+
+            let mut children = self.rects.values_mut();
+            let mut y = abs_rect.origin.y;
+            for line in lines {
+                let (offset, extra) =
+                    justify.distribute(abs_rect.size.width - line.width, line.end - line.start);
+                let mut x = abs_rect.origin.x + offset;
+                for data in children.by_ref().take(line.end - line.start) {
+                    let (child_y, height) =
+                        align_within(alignment, y, line.height, data.size.height);
+                    let rect = AbsoluteRect::new(
+                        AbsolutePoint::new(x, child_y),
+                        Size::new(data.size.width, height).cast_unit(),
+                    );
+
+                    let rect = ui::mirror(rect, abs_rect, direction);
+                    data.evq.emit_owned(base::LayoutEvent::Arrange {
+                        constraints,
+                        rect,
+                        direction,
+                    });
+                    data.node.invalidate();
+                    data.rect = rect;
+
+                    x += data.size.width + spacing + extra;
+                }
+                y += line.height + line_spacing;
+            }
+
+            self.layout.clear_dirty();
+            if self.layout.measured().is_none() {
+                base::LayableWidget::notify_layout(self);
+            }
+        }
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<Flow> for FlowWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<Flow> {
+        &mut self.data
+    }
+}
+
+impl<U, G> StoresParentPosition for FlowWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
@@ -0,0 +1,633 @@
+use {
+    super::{align_within, Align},
+    crate::{
+        base::{self, Layout, Resizable},
+        draw,
+        geom::*,
+        ui,
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{DisplayCommand, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener, RcEventQueue},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::marker::PhantomData,
+};
+
+/// How the size of a row or column of a `Grid` is decided.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridTrack {
+    /// The track is always the given size.
+    Fixed(f32),
+    /// The track fits the largest child within it.
+    Auto,
+    /// The track takes a share (in `fr` units) of the space left over by the other tracks, but never less than its children need.
+    Fraction(f32),
+}
+
+impl Default for GridTrack {
+    fn default() -> Self {
+        GridTrack::Auto
+    }
+}
+
+/// Information about how a `Grid` child should be layed out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridItem {
+    /// Index of the first row occupied by the child.
+    pub row: usize,
+    /// Index of the first column occupied by the child.
+    pub column: usize,
+    /// Amount of rows occupied by the child.
+    pub row_span: usize,
+    /// Amount of columns occupied by the child.
+    pub column_span: usize,
+    /// How the child should be horizontally aligned within its cell.
+    pub horizontal_alignment: Align,
+    /// How the child should be vertically aligned within its cell.
+    pub vertical_alignment: Align,
+}
+
+impl Default for GridItem {
+    fn default() -> Self {
+        GridItem {
+            row: 0,
+            column: 0,
+            row_span: 1,
+            column_span: 1,
+            horizontal_alignment: Align::Begin,
+            vertical_alignment: Align::Begin,
+        }
+    }
+}
+
+impl GridItem {
+    /// Creates an item occupying the single cell at `row` and `column`.
+    pub fn new(row: usize, column: usize) -> GridItem {
+        GridItem { row, column, ..Default::default() }
+    }
+
+    /// Sets the `row_span` value.
+    pub fn row_span(self, row_span: usize) -> GridItem {
+        GridItem { row_span, ..self }
+    }
+
+    /// Sets the `column_span` value.
+    pub fn column_span(self, column_span: usize) -> GridItem {
+        GridItem { column_span, ..self }
+    }
+
+    /// Sets the `horizontal_alignment` value.
+    pub fn horizontal_align(self, horizontal_alignment: Align) -> GridItem {
+        GridItem { horizontal_alignment, ..self }
+    }
+
+    /// Sets the `vertical_alignment` value.
+    pub fn vertical_align(self, vertical_alignment: Align) -> GridItem {
+        GridItem { vertical_alignment, ..self }
+    }
+}
+
+#[derive(Debug)]
+struct ChildData {
+    data: GridItem,
+    evq: BidirSingleEventQueue<base::LayoutEvent, base::LayoutEvent>,
+    node: base::LayoutNode,
+    drop_listener: RcEventListener<base::DropEvent>,
+    rect: AbsoluteRect,
+    /// Size last measured by the child.
+    size: Size,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+lazy_widget! {
+    generic GridWidget,
+    visibility: visibility,
+    theme: themed,
+    drop_event: drop_event
+}
+
+/// Abstract layout widget which arranges children in cells of rows and columns (see `GridItem`).
+///
+/// Children may span several rows or columns, and are aligned within their cells on both axes.
+/// Rows and columns beyond those defined in `rows` and `columns` are `GridTrack::Auto`.
+/// Until it's given a different size (by a parent layout or `set_size`), the grid sizes itself to fit its children.
+/// Otherwise any space beyond what the children need is shared by `GridTrack::Fraction` tracks.
+#[derive(LayableWidget, Movable, Resizable, OperatesVerbGraph)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+#[widget_measure(measure_children)]
+pub struct GridWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub data: base::Observed<Grid>,
+
+    rects: IndexMap<u64, ChildData>,
+    next_rect_id: u64,
+    /// Cell given to the next child pushed without a `GridItem`.
+    next_cell: (usize, usize),
+    /// Whether the grid sizes itself to fit its children.
+    fit: bool,
+    /// Size last given to the grid by `resize_to_fit`.
+    fitted_size: Size,
+    visibility: base::Visibility,
+    themed: draw::PhantomThemed,
+    drop_event: RcEventQueue<base::DropEvent>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    phantom_u: PhantomData<U>,
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    /// Row track definitions, from top to bottom.
+    pub rows: Vec<GridTrack>,
+    /// Column track definitions, from left to right.
+    pub columns: Vec<GridTrack>,
+    /// Space between rows.
+    pub row_gap: f32,
+    /// Space between columns.
+    pub column_gap: f32,
+    /// Horizontal alignment of children pushed without a `GridItem`.
+    pub horizontal_alignment: Align,
+    /// Vertical alignment of children pushed without a `GridItem`.
+    pub vertical_alignment: Align,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Grid
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = GridWidget<U, G>;
+}
+
+impl Grid {
+    pub fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        Grid {
+            rows: Vec::new(),
+            columns: Vec::new(),
+            row_gap: 0.0,
+            column_gap: 0.0,
+            horizontal_alignment: Align::Begin,
+            vertical_alignment: Align::Middle,
+        }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        _theme: &dyn draw::Theme,
+        _u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> GridWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            GridWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.layout.mark_dirty();
+                }
+            }
+        };
+
+        GridWidget {
+            data,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            next_cell: (0, 0),
+            fit: true,
+            fitted_size: Size::zero(),
+            visibility: Default::default(),
+            themed: Default::default(),
+            drop_event: Default::default(),
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            layout: Default::default(),
+
+            graph: graph.into(),
+            phantom_u: Default::default(),
+            phantom_g: Default::default(),
+        }
+    }
+}
+
+/// Computes the size of each of `count` tracks along one axis.
+///
+/// `items` holds the first track, the amount of tracks spanned and the size of each child along the axis.
+/// Without `available` space, fractional tracks are sized like `GridTrack::Auto`.
+fn track_sizes(
+    tracks: &[GridTrack],
+    count: usize,
+    gap: f32,
+    items: &[(usize, usize, f32)],
+    available: Option<f32>,
+) -> Vec<f32> {
+    let track = |i: usize| tracks.get(i).copied().unwrap_or_default();
+    let is_fixed = |i: usize| match track(i) {
+        GridTrack::Fixed(_) => true,
+        _ => false,
+    };
+
+    let mut sizes: Vec<f32> = (0..count)
+        .map(|i| match track(i) {
+            GridTrack::Fixed(size) => size,
+            _ => 0.0,
+        })
+        .collect();
+
+    for &(start, _, size) in items.iter().filter(|&&(_, span, _)| span == 1) {
+        if !is_fixed(start) {
+            sizes[start] = sizes[start].max(size);
+        }
+    }
+
+    // Children spanning several tracks distribute whatever they don't fit into evenly across the non-fixed tracks.
+    for &(start, span, size) in items.iter().filter(|&&(_, span, _)| span > 1) {
+        let end = (start + span).min(count);
+        let current = sizes[start..end].iter().sum::<f32>() + gap * (end - start - 1) as f32;
+        let flexible: Vec<usize> = (start..end).filter(|&i| !is_fixed(i)).collect();
+        if size > current && !flexible.is_empty() {
+            let extra = (size - current) / flexible.len() as f32;
+            for i in flexible {
+                sizes[i] += extra;
+            }
+        }
+    }
+
+    if let Some(available) = available {
+        let fraction = |i: usize| match track(i) {
+            GridTrack::Fraction(fraction) => fraction.max(0.0),
+            _ => 0.0,
+        };
+        let gaps = gap * count.saturating_sub(1) as f32;
+
+        // Tracks whose children don't fit within their share keep their content size, and the rest share what remains.
+        let mut flexible: Vec<usize> = (0..count)
+            .filter(|&i| match track(i) {
+                GridTrack::Fraction(_) => true,
+                _ => false,
+            })
+            .collect();
+        while !flexible.is_empty() {
+            let inflexible: f32 =
+                (0..count).filter(|i| !flexible.contains(i)).map(|i| sizes[i]).sum();
+            let total: f32 = flexible.iter().map(|&i| fraction(i)).sum();
+            if total <= 0.0 {
+                break;
+            }
+
+            let unit = (available - inflexible - gaps).max(0.0) / total;
+            let (too_small, rest): (Vec<usize>, Vec<usize>) =
+                flexible.iter().partition(|&&i| sizes[i] > fraction(i) * unit);
+            if too_small.is_empty() {
+                for i in rest {
+                    sizes[i] = fraction(i) * unit;
+                }
+                break;
+            }
+            flexible = rest;
+        }
+    }
+
+    sizes
+}
+
+/// Returns the start of each track, beginning at `origin`.
+fn track_offsets(sizes: &[f32], gap: f32, origin: f32) -> Vec<f32> {
+    let mut advance = origin;
+    sizes
+        .iter()
+        .map(|size| {
+            let offset = advance;
+            advance += size + gap;
+            offset
+        })
+        .collect()
+}
+
+impl<U, G> GridWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Computes the column and row sizes, sharing `available` space between fractional tracks.
+    fn track_layout(&self, available: Option<Size>) -> (Vec<f32>, Vec<f32>) {
+        let (row_count, column_count) = self.rects.values().fold(
+            (self.data.rows.len(), self.data.columns.len()),
+            |(rows, columns), child| {
+                (
+                    rows.max(child.data.row + child.data.row_span.max(1)),
+                    columns.max(child.data.column + child.data.column_span.max(1)),
+                )
+            },
+        );
+
+        let column_items: Vec<_> = self
+            .rects
+            .values()
+            .map(|child| (child.data.column, child.data.column_span.max(1), child.size.width))
+            .collect();
+        let row_items: Vec<_> = self
+            .rects
+            .values()
+            .map(|child| (child.data.row, child.data.row_span.max(1), child.size.height))
+            .collect();
+
+        (
+            track_sizes(
+                &self.data.columns,
+                column_count,
+                self.data.column_gap,
+                &column_items,
+                available.map(|size| size.width),
+            ),
+            track_sizes(
+                &self.data.rows,
+                row_count,
+                self.data.row_gap,
+                &row_items,
+                available.map(|size| size.height),
+            ),
+        )
+    }
+
+    fn resize_to_fit(&mut self) {
+        if !self.fit {
+            return;
+        }
+
+        let size = self.measure_children(self.layout.constraints());
+        self.fitted_size = size;
+        self.set_size(size);
+    }
+
+    fn on_transform(&mut self) {
+        if self.size() != self.fitted_size {
+            // The grid has been sized from the outside, so fill that size from now on.
+            self.fit = false;
+        }
+        self.layout.mark_dirty();
+        base::LayableWidget::notify_layout(self);
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for GridWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> base::Layout for GridWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = GridItem;
+
+    /// Pushes a child into the grid.
+    /// Without a `GridItem`, the child is placed in the cell after the previously pushed child, wrapping onto the next row after the last column.
+    fn push(&mut self, data: Option<GridItem>, child: &mut impl base::LayableWidget) {
+        self.layout.mark_dirty();
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            layout_node: self.layout.node().clone(),
+        });
+
+        let rect = child.abs_rect();
+
+        let data = data.unwrap_or(GridItem {
+            row: self.next_cell.0,
+            column: self.next_cell.1,
+            horizontal_alignment: self.data.horizontal_alignment,
+            vertical_alignment: self.data.vertical_alignment,
+            ..Default::default()
+        });
+
+        let next_column = data.column + data.column_span.max(1);
+        self.next_cell = if next_column >= self.data.columns.len().max(1) {
+            (data.row + 1, 0)
+        } else {
+            (data.row, next_column)
+        };
+
+        self.rects.insert(
+            id,
+            ChildData {
+                data,
+                evq,
+                node: child.layout_events().node().clone(),
+                drop_listener: child.drop_event().listen(),
+                rect,
+                size: rect.size.cast_unit(),
+                original_rect: rect,
+                id,
+            },
+        );
+
+        self.resize_to_fit();
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.layout.mark_dirty();
+            child.listen_to_layout(None);
+            if restore_original {
+                child.arrange(data.original_rect);
+            }
+        }
+    }
+
+    fn measure_children(&self, constraints: base::Constraints) -> Size {
+        let (columns, rows) = self.track_layout(None);
+        let length = |sizes: &[f32], gap: f32| {
+            sizes.iter().sum::<f32>() + gap * sizes.len().saturating_sub(1) as f32
+        };
+
+        constraints.constrain(Size::new(
+            length(&columns, self.data.column_gap),
+            length(&rows, self.data.row_gap),
+        ))
+    }
+}
+
+impl<U, G> Widget for GridWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+    }
+}
+
+impl<U, G> base::WidgetChildren for GridWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    fn relayout(&mut self) {
+        if self.layout.direction_changed() {
+            self.layout.mark_dirty();
+        }
+
+        {
+            let mut removals = Vec::new();
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    self.layout.mark_dirty();
+                    continue;
+                }
+
+                if let Some(base::LayoutEvent::Measure { rect, size }) = data.evq.retrieve_newest()
+                {
+                    if rect != data.rect || size != data.size {
+                        self.layout.mark_dirty();
+                        data.rect = rect;
+                        data.size = size;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.remove(&removal);
+            }
+        }
+
+        if self.layout.is_dirty() {
+            self.layout.invalidate();
+            self.resize_to_fit();
+            let abs_rect = self.abs_rect();
+            let direction = self.layout.direction();
+            let (column_gap, row_gap) = (self.data.column_gap, self.data.row_gap);
+            let (columns, rows) = self.track_layout(Some(abs_rect.size.cast_unit()));
+            let column_offsets = track_offsets(&columns, column_gap, abs_rect.origin.x);
+            let row_offsets = track_offsets(&rows, row_gap, abs_rect.origin.y);
+
+            let (defined_columns, defined_rows) = (&self.data.columns, &self.data.rows);
+            // Children in fixed tracks are measured within them; other tracks fit whatever their children measure.
+            let bound = |tracks: &[GridTrack], start: usize, span: usize, length: f32| {
+                let fixed = (start..start + span.max(1)).all(|index| match tracks.get(index) {
+                    Some(GridTrack::Fixed(_)) => true,
+                    _ => false,
+                });
+                if fixed {
+                    length
+                } else {
+                    std::f32::INFINITY
+                }
+            };
+
+            // Returns the start and length of the tracks `start..start + span`.
+            let cell = |offsets: &[f32], sizes: &[f32], gap: f32, start: usize, span: usize| {
+                let end = (start + span.max(1)).min(sizes.len());
+                let length = sizes[start..end].iter().sum::<f32>() + gap * (end - start - 1) as f32;
+                (offsets[start], length)
+            };
+
+            for (_, data) in &mut self.rects {
+                let item = data.data;
+                let (cell_x, cell_width) =
+                    cell(&column_offsets, &columns, column_gap, item.column, item.column_span);
+                let (cell_y, cell_height) =
+                    cell(&row_offsets, &rows, row_gap, item.row, item.row_span);
+
+                let (x, width) =
+                    align_within(item.horizontal_alignment, cell_x, cell_width, data.size.width);
+                let (y, height) =
+                    align_within(item.vertical_alignment, cell_y, cell_height, data.size.height);
+                let rect = AbsoluteRect::new(
+                    AbsolutePoint::new(x, y),
+                    Size::new(width, height).cast_unit(),
+                );
+
+                let constraints = base::Constraints::new(
+                    Size::new(
+                        if item.horizontal_alignment == Align::Stretch { cell_width } else { 0.0 },
+                        if item.vertical_alignment == Align::Stretch { cell_height } else { 0.0 },
+                    ),
+                    Size::new(
+                        bound(defined_columns, item.column, item.column_span, cell_width),
+                        bound(defined_rows, item.row, item.row_span, cell_height),
+                    ),
+                );
+
+                let rect = ui::mirror(rect, abs_rect, direction);
+                data.evq.emit_owned(base::LayoutEvent::Arrange { constraints, rect, direction });
+                data.node.invalidate();
+                data.rect = rect;
+            }
+
+            self.layout.clear_dirty();
+            if self.layout.measured().is_none() {
+                base::LayableWidget::notify_layout(self);
+            }
+        }
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<Grid> for GridWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<Grid> {
+        &mut self.data
+    }
+}
+
+impl<U, G> StoresParentPosition for GridWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
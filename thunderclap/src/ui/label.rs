@@ -8,7 +8,7 @@ use {
     reclutch::{
         display::{
             center_horizontally, Color, CommandGroup, DisplayCommand, DisplayListBuilder,
-            DisplayText, GraphicsDisplay, Rect, TextDisplayItem,
+            DisplayText, GraphicsDisplay, Rect, Size, TextDisplayItem,
         },
         event::RcEventQueue,
         prelude::*,
@@ -39,6 +39,7 @@ pub enum TextAlign {
 #[widget_children_trait(base::WidgetChildren)]
 #[thunderclap_crate(crate)]
 #[widget_transform_callback(on_transform)]
+#[widget_measure(measure_text)]
 pub struct LabelWidget<U, G>
 where
     U: base::UpdateAuxiliary + 'static,
@@ -71,8 +72,11 @@ pub struct Label {
     pub text: DisplayText,
     pub typeface: draw::TypefaceStyle,
     pub color: Color,
+    /// Horizontal alignment of the text; `Left` and `Right` are swapped within a right-to-left layout.
     pub align: TextAlign,
     pub wrap: bool,
+    /// How lines which don't fit within the label are displayed.
+    pub overflow: draw::TextOverflow,
 }
 
 impl<U, G> ui::WidgetDataTarget<U, G> for Label
@@ -92,6 +96,7 @@ impl Label {
             color: data.scheme.over_control_outset,
             align: TextAlign::Left,
             wrap: true,
+            overflow: draw::TextOverflow::Clip,
         }
     }
 
@@ -112,6 +117,7 @@ impl Label {
             U as _aux,
             "bind" => _ev in &data.on_change => {
                 change => {
+                    obj.layout.invalidate();
                     obj.update_text_items();
                     obj.repaint();
                 }
@@ -156,61 +162,83 @@ impl<U: base::UpdateAuxiliary, G: base::GraphicalAuxiliary> LabelWidget<U, G> {
         }
 
         self.previous_rect = self.rect;
-        self.layout.notify(self.abs_rect());
+        base::LayableWidget::notify_layout(self);
         self.repaint();
     }
 
     fn update_text_items(&mut self) {
-        let (text_items, bounds) = {
-            let font = self.data.typeface.typeface.pick(self.data.typeface.style);
-
-            let mut text = TextDisplayItem {
-                text: self.data.text.clone(),
-                font: font.0,
-                font_info: font.1.clone(),
-                size: self.data.typeface.size,
-                bottom_left: Default::default(),
-                color: self.data.color.into(),
-            };
+        let (text_items, bounds) = self.layout_text(self.abs_rect());
+        self.text_items = text_items;
+        self.set_ctxt_rect(bounds);
+    }
+
+    /// Returns the alignment of the text, mirrored within a right-to-left layout.
+    fn align(&self) -> TextAlign {
+        match (self.data.align, self.layout.direction()) {
+            (TextAlign::Left, base::LayoutDirection::Rtl) => TextAlign::Right,
+            (TextAlign::Right, base::LayoutDirection::Rtl) => TextAlign::Left,
+            (align, _) => align,
+        }
+    }
 
-            text.set_top_left(self.abs_rect().origin.cast_unit());
-
-            let metrics = font.1.font.metrics();
-            let mut text_items = if self.data.wrap {
-                text.linebreak(
-                    self.abs_rect().cast_unit(),
-                    (metrics.ascent + metrics.line_gap) / metrics.units_per_em as f32
-                        * self.data.typeface.size,
-                    true,
-                )
-                .unwrap()
+    /// Lays out the text within `rect`, returning the text items and their total bounds.
+    fn layout_text(&self, rect: AbsoluteRect) -> (Vec<TextDisplayItem>, AbsoluteRect) {
+        let font = self.data.typeface.typeface.pick(self.data.typeface.style);
+
+        let mut text = TextDisplayItem {
+            text: self.data.text.clone(),
+            font: font.0,
+            font_info: font.1.clone(),
+            size: self.data.typeface.size,
+            bottom_left: Default::default(),
+            color: self.data.color.into(),
+        };
+
+        text.set_top_left(rect.origin.cast_unit());
+
+        let metrics = font.1.font.metrics();
+        let mut text_items = if self.data.wrap {
+            text.linebreak(
+                rect.cast_unit(),
+                (metrics.ascent + metrics.line_gap) / metrics.units_per_em as f32
+                    * self.data.typeface.size,
+                true,
+            )
+            .unwrap()
+        } else {
+            vec![text]
+        };
+
+        let mut total_bounds: Option<AbsoluteRect> = None;
+        for text_item in &mut text_items {
+            draw::fit_text(text_item, rect.cast_unit(), self.data.overflow);
+            let bounds = text_item.bounds().unwrap().cast_unit();
+            if let Some(ref mut total_bounds) = total_bounds {
+                *total_bounds = total_bounds.union(&bounds);
             } else {
-                vec![text]
+                total_bounds = Some(bounds);
+            }
+            let left = match self.align() {
+                TextAlign::Left => text_item.bottom_left.x,
+                TextAlign::Middle => center_horizontally(bounds.cast_unit(), rect.cast_unit()).x,
+                TextAlign::Right => rect.max_x() - bounds.size.width,
             };
+            text_item.bottom_left.x = left;
+        }
 
-            let mut total_bounds: Option<AbsoluteRect> = None;
-            for text_item in &mut text_items {
-                let bounds = text_item.bounds().unwrap().cast_unit();
-                if let Some(ref mut total_bounds) = total_bounds {
-                    *total_bounds = total_bounds.union(&bounds);
-                } else {
-                    total_bounds = Some(bounds);
-                }
-                let left = match self.data.align {
-                    TextAlign::Left => text_item.bottom_left.x,
-                    TextAlign::Middle => {
-                        center_horizontally(bounds.cast_unit(), self.abs_rect().cast_unit()).x
-                    }
-                    TextAlign::Right => self.abs_rect().max_x() - bounds.size.width,
-                };
-                text_item.bottom_left.x = left;
-            }
+        (text_items, total_bounds.unwrap_or_default())
+    }
 
-            (text_items, total_bounds.unwrap_or_default())
-        };
+    /// Measures the text wrapped within the maximum width of `constraints`.
+    fn measure_text(&mut self, constraints: base::Constraints) -> Size {
+        // Text can't be layed out within infinite space, so unbounded axes are given plenty of room instead.
+        let bound = |length: f32| if length.is_finite() { length } else { 1.0e6 };
+        let rect = AbsoluteRect::new(
+            self.abs_rect().origin,
+            Size::new(bound(constraints.max.width), bound(constraints.max.height)).cast_unit(),
+        );
 
-        self.text_items = text_items;
-        self.set_ctxt_rect(bounds);
+        constraints.constrain(self.layout_text(rect).1.size.cast_unit())
     }
 }
 
@@ -239,8 +267,7 @@ where
     }
 
     fn update(&mut self, aux: &mut U) {
-        if let Some(rect) = self.layout.receive() {
-            self.set_ctxt_rect(rect);
+        if self.layout.direction_changed() {
             self.dirty = true;
         }
 
@@ -0,0 +1,368 @@
+//! Hyperlink-style text widget.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state, HasTheme},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, CommandGroup, DisplayCommand, DisplayText, GraphicsDisplay, Rect},
+        event::RcEventQueue,
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::marker::PhantomData,
+};
+
+/// Events emitted by a link.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum LinkEvent {
+    /// Emitted when the link is clicked or activated with the keyboard, carrying the `url` of the link.
+    #[event_key(activated)]
+    Activated(String),
+    /// Emitted when focus is gained.
+    #[event_key(focus)]
+    Focus,
+    /// Emitted when focus is lost.
+    #[event_key(blur)]
+    Blur,
+}
+
+pub fn link_handler<U, G>() -> vg::UnboundQueueHandler<LinkWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        LinkWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        key_press => {
+            if event
+                .with(|(key, _)| {
+                    !obj.data.disabled
+                        && obj.interaction.contains(state::InteractionState::FOCUSED)
+                        && *key == base::KeyInput::Return
+                })
+                .is_some()
+            {
+                obj.activate();
+            }
+        }
+    }
+}
+
+/// Focus-able text which underlines on hover and emits `LinkEvent::Activated` when clicked.
+///
+/// The link remembers whether it has been activated (`visited`), which is drawn using `visited_color`.
+#[derive(
+    WidgetChildren,
+    LayableWidget,
+    DropNotifier,
+    HasVisibility,
+    Repaintable,
+    Movable,
+    Resizable,
+    OperatesVerbGraph,
+)]
+#[widget_children_trait(base::WidgetChildren)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+pub struct LinkWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub event_queue: RcEventQueue<LinkEvent>,
+
+    pub data: base::Observed<Link>,
+    graph: vg::OptionVerbGraph<Self, U>,
+    interaction: state::InteractionState,
+    painter: Box<dyn draw::Painter<state::LinkState>>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_visibility]
+    visibility: base::Visibility,
+    #[repaint_target]
+    command_group: CommandGroup,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+    #[widget_drop_event]
+    drop_event: RcEventQueue<base::DropEvent>,
+
+    phantom_g: PhantomData<G>,
+}
+
+impl<U, G> ui::InteractiveWidget for LinkWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline(always)]
+    fn interaction(&mut self) -> &mut state::InteractionState {
+        &mut self.interaction
+    }
+
+    #[inline]
+    fn mouse_bounds(&self) -> RelativeRect {
+        self.painter.mouse_hint(self.rect)
+    }
+
+    #[inline(always)]
+    fn disabled(&self) -> bool {
+        self.data.disabled
+    }
+
+    fn on_interaction_event(&mut self, event: ui::InteractionEvent) {
+        self.repaint();
+        match event {
+            ui::InteractionEvent::Released(pos) => {
+                if self.abs_convert_rect(self.mouse_bounds()).contains(pos) {
+                    self.activate();
+                }
+            }
+            ui::InteractionEvent::Focus => self.event_queue.emit_owned(LinkEvent::Focus),
+            ui::InteractionEvent::Blur => self.event_queue.emit_owned(LinkEvent::Blur),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub text: DisplayText,
+    /// URL (or any other identifier) emitted through `LinkEvent::Activated`.
+    pub url: String,
+    pub typeface: draw::TypefaceStyle,
+    pub color: Color,
+    /// Color of the text once the link has been activated.
+    pub visited_color: Color,
+    pub focus: Color,
+    pub contrast: draw::ThemeContrast,
+    /// Whether the link has been activated before. This is set automatically upon activation.
+    pub visited: bool,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Link
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = LinkWidget<U, G>;
+}
+
+impl Link {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Link {
+            text: "".to_string().into(),
+            url: String::new(),
+            typeface: data.typography.body.clone(),
+            color: data.scheme.primary,
+            visited_color: draw::darken(data.scheme.primary, 0.2),
+            focus: data.scheme.focus,
+            contrast: data.contrast,
+            visited: false,
+            disabled: false,
+        }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        theme: &dyn draw::Theme,
+        u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> LinkWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            LinkWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.resize_from_theme();
+                    obj.command_group.repaint();
+                }
+            }
+        };
+
+        graph = graph.add(
+            "interaction",
+            ui::basic_interaction_handler::<LinkWidget<U, G>, U>().bind(u_aux.window_queue()),
+        );
+        graph = graph.add("link", link_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let painter = theme.link();
+        let rect = RelativeRect::new(
+            Default::default(),
+            painter
+                .size_hint(state::LinkState {
+                    rect: Default::default(),
+                    data: data.clone(),
+                    interaction: state::InteractionState::empty(),
+                })
+                .cast_unit(),
+        );
+
+        LinkWidget {
+            event_queue: Default::default(),
+            data,
+            graph: graph.into(),
+            interaction: state::InteractionState::empty(),
+            painter,
+            parent_position: Default::default(),
+            rect,
+            visibility: Default::default(),
+            command_group: Default::default(),
+            layout: Default::default(),
+            drop_event: Default::default(),
+            phantom_g: Default::default(),
+        }
+    }
+}
+
+impl<U, G> LinkWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Marks the link as visited and emits `LinkEvent::Activated`.
+    pub fn activate(&mut self) {
+        if !self.data.visited {
+            self.data.visited = true;
+        }
+        self.event_queue.emit_owned(LinkEvent::Activated(self.data.url.clone()));
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        base::LayableWidget::notify_layout(self);
+    }
+
+    fn derive_state(&self) -> state::LinkState {
+        state::LinkState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            interaction: self.interaction,
+        }
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for LinkWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> Widget for LinkWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if !self.data.disabled && self.interaction.contains(state::InteractionState::HOVERED) {
+            aux.set_cursor_icon(base::CursorIcon::Pointer);
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let link_state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(link_state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
+
+impl<U, G> StoresParentPosition for LinkWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
+
+impl<U, G> HasTheme for LinkWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn theme(&mut self) -> &mut dyn draw::Themed {
+        &mut self.painter
+    }
+
+    fn resize_from_theme(&mut self) {
+        self.set_size(self.painter.size_hint(self.derive_state()));
+    }
+}
+
+impl<U, G> ui::DefaultEventQueue<LinkEvent> for LinkWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_event_queue(&self) -> &RcEventQueue<LinkEvent> {
+        &self.event_queue
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<Link> for LinkWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<Link> {
+        &mut self.data
+    }
+}
+
+impl<U, G> Drop for LinkWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn drop(&mut self) {
+        self.drop_event.emit_owned(base::DropEvent);
+    }
+}
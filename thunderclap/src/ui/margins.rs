@@ -1,6 +1,6 @@
 use {
     crate::{
-        base::{self, Resizable},
+        base::{self, Layout, Resizable},
         draw,
         geom::*,
         ui,
@@ -18,9 +18,12 @@ use {
 
 #[derive(Debug)]
 struct ChildData {
-    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    evq: BidirSingleEventQueue<base::LayoutEvent, base::LayoutEvent>,
+    node: base::LayoutNode,
     drop_listener: RcEventListener<base::DropEvent>,
     rect: AbsoluteRect,
+    /// Size last measured by the child.
+    size: Size,
     original_rect: AbsoluteRect,
     distance_from_tl: Vector,
     id: u64,
@@ -34,10 +37,10 @@ lazy_widget! {
 }
 
 /// Abstract layout widget which places margins around it's children.
-#[derive(WidgetChildren, LayableWidget, Movable, Resizable, OperatesVerbGraph)]
-#[widget_children_trait(base::WidgetChildren)]
+#[derive(LayableWidget, Movable, Resizable, OperatesVerbGraph)]
 #[thunderclap_crate(crate)]
 #[widget_transform_callback(on_transform)]
+#[widget_measure(measure_children)]
 pub struct MarginsWidget<U, G>
 where
     U: base::UpdateAuxiliary,
@@ -47,7 +50,6 @@ where
 
     rects: IndexMap<u64, ChildData>,
     next_rect_id: u64,
-    dirty: bool,
     visibility: base::Visibility,
     themed: draw::PhantomThemed,
     drop_event: RcEventQueue<base::DropEvent>,
@@ -100,7 +102,6 @@ impl Margins {
 
             rects: IndexMap::new(),
             next_rect_id: 0,
-            dirty: true,
             visibility: Default::default(),
             themed: Default::default(),
             drop_event: Default::default(),
@@ -122,23 +123,13 @@ where
     G: base::GraphicalAuxiliary,
 {
     fn resize_to_fit(&mut self) {
-        let mut max_rect = AbsoluteRect::default();
-        for (_, child) in &self.rects {
-            max_rect = max_rect.union(&child.rect);
-        }
-
-        self.set_size(
-            max_rect.size.cast_unit()
-                + Size::new(
-                    self.data.margins.left + self.data.margins.right,
-                    self.data.margins.top + self.data.margins.bottom,
-                ),
-        );
+        let size = self.measure_children(self.layout.constraints());
+        self.set_size(size);
     }
 
     fn on_transform(&mut self) {
-        self.dirty = true;
-        self.layout.notify(self.abs_rect());
+        self.layout.mark_dirty();
+        base::LayableWidget::notify_layout(self);
     }
 }
 
@@ -160,14 +151,18 @@ where
     type PushData = ();
 
     fn push(&mut self, _data: Option<()>, child: &mut impl base::LayableWidget) {
-        self.dirty = true;
+        self.layout.mark_dirty();
 
         let id = self.next_rect_id;
         self.next_rect_id += 1;
 
         let evq = BidirSingleEventQueue::new();
 
-        child.listen_to_layout(base::WidgetLayoutEventsInner { id, evq: evq.secondary() });
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            layout_node: self.layout.node().clone(),
+        });
 
         let rect = child.abs_rect();
 
@@ -175,8 +170,10 @@ where
             id,
             ChildData {
                 evq,
+                node: child.layout_events().node().clone(),
                 drop_listener: child.drop_event().listen(),
                 rect,
+                size: rect.size.cast_unit(),
                 original_rect: rect,
                 distance_from_tl: (rect.origin - self.abs_rect().origin).cast_unit(),
                 id,
@@ -190,10 +187,25 @@ where
         if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
             child.listen_to_layout(None);
             if restore_original {
-                child.set_ctxt_rect(data.original_rect);
+                child.arrange(data.original_rect);
             }
         }
     }
+
+    fn measure_children(&self, constraints: base::Constraints) -> Size {
+        let mut size = Size::zero();
+        for (_, child) in &self.rects {
+            size.width = size.width.max(child.distance_from_tl.x + child.size.width);
+            size.height = size.height.max(child.distance_from_tl.y + child.size.height);
+        }
+
+        constraints.constrain(
+            size + Size::new(
+                self.data.margins.left + self.data.margins.right,
+                self.data.margins.top + self.data.margins.bottom,
+            ),
+        )
+    }
 }
 
 impl<U, G> Widget for MarginsWidget<U, G>
@@ -208,27 +220,40 @@ where
     fn bounds(&self) -> Rect {
         self.rect.cast_unit()
     }
+}
 
-    fn update(&mut self, _aux: &mut U) {
-        if let Some(rect) = self.layout.receive() {
-            self.set_ctxt_rect(rect);
-            self.dirty = true;
+impl<U, G> base::WidgetChildren for MarginsWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    fn relayout(&mut self) {
+        if self.layout.direction_changed() {
+            self.layout.mark_dirty();
         }
 
         {
-            let abs_rect = self.abs_rect().inner_rect(self.data.margins);
+            let outer_rect = self.abs_rect();
+            let abs_rect = outer_rect.inner_rect(self.data.margins);
+            let direction = self.layout.direction();
             let mut removals = Vec::new();
             for (_, data) in &mut self.rects {
                 if !data.drop_listener.peek().is_empty() {
                     removals.push(data.id);
-                    self.dirty = true;
+                    self.layout.mark_dirty();
                     continue;
                 }
 
-                if let Some(new_ev) = data.evq.retrieve_newest() {
-                    self.dirty = true;
-                    data.rect = new_ev;
-                    data.distance_from_tl = (new_ev.origin - abs_rect.origin).cast_unit();
+                if let Some(base::LayoutEvent::Measure { rect, size }) = data.evq.retrieve_newest()
+                {
+                    if rect != data.rect || size != data.size {
+                        self.layout.mark_dirty();
+                        data.rect = rect;
+                        data.size = size;
+                        // Distances are kept left-to-right, so that they can be mirrored again when arranging.
+                        let rect = ui::mirror(rect, outer_rect, direction);
+                        data.distance_from_tl = (rect.origin - abs_rect.origin).cast_unit();
+                    }
                 }
             }
             for removal in removals {
@@ -236,19 +261,37 @@ where
             }
         }
 
-        if self.dirty {
+        if self.layout.is_dirty() {
+            self.layout.invalidate();
             self.resize_to_fit();
             let abs_rect = self.abs_rect();
+            let direction = self.layout.direction();
+            let constraints = base::Constraints::loose(
+                self.layout
+                    .constraints()
+                    .deflate(
+                        self.data.margins.left + self.data.margins.right,
+                        self.data.margins.top + self.data.margins.bottom,
+                    )
+                    .max,
+            );
             for (_, data) in &mut self.rects {
-                let mut rect = data.rect;
-                rect.origin = abs_rect.origin
-                    + data.distance_from_tl.cast_unit()
-                    + Vector::new(self.data.margins.left, self.data.margins.top).cast_unit();
-                data.evq.emit_owned(rect);
+                let rect = AbsoluteRect::new(
+                    abs_rect.origin
+                        + data.distance_from_tl.cast_unit()
+                        + Vector::new(self.data.margins.left, self.data.margins.top).cast_unit(),
+                    data.size.cast_unit(),
+                );
+                let rect = ui::mirror(rect, abs_rect, direction);
+                data.evq.emit_owned(base::LayoutEvent::Arrange { constraints, rect, direction });
+                data.node.invalidate();
                 data.rect = rect;
             }
 
-            self.dirty = false;
+            self.layout.clear_dirty();
+            if self.layout.measured().is_none() {
+                base::LayableWidget::notify_layout(self);
+            }
         }
     }
 }
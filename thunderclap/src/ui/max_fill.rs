@@ -1,13 +1,13 @@
 use {
     crate::{
-        base::{self, Resizable},
+        base::{self, Layout, Resizable},
         draw,
         geom::*,
         ui,
     },
     indexmap::IndexMap,
     reclutch::{
-        display::{DisplayCommand, Rect},
+        display::{DisplayCommand, Rect, Size},
         event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener, RcEventQueue},
         prelude::*,
         verbgraph as vg,
@@ -16,9 +16,12 @@ use {
 };
 
 struct ChildData {
-    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    evq: BidirSingleEventQueue<base::LayoutEvent, base::LayoutEvent>,
+    node: base::LayoutNode,
     drop_listener: RcEventListener<base::DropEvent>,
     rect: AbsoluteRect,
+    /// Size last measured by the child.
+    size: Size,
     original_rect: AbsoluteRect,
     id: u64,
 }
@@ -31,10 +34,10 @@ lazy_widget! {
 }
 
 /// Abstract layout widget which finds resizes itself to fit all it's children, then changes all it's children's rectangles to it's own.
-#[derive(WidgetChildren, LayableWidget, Movable, Resizable, OperatesVerbGraph)]
-#[widget_children_trait(base::WidgetChildren)]
+#[derive(LayableWidget, Movable, Resizable, OperatesVerbGraph)]
 #[thunderclap_crate(crate)]
 #[widget_transform_callback(on_transform)]
+#[widget_measure(measure_children)]
 pub struct MaxFillWidget<U, G>
 where
     U: base::UpdateAuxiliary,
@@ -44,7 +47,6 @@ where
 
     rects: IndexMap<u64, ChildData>,
     next_rect_id: u64,
-    dirty: bool,
     visibility: base::Visibility,
     themed: draw::PhantomThemed,
     drop_event: RcEventQueue<base::DropEvent>,
@@ -93,7 +95,6 @@ impl MaxFill {
 
             rects: IndexMap::new(),
             next_rect_id: 0,
-            dirty: true,
             visibility: Default::default(),
             themed: Default::default(),
             drop_event: Default::default(),
@@ -115,17 +116,13 @@ where
     G: base::GraphicalAuxiliary,
 {
     fn resize_to_fit(&mut self) {
-        let mut max_rect = AbsoluteRect::default();
-        for (_, child) in &self.rects {
-            max_rect = max_rect.union(&child.rect);
-        }
-
-        self.set_size(max_rect.size.cast_unit());
+        let size = self.measure_children(self.layout.constraints());
+        self.set_size(size);
     }
 
     fn on_transform(&mut self) {
-        self.dirty = true;
-        self.layout.notify(self.abs_rect());
+        self.layout.mark_dirty();
+        base::LayableWidget::notify_layout(self);
     }
 }
 
@@ -147,14 +144,18 @@ where
     type PushData = ();
 
     fn push(&mut self, _data: Option<()>, child: &mut impl base::LayableWidget) {
-        self.dirty = true;
+        self.layout.mark_dirty();
 
         let id = self.next_rect_id;
         self.next_rect_id += 1;
 
         let evq = BidirSingleEventQueue::new();
 
-        child.listen_to_layout(base::WidgetLayoutEventsInner { id, evq: evq.secondary() });
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            layout_node: self.layout.node().clone(),
+        });
 
         let rect = child.abs_rect();
 
@@ -162,8 +163,10 @@ where
             id,
             ChildData {
                 evq,
+                node: child.layout_events().node().clone(),
                 drop_listener: child.drop_event().listen(),
                 rect,
+                size: rect.size.cast_unit(),
                 original_rect: rect,
                 id,
             },
@@ -176,10 +179,20 @@ where
         if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
             child.listen_to_layout(None);
             if restore_original {
-                child.set_ctxt_rect(data.original_rect);
+                child.arrange(data.original_rect);
             }
         }
     }
+
+    fn measure_children(&self, constraints: base::Constraints) -> Size {
+        let mut size = Size::zero();
+        for (_, child) in &self.rects {
+            size.width = size.width.max(child.size.width);
+            size.height = size.height.max(child.size.height);
+        }
+
+        constraints.constrain(size)
+    }
 }
 
 impl<U, G> Widget for MaxFillWidget<U, G>
@@ -194,25 +207,34 @@ where
     fn bounds(&self) -> Rect {
         self.rect.cast_unit()
     }
+}
 
-    fn update(&mut self, _aux: &mut U) {
-        if let Some(rect) = self.layout.receive() {
-            self.set_ctxt_rect(rect);
-            self.dirty = true;
+impl<U, G> base::WidgetChildren for MaxFillWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    fn relayout(&mut self) {
+        if self.layout.direction_changed() {
+            self.layout.mark_dirty();
         }
 
         {
             let mut removals = Vec::new();
-            let dirty = &mut self.dirty;
             for (_, data) in &mut self.rects {
                 if !data.drop_listener.peek().is_empty() {
                     removals.push(data.id);
-                    *dirty = true;
+                    self.layout.mark_dirty();
                     continue;
                 }
 
-                if let Some(_) = data.evq.retrieve_newest() {
-                    *dirty = true;
+                if let Some(base::LayoutEvent::Measure { rect, size }) = data.evq.retrieve_newest()
+                {
+                    if rect != data.rect || size != data.size {
+                        self.layout.mark_dirty();
+                        data.rect = rect;
+                        data.size = size;
+                    }
                 }
             }
             for removal in removals {
@@ -220,15 +242,26 @@ where
             }
         }
 
-        if self.dirty {
+        if self.layout.is_dirty() {
+            self.layout.invalidate();
             self.resize_to_fit();
             let abs_rect = self.abs_rect();
+            let direction = self.layout.direction();
+            let constraints = base::Constraints::tight(abs_rect.size.cast_unit());
             for (_, data) in &mut self.rects {
-                data.evq.emit_owned(abs_rect);
+                data.evq.emit_owned(base::LayoutEvent::Arrange {
+                    constraints,
+                    rect: abs_rect,
+                    direction,
+                });
+                data.node.invalidate();
                 data.rect = abs_rect;
             }
 
-            self.dirty = false;
+            self.layout.clear_dirty();
+            if self.layout.measured().is_none() {
+                base::LayableWidget::notify_layout(self);
+            }
         }
     }
 }
@@ -0,0 +1,295 @@
+//! Menu item model shared by menu widgets.
+//!
+//! Commands are described once as a list of `MenuItem`s and can then be handed to any widget
+//! which displays menus (such as `MenuBar`).
+
+use {
+    crate::{base, draw, geom::*},
+    reclutch::display::{Color, Size, StyleColor, TextDisplayItem},
+};
+
+/// A command which can be activated from a menu.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuAction {
+    /// Identifier emitted when the action is activated.
+    pub id: String,
+    /// Text shown for the action. An `&` marks the following character as the mnemonic.
+    pub text: String,
+    /// Keyboard shortcut hint shown alongside the text (purely visual).
+    pub shortcut: Option<String>,
+    /// Whether the action can be activated.
+    pub disabled: bool,
+}
+
+/// A single entry within a menu.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MenuItem {
+    /// An activatable command.
+    Action(MenuAction),
+    /// A visual divider between groups of actions.
+    Separator,
+}
+
+impl MenuItem {
+    /// Creates an enabled action without a shortcut hint.
+    pub fn action(id: impl Into<String>, text: impl Into<String>) -> Self {
+        MenuItem::Action(MenuAction {
+            id: id.into(),
+            text: text.into(),
+            shortcut: None,
+            disabled: false,
+        })
+    }
+
+    /// Sets the shortcut hint of an action. Has no effect on separators.
+    pub fn shortcut(self, shortcut: impl Into<String>) -> Self {
+        match self {
+            MenuItem::Action(action) => {
+                MenuItem::Action(MenuAction { shortcut: Some(shortcut.into()), ..action })
+            }
+            MenuItem::Separator => MenuItem::Separator,
+        }
+    }
+
+    /// Sets whether an action is disabled. Has no effect on separators.
+    pub fn disabled(self, disabled: bool) -> Self {
+        match self {
+            MenuItem::Action(action) => MenuItem::Action(MenuAction { disabled, ..action }),
+            MenuItem::Separator => MenuItem::Separator,
+        }
+    }
+
+    /// Returns the inner action if this item can currently be activated.
+    pub fn enabled_action(&self) -> Option<&MenuAction> {
+        match self {
+            MenuItem::Action(action) if !action.disabled => Some(action),
+            _ => None,
+        }
+    }
+}
+
+/// A titled list of menu items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Menu {
+    /// Title of the menu. An `&` marks the following character as the mnemonic.
+    pub text: String,
+    pub items: Vec<MenuItem>,
+}
+
+impl Menu {
+    pub fn new(text: impl Into<String>, items: Vec<MenuItem>) -> Self {
+        Menu { text: text.into(), items }
+    }
+}
+
+/// Visual properties shared by all menu widgets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuStyle {
+    pub typeface: draw::TypefaceStyle,
+    /// Color of the item text.
+    pub color: Color,
+    /// Color of disabled item text.
+    pub disabled_color: Color,
+    /// Background color of the menu bar and menu popups.
+    pub background: Color,
+    /// Background color of the highlighted item.
+    pub highlight: Color,
+    /// Color of text over `highlight`.
+    pub over_highlight: Color,
+    pub contrast: draw::ThemeContrast,
+    /// Padding placed around item text.
+    pub padding: f32,
+}
+
+impl MenuStyle {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        MenuStyle {
+            typeface: data.typography.body.clone(),
+            color: data.scheme.over_control_outset,
+            disabled_color: draw::weaken(data.scheme.over_control_outset, 0.4, data.contrast),
+            background: data.scheme.control_outset,
+            highlight: data.scheme.primary,
+            over_highlight: data.scheme.over_primary,
+            contrast: data.contrast,
+            padding: 6.0,
+        }
+    }
+
+    /// Creates a text item for `text` (which should already have mnemonic markers removed).
+    pub fn text_item(&self, text: &str, color: StyleColor) -> TextDisplayItem {
+        let font = self.typeface.typeface.pick(self.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: font.0,
+            font_info: font.1,
+            size: self.typeface.size,
+            bottom_left: Default::default(),
+            color,
+        }
+    }
+
+    /// Returns the height of a single line of text.
+    pub fn line_height(&self) -> f32 {
+        let font = self.typeface.typeface.pick(self.typeface.style);
+        let metrics = font.1.font.metrics();
+        (metrics.ascent - metrics.descent) / metrics.units_per_em as f32 * self.typeface.size
+    }
+
+    /// Returns the width of `text` (which should already have mnemonic markers removed).
+    pub fn text_width(&self, text: &str) -> f32 {
+        if text.is_empty() {
+            0.0
+        } else {
+            self.text_item(text, Color::default().into()).bounds().unwrap().size.width
+        }
+    }
+}
+
+/// Removes the mnemonic marker from `text`, returning the displayed text and the character index of the mnemonic (if any).
+///
+/// A single `&` marks the following character as the mnemonic, whereas `&&` is displayed as a literal `&`.
+pub fn split_mnemonic(text: &str) -> (String, Option<usize>) {
+    let mut display = String::with_capacity(text.len());
+    let mut mnemonic = None;
+    let mut chars = text.chars().peekable();
+    let mut index = 0;
+    while let Some(c) = chars.next() {
+        if c == '&' {
+            match chars.next() {
+                Some('&') => display.push('&'),
+                Some(next) => {
+                    if mnemonic.is_none() {
+                        mnemonic = Some(index);
+                    }
+                    display.push(next);
+                }
+                None => break,
+            }
+        } else {
+            display.push(c);
+        }
+        index += 1;
+    }
+    (display, mnemonic)
+}
+
+/// Returns the (lowercase) mnemonic character of `text`, if any.
+pub fn mnemonic_char(text: &str) -> Option<char> {
+    let (display, index) = split_mnemonic(text);
+    index.and_then(|index| display.chars().nth(index)).map(|c| c.to_lowercase().next().unwrap_or(c))
+}
+
+/// Converts an alphanumeric key into the (lowercase) character it types.
+pub fn key_char(key: base::KeyInput) -> Option<char> {
+    use base::KeyInput::*;
+    Some(match key {
+        Key1 | Numpad1 => '1',
+        Key2 | Numpad2 => '2',
+        Key3 | Numpad3 => '3',
+        Key4 | Numpad4 => '4',
+        Key5 | Numpad5 => '5',
+        Key6 | Numpad6 => '6',
+        Key7 | Numpad7 => '7',
+        Key8 | Numpad8 => '8',
+        Key9 | Numpad9 => '9',
+        Key0 | Numpad0 => '0',
+        A => 'a',
+        B => 'b',
+        C => 'c',
+        D => 'd',
+        E => 'e',
+        F => 'f',
+        G => 'g',
+        H => 'h',
+        I => 'i',
+        J => 'j',
+        K => 'k',
+        L => 'l',
+        M => 'm',
+        N => 'n',
+        O => 'o',
+        P => 'p',
+        Q => 'q',
+        R => 'r',
+        S => 's',
+        T => 't',
+        U => 'u',
+        V => 'v',
+        W => 'w',
+        X => 'x',
+        Y => 'y',
+        Z => 'z',
+        _ => return None,
+    })
+}
+
+/// Computes the popup rectangle and the rectangle of each item for a menu placed at `origin`.
+///
+/// The popup is at least `min_width` wide.
+pub fn layout_menu(
+    style: &MenuStyle,
+    items: &[MenuItem],
+    origin: AbsolutePoint,
+    min_width: f32,
+) -> (AbsoluteRect, Vec<AbsoluteRect>) {
+    let item_height = style.line_height() + style.padding * 2.0;
+    let separator_height = style.padding + 1.0;
+
+    let mut width: f32 = 0.0;
+    for item in items {
+        if let MenuItem::Action(action) = item {
+            let mut item_width = style.text_width(&split_mnemonic(&action.text).0);
+            if let Some(ref shortcut) = action.shortcut {
+                item_width += style.padding * 4.0 + style.text_width(shortcut);
+            }
+            width = width.max(item_width);
+        }
+    }
+    let width = (width + style.padding * 4.0).max(min_width);
+
+    let mut item_rects = Vec::with_capacity(items.len());
+    let mut advance = origin.y + style.padding / 2.0;
+    for item in items {
+        let height = match item {
+            MenuItem::Action(_) => item_height,
+            MenuItem::Separator => separator_height,
+        };
+        item_rects.push(AbsoluteRect::new(
+            AbsolutePoint::new(origin.x, advance),
+            Size::new(width, height).cast_unit(),
+        ));
+        advance += height;
+    }
+
+    (
+        AbsoluteRect::new(
+            origin,
+            Size::new(width, advance - origin.y + style.padding / 2.0).cast_unit(),
+        ),
+        item_rects,
+    )
+}
+
+/// Returns the index of the next enabled action after (or before, if `step` is negative) `from`, wrapping around.
+pub fn next_enabled_item(items: &[MenuItem], from: Option<usize>, step: isize) -> Option<usize> {
+    let len = items.len() as isize;
+    if len == 0 {
+        return None;
+    }
+
+    let mut index = match from {
+        Some(from) => from as isize,
+        None if step >= 0 => -1,
+        None => len,
+    };
+
+    for _ in 0..len {
+        index = (index + step).rem_euclid(len);
+        if items[index as usize].enabled_action().is_some() {
+            return Some(index as usize);
+        }
+    }
+
+    None
+}
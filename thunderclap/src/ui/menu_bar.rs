@@ -0,0 +1,666 @@
+//! Application menu bar widget.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state, HasTheme},
+        geom::*,
+        ui::{self, menu},
+    },
+    reclutch::{
+        display::{CommandGroup, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::RcEventQueue,
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::marker::PhantomData,
+};
+
+/// Events emitted by a menu bar.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum MenuBarEvent {
+    /// Emitted when a menu action is activated, carrying the action ID.
+    #[event_key(activate)]
+    Activate(String),
+    /// Emitted when a menu is opened, carrying the index of the menu.
+    #[event_key(open)]
+    Open(usize),
+    /// Emitted when the open menu is closed.
+    #[event_key(close)]
+    Close,
+}
+
+pub fn menu_bar_handler<U, G>() -> vg::UnboundQueueHandler<MenuBarWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        MenuBarWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let pos = event.get().0;
+            let title = obj.title_at(pos);
+            let in_menu = obj.menu_rect().map(|rect| rect.contains(pos)).unwrap_or(false);
+            if !obj.data.disabled {
+                obj.keyboard_mode = false;
+                if event
+                    .with(|(_, button, _)| {
+                        *button == base::MouseButton::Left && (title.is_some() || in_menu)
+                    })
+                    .is_some()
+                {
+                    if let Some(title) = title {
+                        if obj.open == Some(title) {
+                            obj.close_menu();
+                        } else {
+                            obj.open_menu(title);
+                        }
+                    }
+                } else {
+                    // Clicking anywhere else dismisses the open menu.
+                    obj.close_menu();
+                }
+            }
+        }
+
+        mouse_release => {
+            let pos = event.get().0;
+            let item = obj.item_at(pos);
+            let in_menu = obj.menu_rect().map(|rect| rect.contains(pos)).unwrap_or(false);
+            if event.with(|(_, button, _)| *button == base::MouseButton::Left && in_menu).is_some() {
+                if let Some(item) = item {
+                    obj.activate(item);
+                }
+            }
+        }
+
+        mouse_move => {
+            let pos = event.get().0;
+            let title = obj.title_at(pos);
+            let item = obj.item_at(pos);
+            let in_menu = obj.menu_rect().map(|rect| rect.contains(pos)).unwrap_or(false);
+
+            if let Some(title) = title {
+                if obj.open.is_some() && obj.open != Some(title) {
+                    obj.open_menu(title);
+                }
+            }
+
+            if !obj.keyboard_mode && obj.open.is_none() && obj.highlighted_title != title {
+                obj.highlighted_title = title;
+                obj.repaint();
+            }
+
+            if in_menu && obj.highlighted_item != item {
+                obj.highlighted_item = item;
+                obj.repaint();
+            }
+
+            event.with(|_| title.is_some() || in_menu);
+        }
+
+        key_press => {
+            let (key, modifiers) = *event.get();
+            if obj.data.disabled {
+                obj.alt_alone = false;
+            } else if key == base::KeyInput::LAlt || key == base::KeyInput::RAlt {
+                obj.alt_alone = true;
+                obj.set_alt_held(true);
+            } else {
+                obj.alt_alone = false;
+                if event.with(|&(key, modifiers)| obj.handle_key(key, modifiers)).is_none()
+                    && obj.keyboard_mode
+                    && obj.open.is_none()
+                    && !modifiers.alt
+                {
+                    // Keys which aren't handled by the menu bar end keyboard navigation.
+                    obj.keyboard_mode = false;
+                    obj.highlighted_title = None;
+                    obj.repaint();
+                }
+            }
+        }
+
+        key_release => {
+            let (key, _) = *event.get();
+            if key == base::KeyInput::LAlt || key == base::KeyInput::RAlt {
+                obj.set_alt_held(false);
+                if obj.alt_alone && !obj.data.disabled {
+                    obj.alt_alone = false;
+                    obj.toggle_keyboard_mode();
+                }
+            }
+        }
+
+        text_input => {
+            // While navigating with the keyboard, typed characters are mnemonics and shouldn't reach other widgets.
+            event.with(|_| obj.keyboard_mode || obj.open.is_some());
+        }
+    }
+}
+
+/// Bundles the painters for the bar itself and its menu popups so that both follow theme changes.
+struct MenuBarPainters {
+    bar: Box<dyn draw::Painter<state::MenuBarState>>,
+    menu: Box<dyn draw::Painter<state::MenuState>>,
+}
+
+impl draw::Themed for MenuBarPainters {
+    fn load_theme(&mut self, theme: &dyn draw::Theme, aux: &dyn base::GraphicalAuxiliary) {
+        self.bar.load_theme(theme, aux);
+        self.menu.load_theme(theme, aux);
+    }
+}
+
+/// Horizontal bar of top-level menus, which open as popups drawn over the rest of the window.
+///
+/// Menus can be opened by clicking their titles, with `Alt` and the mnemonic of the title,
+/// or by pressing and releasing `Alt` and then navigating with the arrow keys.
+#[derive(
+    WidgetChildren,
+    LayableWidget,
+    DropNotifier,
+    HasVisibility,
+    Repaintable,
+    Movable,
+    Resizable,
+    OperatesVerbGraph,
+)]
+#[widget_children_trait(base::WidgetChildren)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+pub struct MenuBarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub event_queue: RcEventQueue<MenuBarEvent>,
+    pub data: base::Observed<MenuBar>,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    painters: MenuBarPainters,
+    title_widths: Vec<f32>,
+    open: Option<usize>,
+    menu_geometry: Option<(AbsoluteRect, Vec<AbsoluteRect>)>,
+    highlighted_title: Option<usize>,
+    highlighted_item: Option<usize>,
+    keyboard_mode: bool,
+    alt_held: bool,
+    alt_alone: bool,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_visibility]
+    visibility: base::Visibility,
+    #[repaint_target]
+    command_group: CommandGroup,
+    #[repaint_target]
+    menu_command_group: CommandGroup,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+    #[widget_drop_event]
+    drop_event: RcEventQueue<base::DropEvent>,
+
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuBar {
+    pub menus: Vec<ui::Menu>,
+    pub style: ui::MenuStyle,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for MenuBar
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = MenuBarWidget<U, G>;
+}
+
+impl MenuBar {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        MenuBar { menus: Vec::new(), style: ui::MenuStyle::from_theme(theme), disabled: false }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        theme: &dyn draw::Theme,
+        u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> MenuBarWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            MenuBarWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.close_menu();
+                    obj.update_title_widths();
+                    obj.resize_from_theme();
+                    obj.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("menu_bar", menu_bar_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let mut menu_bar = MenuBarWidget {
+            event_queue: Default::default(),
+            data,
+
+            graph: graph.into(),
+            painters: MenuBarPainters { bar: theme.menu_bar(), menu: theme.menu() },
+            title_widths: Vec::new(),
+            open: None,
+            menu_geometry: None,
+            highlighted_title: None,
+            highlighted_item: None,
+            keyboard_mode: false,
+            alt_held: false,
+            alt_alone: false,
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            visibility: Default::default(),
+            command_group: Default::default(),
+            menu_command_group: Default::default(),
+            layout: Default::default(),
+            drop_event: Default::default(),
+
+            phantom_g: Default::default(),
+        };
+
+        menu_bar.update_title_widths();
+        menu_bar.resize_from_theme();
+
+        menu_bar
+    }
+}
+
+impl<U, G> MenuBarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn on_transform(&mut self) {
+        if let Some(open) = self.open {
+            self.menu_geometry = Some(self.compute_menu_geometry(open));
+        }
+        self.repaint();
+        base::LayableWidget::notify_layout(self);
+    }
+
+    fn update_title_widths(&mut self) {
+        let style = &self.data.style;
+        self.title_widths = self
+            .data
+            .menus
+            .iter()
+            .map(|menu| style.text_width(&menu::split_mnemonic(&menu.text).0) + style.padding * 2.0)
+            .collect();
+    }
+
+    /// Returns the absolute rectangle of each menu title.
+    fn title_rects(&self) -> Vec<AbsoluteRect> {
+        let abs_rect = self.abs_rect();
+        let mut advance = abs_rect.origin.x;
+        self.title_widths
+            .iter()
+            .map(|&width| {
+                let rect = AbsoluteRect::new(
+                    AbsolutePoint::new(advance, abs_rect.origin.y),
+                    Size::new(width, abs_rect.size.height).cast_unit(),
+                );
+                advance += width;
+                rect
+            })
+            .collect()
+    }
+
+    fn title_at(&self, pos: AbsolutePoint) -> Option<usize> {
+        self.title_rects().iter().position(|rect| rect.contains(pos))
+    }
+
+    fn menu_rect(&self) -> Option<AbsoluteRect> {
+        self.menu_geometry.as_ref().map(|(rect, _)| *rect)
+    }
+
+    fn item_at(&self, pos: AbsolutePoint) -> Option<usize> {
+        let open = self.open?;
+        let (_, item_rects) = self.menu_geometry.as_ref()?;
+        item_rects
+            .iter()
+            .position(|rect| rect.contains(pos))
+            .filter(|&index| self.data.menus[open].items[index].enabled_action().is_some())
+    }
+
+    fn compute_menu_geometry(&self, index: usize) -> (AbsoluteRect, Vec<AbsoluteRect>) {
+        let title = self.title_rects()[index];
+        menu::layout_menu(
+            &self.data.style,
+            &self.data.menus[index].items,
+            AbsolutePoint::new(title.min_x(), title.max_y()),
+            title.size.width,
+        )
+    }
+
+    fn open_menu(&mut self, index: usize) {
+        if index >= self.data.menus.len() {
+            return;
+        }
+
+        self.open = Some(index);
+        self.highlighted_title = Some(index);
+        self.highlighted_item = if self.keyboard_mode {
+            menu::next_enabled_item(&self.data.menus[index].items, None, 1)
+        } else {
+            None
+        };
+        self.menu_geometry = Some(self.compute_menu_geometry(index));
+        self.repaint();
+        self.event_queue.emit_owned(MenuBarEvent::Open(index));
+    }
+
+    fn close_menu(&mut self) {
+        if self.open.take().is_some() {
+            self.menu_geometry = None;
+            self.highlighted_item = None;
+            if !self.keyboard_mode {
+                self.highlighted_title = None;
+            }
+            self.repaint();
+            self.event_queue.emit_owned(MenuBarEvent::Close);
+        }
+    }
+
+    fn activate(&mut self, item: usize) {
+        let id = self.open.and_then(|open| {
+            self.data.menus[open].items.get(item)?.enabled_action().map(|action| action.id.clone())
+        });
+
+        self.keyboard_mode = false;
+        self.close_menu();
+        self.highlighted_title = None;
+        self.repaint();
+
+        if let Some(id) = id {
+            self.event_queue.emit_owned(MenuBarEvent::Activate(id));
+        }
+    }
+
+    fn set_alt_held(&mut self, held: bool) {
+        if self.alt_held != held {
+            self.alt_held = held;
+            self.repaint();
+        }
+    }
+
+    fn toggle_keyboard_mode(&mut self) {
+        if self.keyboard_mode || self.open.is_some() {
+            self.keyboard_mode = false;
+            self.close_menu();
+            self.highlighted_title = None;
+        } else if !self.data.menus.is_empty() {
+            self.keyboard_mode = true;
+            self.highlighted_title = Some(0);
+        }
+        self.repaint();
+    }
+
+    fn menu_with_mnemonic(&self, c: char) -> Option<usize> {
+        self.data.menus.iter().position(|menu| menu::mnemonic_char(&menu.text) == Some(c))
+    }
+
+    /// Handles a key press, returning `true` if the key was consumed.
+    fn handle_key(&mut self, key: base::KeyInput, modifiers: base::KeyModifiers) -> bool {
+        let menu_count = self.data.menus.len();
+        if menu_count == 0 {
+            return false;
+        }
+
+        if let Some(open) = self.open {
+            match key {
+                base::KeyInput::Left => {
+                    self.keyboard_mode = true;
+                    self.open_menu((open + menu_count - 1) % menu_count);
+                }
+                base::KeyInput::Right => {
+                    self.keyboard_mode = true;
+                    self.open_menu((open + 1) % menu_count);
+                }
+                base::KeyInput::Up | base::KeyInput::Down => {
+                    self.keyboard_mode = true;
+                    let step = if key == base::KeyInput::Up { -1 } else { 1 };
+                    self.highlighted_item = menu::next_enabled_item(
+                        &self.data.menus[open].items,
+                        self.highlighted_item,
+                        step,
+                    );
+                    self.repaint();
+                }
+                base::KeyInput::Return | base::KeyInput::NumpadEnter | base::KeyInput::Space => {
+                    if let Some(item) = self.highlighted_item {
+                        self.activate(item);
+                    }
+                }
+                base::KeyInput::Escape => {
+                    self.keyboard_mode = false;
+                    self.close_menu();
+                    self.highlighted_title = None;
+                    self.repaint();
+                }
+                _ => {
+                    let c = match menu::key_char(key) {
+                        Some(c) => c,
+                        None => return false,
+                    };
+
+                    if modifiers.alt {
+                        if let Some(index) = self.menu_with_mnemonic(c) {
+                            self.keyboard_mode = true;
+                            self.open_menu(index);
+                            return true;
+                        }
+                    }
+
+                    if let Some(item) = self.data.menus[open].items.iter().position(|item| {
+                        item.enabled_action()
+                            .map(|action| menu::mnemonic_char(&action.text) == Some(c))
+                            .unwrap_or(false)
+                    }) {
+                        self.activate(item);
+                    } else {
+                        return false;
+                    }
+                }
+            }
+
+            true
+        } else if self.keyboard_mode {
+            let highlighted = self.highlighted_title.unwrap_or(0);
+            match key {
+                base::KeyInput::Left => {
+                    self.highlighted_title = Some((highlighted + menu_count - 1) % menu_count);
+                    self.repaint();
+                }
+                base::KeyInput::Right => {
+                    self.highlighted_title = Some((highlighted + 1) % menu_count);
+                    self.repaint();
+                }
+                base::KeyInput::Down | base::KeyInput::Return | base::KeyInput::NumpadEnter => {
+                    self.open_menu(highlighted);
+                }
+                base::KeyInput::Escape => {
+                    self.toggle_keyboard_mode();
+                }
+                _ => match menu::key_char(key).and_then(|c| self.menu_with_mnemonic(c)) {
+                    Some(index) => self.open_menu(index),
+                    None => return false,
+                },
+            }
+
+            true
+        } else if modifiers.alt {
+            match menu::key_char(key).and_then(|c| self.menu_with_mnemonic(c)) {
+                Some(index) => {
+                    self.keyboard_mode = true;
+                    self.open_menu(index);
+                    true
+                }
+                None => false,
+            }
+        } else {
+            false
+        }
+    }
+
+    fn derive_state(&self) -> state::MenuBarState {
+        state::MenuBarState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            titles: self.title_rects(),
+            open: self.open,
+            highlighted: self.highlighted_title,
+            mnemonics: self.keyboard_mode || self.alt_held,
+        }
+    }
+
+    fn derive_menu_state(&self) -> Option<state::MenuState> {
+        let open = self.open?;
+        let (rect, item_rects) = self.menu_geometry.clone()?;
+        Some(state::MenuState {
+            rect,
+            items: self.data.menus[open].items.clone(),
+            item_rects,
+            highlighted: self.highlighted_item,
+            mnemonics: self.keyboard_mode || self.alt_held,
+            style: self.data.style.clone(),
+        })
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for MenuBarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> Widget for MenuBarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painters.bar.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let bar_state = self.derive_state();
+        let menu_state = self.derive_menu_state();
+
+        let painters = &mut self.painters;
+        self.command_group.push_with(
+            display,
+            || painters.bar.draw(bar_state),
+            Default::default(),
+            None,
+            None,
+        );
+        self.menu_command_group.push_with(
+            display,
+            || menu_state.map(|menu_state| painters.menu.draw(menu_state)).unwrap_or_default(),
+            ui::OVERLAY_Z_ORDER,
+            None,
+            None,
+        );
+    }
+}
+
+impl<U, G> StoresParentPosition for MenuBarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
+
+impl<U, G> HasTheme for MenuBarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn theme(&mut self) -> &mut dyn draw::Themed {
+        &mut self.painters
+    }
+
+    fn resize_from_theme(&mut self) {
+        let hint = self.painters.bar.size_hint(self.derive_state());
+        self.set_size(Size::new(self.rect.size.width.max(hint.width), hint.height));
+    }
+}
+
+impl<U, G> ui::DefaultEventQueue<MenuBarEvent> for MenuBarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_event_queue(&self) -> &RcEventQueue<MenuBarEvent> {
+        &self.event_queue
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<MenuBar> for MenuBarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<MenuBar> {
+        &mut self.data
+    }
+}
+
+impl<U, G> Drop for MenuBarWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn drop(&mut self) {
+        self.drop_event.emit_owned(base::DropEvent);
+    }
+}
@@ -1,25 +1,189 @@
 //! The main part of Thunderclap; a widget toolkit built atop Reclutch.
 
+/// Implements everything but the measurement and arrangement of a layout which holds a single child (in a `child: ui::SingleChild` field).
+///
+/// The widget supplies the rest through two inherent methods;
+/// - `measure_single(&self, constraints: Constraints, child: Size) -> Size`; the size of the widget within `constraints`, given the measured size of the child (zero without a child).
+/// - `arrange_single(&self, rect: AbsoluteRect, child: Size) -> (Constraints, AbsoluteRect)`; the constraints and rectangle of the child, given the rectangle of the widget.
+///
+/// The widget must also have `data`, `rect`, `layout`, `parent_position` and `graph` fields, and use `on_transform` as its transform callback.
+/// This is defined ahead of the modules below so that they can use it.
+macro_rules! single_child_layout {
+    ($name:ident, $data:ty) => {
+        impl<U, G> $name<U, G>
+        where
+            U: $crate::base::UpdateAuxiliary,
+            G: $crate::base::GraphicalAuxiliary,
+        {
+            fn resize_to_fit(&mut self) {
+                let size = $crate::base::Layout::measure_children(self, self.layout.constraints());
+                $crate::base::Resizable::set_size(self, size);
+            }
+
+            fn on_transform(&mut self) {
+                self.layout.mark_dirty();
+                $crate::base::LayableWidget::notify_layout(self);
+            }
+        }
+
+        impl<U, G> $crate::reclutch::verbgraph::HasVerbGraph for $name<U, G>
+        where
+            U: $crate::base::UpdateAuxiliary,
+            G: $crate::base::GraphicalAuxiliary,
+        {
+            fn verb_graph(&mut self) -> &mut $crate::reclutch::verbgraph::OptionVerbGraph<Self, U> {
+                &mut self.graph
+            }
+        }
+
+        impl<U, G> $crate::base::Layout for $name<U, G>
+        where
+            U: $crate::base::UpdateAuxiliary,
+            G: $crate::base::GraphicalAuxiliary,
+        {
+            type PushData = ();
+
+            fn push(&mut self, _data: Option<()>, child: &mut impl $crate::base::LayableWidget) {
+                self.child.push(&mut self.layout, child);
+                self.resize_to_fit();
+            }
+
+            fn remove(
+                &mut self,
+                child: &mut impl $crate::base::LayableWidget,
+                restore_original: bool,
+            ) {
+                self.child.remove(&mut self.layout, child, restore_original);
+            }
+
+            fn measure_children(
+                &self,
+                constraints: $crate::base::Constraints,
+            ) -> $crate::reclutch::display::Size {
+                let size = self.child.size().unwrap_or_else($crate::reclutch::display::Size::zero);
+                self.measure_single(constraints, size)
+            }
+        }
+
+        impl<U, G> $crate::reclutch::widget::Widget for $name<U, G>
+        where
+            U: $crate::base::UpdateAuxiliary + 'static,
+            G: $crate::base::GraphicalAuxiliary + 'static,
+        {
+            type UpdateAux = U;
+            type GraphicalAux = G;
+            type DisplayObject = $crate::reclutch::display::DisplayCommand;
+
+            fn bounds(&self) -> $crate::reclutch::display::Rect {
+                self.rect.cast_unit()
+            }
+
+            fn update(&mut self, aux: &mut U) {
+                let mut graph = self.graph.take().unwrap();
+                graph.update_all(self, aux);
+                self.graph = Some(graph);
+            }
+        }
+
+        impl<U, G> $crate::base::WidgetChildren for $name<U, G>
+        where
+            U: $crate::base::UpdateAuxiliary + 'static,
+            G: $crate::base::GraphicalAuxiliary + 'static,
+        {
+            fn relayout(&mut self) {
+                self.child.update(&mut self.layout);
+
+                if self.layout.is_dirty() {
+                    self.layout.invalidate();
+                    self.resize_to_fit();
+                    if let Some(size) = self.child.size() {
+                        let abs_rect = $crate::geom::ContextuallyRectangular::abs_rect(self);
+                        let (constraints, rect) = self.arrange_single(abs_rect, size);
+                        self.child.arrange(constraints, rect, self.layout.direction());
+                    }
+
+                    self.layout.clear_dirty();
+                    if self.layout.measured().is_none() {
+                        $crate::base::LayableWidget::notify_layout(self);
+                    }
+                }
+            }
+        }
+
+        impl<U, G> $crate::ui::DefaultWidgetData<$data> for $name<U, G>
+        where
+            U: $crate::base::UpdateAuxiliary,
+            G: $crate::base::GraphicalAuxiliary,
+        {
+            #[inline]
+            fn default_data(&mut self) -> &mut $crate::base::Observed<$data> {
+                &mut self.data
+            }
+        }
+
+        impl<U, G> $crate::geom::StoresParentPosition for $name<U, G>
+        where
+            U: $crate::base::UpdateAuxiliary,
+            G: $crate::base::GraphicalAuxiliary,
+        {
+            fn set_parent_position(&mut self, parent_pos: $crate::geom::AbsolutePoint) {
+                self.parent_position = parent_pos;
+                self.on_transform();
+            }
+
+            fn parent_position(&self) -> $crate::geom::AbsolutePoint {
+                self.parent_position
+            }
+        }
+    };
+}
+
+pub mod accordion;
+pub mod anchor;
+pub mod aspect_ratio;
 pub mod button;
+pub mod center;
 pub mod checkbox;
+pub mod collapsible;
+pub mod color_picker;
 pub mod container;
+pub mod date_picker;
+pub mod flow;
+pub mod grid;
 pub mod hstack;
 pub mod label;
+pub mod link;
 pub mod margins;
 pub mod max_fill;
+pub mod menu;
+pub mod menu_bar;
+pub mod picker;
+pub mod responsive;
+pub mod rich_label;
+pub mod search_field;
+pub mod sized_box;
+pub mod spin_box;
+pub mod split;
 pub mod text_area;
+pub mod text_edit;
+pub mod time_picker;
+pub mod toaster;
 pub mod vstack;
+pub mod zstack;
 
 pub use {
-    button::*, checkbox::*, container::*, hstack::*, label::*, margins::*, max_fill::*,
-    text_area::*, vstack::*,
+    accordion::*, anchor::*, aspect_ratio::*, button::*, center::*, checkbox::*, collapsible::*,
+    color_picker::*, container::*, date_picker::*, flow::*, grid::*, hstack::*, label::*, link::*,
+    margins::*, max_fill::*, menu::*, menu_bar::*, picker::*, responsive::*, rich_label::*,
+    search_field::*, sized_box::*, spin_box::*, split::*, text_area::*, text_edit::*,
+    time_picker::*, toaster::*, vstack::*, zstack::*,
 };
 
 use {
     crate::{base, draw::state, geom::*},
     reclutch::{
         display,
-        event::RcEventQueue,
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener, RcEventQueue},
         verbgraph::{unbound_queue_handler, UnboundQueueHandler},
     },
 };
@@ -69,6 +233,7 @@ macro_rules! define_layout {
 
 /// How a child should be aligned within a layout.
 /// On which axis the align applies to depends on the layout.
+/// Horizontally, `Begin` and `End` are swapped within a right-to-left layout (see `base::LayoutDirection`).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Align {
     /// The child is aligned to the beginning of the layout.
@@ -87,6 +252,159 @@ impl Default for Align {
     }
 }
 
+/// Mirrors `rect` horizontally within `within` if `direction` is right-to-left.
+pub(crate) fn mirror(
+    rect: AbsoluteRect,
+    within: AbsoluteRect,
+    direction: base::LayoutDirection,
+) -> AbsoluteRect {
+    match direction {
+        base::LayoutDirection::Ltr => rect,
+        base::LayoutDirection::Rtl => AbsoluteRect::new(
+            AbsolutePoint::new(
+                within.max_x() - (rect.origin.x - within.origin.x) - rect.size.width,
+                rect.origin.y,
+            ),
+            rect.size,
+        ),
+    }
+}
+
+/// Returns the position and length of a child of length `length` aligned within `start..start + space`.
+pub(crate) fn align_within(alignment: Align, start: f32, space: f32, length: f32) -> (f32, f32) {
+    match alignment {
+        Align::Begin => (start, length),
+        Align::Middle => (start + ((space - length) / 2.0).round(), length),
+        Align::End => (start + space - length, length),
+        Align::Stretch => (start, space),
+    }
+}
+
+/// Returns a rectangle of `size` centered within `within`.
+pub(crate) fn center_within(size: display::Size, within: AbsoluteRect) -> AbsoluteRect {
+    let (x, width) = align_within(Align::Middle, within.origin.x, within.size.width, size.width);
+    let (y, height) = align_within(Align::Middle, within.origin.y, within.size.height, size.height);
+    AbsoluteRect::new(AbsolutePoint::new(x, y), display::Size::new(width, height).cast_unit())
+}
+
+/// How leftover space along the main axis of a stack is distributed between its children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Justify {
+    /// Children are packed at the beginning of the stack.
+    Start,
+    /// Children are packed in the middle of the stack.
+    Center,
+    /// Children are packed at the end of the stack.
+    End,
+    /// The first and last children touch the edges of the stack, with equal space between each child.
+    SpaceBetween,
+    /// Equal space is placed around each child, so the edges get half the space found between children.
+    SpaceAround,
+    /// Equal space is placed between each child and the edges of the stack.
+    SpaceEvenly,
+}
+
+impl Default for Justify {
+    fn default() -> Self {
+        Justify::Start
+    }
+}
+
+impl Justify {
+    /// Returns the offset of the first child and the extra space placed between each child,
+    /// given the `leftover` space and the amount of children.
+    pub(crate) fn distribute(self, leftover: f32, count: usize) -> (f32, f32) {
+        let leftover = leftover.max(0.0);
+        match self {
+            Justify::Start => (0.0, 0.0),
+            Justify::Center => ((leftover / 2.0).round(), 0.0),
+            Justify::End => (leftover, 0.0),
+            Justify::SpaceBetween if count > 1 => (0.0, leftover / (count - 1) as f32),
+            Justify::SpaceBetween => (0.0, 0.0),
+            Justify::SpaceAround if count > 0 => {
+                let spacing = leftover / count as f32;
+                (spacing / 2.0, spacing)
+            }
+            Justify::SpaceAround => (0.0, 0.0),
+            Justify::SpaceEvenly => {
+                let spacing = leftover / (count + 1) as f32;
+                (spacing, spacing)
+            }
+        }
+    }
+}
+
+/// Main-axis sizing parameters of a single stack child, used by `flex_sizes`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct FlexItem {
+    /// Size the child would like to be.
+    pub basis: f32,
+    pub grow: f32,
+    pub shrink: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Sizes children along the main axis of a stack so that they fill `available` space.
+///
+/// Extra space is given out in proportion to `grow`, and missing space is taken in proportion to `shrink` (weighted by `basis`).
+/// Children which reach their `min`/`max` are frozen and the remainder is redistributed among the others.
+pub(crate) fn flex_sizes(items: &[FlexItem], available: f32) -> Vec<f32> {
+    let mut sizes: Vec<f32> =
+        items.iter().map(|item| item.basis.max(item.min).min(item.max)).collect();
+    let mut frozen = vec![false; items.len()];
+
+    loop {
+        let free = available - sizes.iter().sum::<f32>();
+        if free.abs() < 0.01 {
+            break;
+        }
+
+        let weight = |item: &FlexItem| {
+            if free > 0.0 {
+                item.grow.max(0.0)
+            } else {
+                item.shrink.max(0.0) * item.basis
+            }
+        };
+        let total: f32 = items
+            .iter()
+            .zip(&frozen)
+            .filter(|(_, &frozen)| !frozen)
+            .map(|(item, _)| weight(item))
+            .sum();
+        if total <= 0.0 {
+            break;
+        }
+
+        let mut clamped = false;
+        for (i, item) in items.iter().enumerate() {
+            if frozen[i] || weight(item) <= 0.0 {
+                continue;
+            }
+
+            let target = sizes[i] + free * weight(item) / total;
+            let bounded = target.max(item.min).min(item.max);
+            if bounded != target {
+                frozen[i] = true;
+                clamped = true;
+            }
+            sizes[i] = bounded;
+        }
+
+        if !clamped {
+            break;
+        }
+    }
+
+    sizes
+}
+
+/// Z-order of display commands which are drawn over the rest of the UI, such as popup menus.
+///
+/// These commands aren't clipped to the bounds of the widget which draws them.
+pub const OVERLAY_Z_ORDER: display::ZOrder = display::ZOrder(std::i32::MAX / 2);
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum InteractionEvent {
     Pressed(AbsolutePoint),
@@ -116,6 +434,119 @@ pub trait DefaultEventQueue<E> {
     fn default_event_queue(&self) -> &RcEventQueue<E>;
 }
 
+#[derive(Debug)]
+struct SingleChildData {
+    evq: BidirSingleEventQueue<base::LayoutEvent, base::LayoutEvent>,
+    node: base::LayoutNode,
+    drop_listener: RcEventListener<base::DropEvent>,
+    rect: AbsoluteRect,
+    /// Size last measured by the child.
+    size: display::Size,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+/// The child of a layout which holds a single child (see `single_child_layout`).
+///
+/// Pushing another child replaces the previous one.
+#[derive(Debug, Default)]
+pub(crate) struct SingleChild {
+    data: Option<SingleChildData>,
+    next_id: u64,
+}
+
+impl SingleChild {
+    pub(crate) fn push(
+        &mut self,
+        layout: &mut base::WidgetLayoutEvents,
+        child: &mut impl base::LayableWidget,
+    ) {
+        layout.mark_dirty();
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            layout_node: layout.node().clone(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.data = Some(SingleChildData {
+            evq,
+            node: child.layout_events().node().clone(),
+            drop_listener: child.drop_event().listen(),
+            rect,
+            size: rect.size.cast_unit(),
+            original_rect: rect,
+            id,
+        });
+    }
+
+    pub(crate) fn remove(
+        &mut self,
+        layout: &mut base::WidgetLayoutEvents,
+        child: &mut impl base::LayableWidget,
+        restore_original: bool,
+    ) {
+        if child.layout_id().is_some()
+            && child.layout_id() == self.data.as_ref().map(|data| data.id)
+        {
+            let data = self.data.take().unwrap();
+            layout.mark_dirty();
+            child.listen_to_layout(None);
+            if restore_original {
+                child.arrange(data.original_rect);
+            }
+        }
+    }
+
+    /// Returns the size last measured by the child, if there is one.
+    #[inline]
+    pub(crate) fn size(&self) -> Option<display::Size> {
+        self.data.as_ref().map(|data| data.size)
+    }
+
+    /// Handles the child being dropped or measuring itself differently, marking `layout` dirty if either happened.
+    pub(crate) fn update(&mut self, layout: &mut base::WidgetLayoutEvents) {
+        if layout.direction_changed() {
+            layout.mark_dirty();
+        }
+
+        if self.data.as_ref().map(|data| !data.drop_listener.peek().is_empty()).unwrap_or(false) {
+            self.data = None;
+            layout.mark_dirty();
+        }
+        if let Some(data) = &mut self.data {
+            if let Some(base::LayoutEvent::Measure { rect, size }) = data.evq.retrieve_newest() {
+                if rect != data.rect || size != data.size {
+                    layout.mark_dirty();
+                    data.rect = rect;
+                    data.size = size;
+                }
+            }
+        }
+    }
+
+    /// Arranges the child (if there is one) into `rect`.
+    pub(crate) fn arrange(
+        &mut self,
+        constraints: base::Constraints,
+        rect: AbsoluteRect,
+        direction: base::LayoutDirection,
+    ) {
+        if let Some(data) = &mut self.data {
+            data.evq.emit_owned(base::LayoutEvent::Arrange { constraints, rect, direction });
+            data.node.invalidate();
+            data.rect = rect;
+        }
+    }
+}
+
 pub trait DefaultWidgetData<D> {
     fn default_data(&mut self) -> &mut base::Observed<D>;
 }
@@ -0,0 +1,90 @@
+//! Visual properties and geometry shared by widgets which pop up from a compact field (such as `DatePicker` and `TimePicker`).
+
+use {
+    crate::{draw, geom::*},
+    reclutch::display::{Color, Size, StyleColor, TextDisplayItem},
+};
+
+/// Width reserved for the drop-down indicator on the right of a picker field.
+pub const INDICATOR_WIDTH: f32 = 20.0;
+
+/// Visual properties shared by picker widgets and their popups.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickerStyle {
+    pub typeface: draw::TypefaceStyle,
+    /// Color of text.
+    pub color: Color,
+    /// Color of text which can't be selected.
+    pub disabled_color: Color,
+    /// Background color of the field.
+    pub background: Color,
+    /// Background color of the popup.
+    pub popup_background: Color,
+    /// Background color of the selected (or hovered) entry.
+    pub highlight: Color,
+    /// Color of text over `highlight`.
+    pub over_highlight: Color,
+    /// Color of the focus ring.
+    pub focus: Color,
+    pub contrast: draw::ThemeContrast,
+    /// Padding placed around text.
+    pub padding: f32,
+}
+
+impl PickerStyle {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        PickerStyle {
+            typeface: data.typography.body.clone(),
+            color: data.scheme.over_control_inset,
+            disabled_color: draw::weaken(data.scheme.over_control_inset, 0.4, data.contrast),
+            background: data.scheme.control_inset,
+            popup_background: data.scheme.control_outset,
+            highlight: data.scheme.primary,
+            over_highlight: data.scheme.over_primary,
+            focus: data.scheme.focus,
+            contrast: data.contrast,
+            padding: 6.0,
+        }
+    }
+
+    pub fn text_item(&self, text: &str, color: StyleColor) -> TextDisplayItem {
+        let font = self.typeface.typeface.pick(self.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: font.0,
+            font_info: font.1,
+            size: self.typeface.size,
+            bottom_left: Default::default(),
+            color,
+        }
+    }
+
+    /// Returns the height of a single line of text.
+    pub fn line_height(&self) -> f32 {
+        let font = self.typeface.typeface.pick(self.typeface.style);
+        let metrics = font.1.font.metrics();
+        (metrics.ascent - metrics.descent) / metrics.units_per_em as f32 * self.typeface.size
+    }
+
+    pub fn text_width(&self, text: &str) -> f32 {
+        if text.is_empty() {
+            0.0
+        } else {
+            self.text_item(text, Color::default().into()).bounds().unwrap().size.width
+        }
+    }
+
+    /// Returns the size of a field which fits `text`.
+    pub fn field_size(&self, text: &str) -> Size {
+        Size::new(
+            self.text_width(text) + self.padding * 2.0 + INDICATOR_WIDTH,
+            self.line_height() + self.padding * 2.0,
+        )
+    }
+}
+
+/// Returns the origin of a popup opened from `field`, placed directly below it.
+pub fn popup_origin(field: AbsoluteRect) -> AbsolutePoint {
+    AbsolutePoint::new(field.min_x(), field.max_y() + 2.0)
+}
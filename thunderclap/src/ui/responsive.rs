@@ -0,0 +1,283 @@
+use {
+    crate::{
+        base::{self, HasVisibility, Movable, Repaintable, WidgetChildren},
+        draw,
+        geom::*,
+        ui,
+    },
+    reclutch::{display::DisplayCommand, event::RcEventQueue, prelude::*, verbgraph as vg},
+    std::marker::PhantomData,
+};
+
+/// Events emitted by a responsive container.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResponsiveEvent {
+    /// A different branch became active, due to the window width crossing a breakpoint.
+    #[event_key(switch)]
+    Switch(usize),
+}
+
+pub fn responsive_handler<U, G>(
+) -> vg::UnboundQueueHandler<ResponsiveWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        ResponsiveWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        resize => {
+            obj.width = event.width;
+            obj.update_branch();
+        }
+    }
+}
+
+lazy_widget! {
+    generic ResponsiveWidget,
+    visibility: visibility,
+    theme: themed,
+    drop_event: drop_event
+}
+
+/// Container which switches between alternative children ("branches") depending on the width of the window.
+///
+/// Only the active branch is updated and drawn; the others are given `Visibility::None`.
+/// To change layout push data instead of swapping out children, listen for `ResponsiveEvent::Switch`
+/// and re-push the affected children with the new data.
+#[derive(Movable, Resizable, OperatesVerbGraph)]
+#[thunderclap_crate(crate)]
+pub struct ResponsiveWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub event_queue: RcEventQueue<ResponsiveEvent>,
+    pub data: base::Observed<Responsive>,
+
+    branches: Vec<
+        Box<
+            dyn base::WidgetChildren<
+                UpdateAux = U,
+                GraphicalAux = G,
+                DisplayObject = DisplayCommand,
+            >,
+        >,
+    >,
+    width: f32,
+    active: usize,
+
+    themed: draw::PhantomThemed,
+    visibility: base::Visibility,
+    drop_event: RcEventQueue<base::DropEvent>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Responsive {
+    /// Window widths, in ascending order, at which the next branch becomes active.
+    ///
+    /// For example, `vec![800.0]` activates the first branch below 800 pixels and the second branch at or above 800 pixels.
+    pub breakpoints: Vec<f32>,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Responsive
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = ResponsiveWidget<U, G>;
+}
+
+impl Responsive {
+    pub fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        Responsive { breakpoints: Vec::new() }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        _theme: &dyn draw::Theme,
+        u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> ResponsiveWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            ResponsiveWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.update_branch();
+                }
+            }
+        };
+
+        graph = graph.add("responsive", responsive_handler::<U, G>().bind(u_aux.window_queue()));
+
+        ResponsiveWidget {
+            event_queue: Default::default(),
+            data,
+
+            branches: Vec::new(),
+            width: 0.0,
+            active: 0,
+
+            themed: Default::default(),
+            visibility: Default::default(),
+            drop_event: Default::default(),
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+
+            graph: graph.into(),
+            phantom_g: Default::default(),
+        }
+    }
+}
+
+impl<U, G> ResponsiveWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Moves a branch into the container.
+    /// Branches are activated in the order they're pushed, from narrowest to widest.
+    pub fn push(
+        &mut self,
+        mut child: impl base::WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>
+            + 'static,
+    ) {
+        child.set_visibility(if self.branches.len() == self.active {
+            base::Visibility::Normal
+        } else {
+            base::Visibility::None
+        });
+        self.branches.push(Box::new(child));
+        self.update_branch();
+    }
+
+    /// Returns the index of the active branch.
+    #[inline]
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// Returns the index of the branch which should be active within a window of the given width.
+    fn branch_for(&self, width: f32) -> usize {
+        let index = self.data.breakpoints.iter().filter(|&&breakpoint| width >= breakpoint).count();
+        index.min(self.branches.len().saturating_sub(1))
+    }
+
+    fn update_branch(&mut self) {
+        let active = self.branch_for(self.width);
+        if active == self.active {
+            return;
+        }
+
+        self.active = active;
+        for (i, branch) in self.branches.iter_mut().enumerate() {
+            branch.set_visibility(if i == active {
+                base::Visibility::Normal
+            } else {
+                base::Visibility::None
+            });
+            branch.repaint();
+        }
+
+        // Hidden branches are skipped by the relayout pass, so the shown branch has to be layed out again.
+        if let Some(node) = self.branches.get(active).and_then(|branch| branch.layout_node()) {
+            node.invalidate();
+        }
+
+        self.event_queue.emit_owned(ResponsiveEvent::Switch(active));
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for ResponsiveWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U: base::UpdateAuxiliary, G: base::GraphicalAuxiliary> Widget for ResponsiveWidget<U, G> {
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        base::invoke_update(self, aux);
+
+        let rect =
+            self.branches.get(self.active).map(|branch| branch.abs_bounds()).unwrap_or_default();
+        self.set_ctxt_rect(rect);
+    }
+}
+
+impl<U: base::UpdateAuxiliary, G: base::GraphicalAuxiliary> WidgetChildren
+    for ResponsiveWidget<U, G>
+{
+    fn children(
+        &self,
+    ) -> Vec<
+        &dyn base::WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>,
+    > {
+        self.branches.iter().map(|child| child.as_ref() as _).collect()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut dyn base::WidgetChildren<
+            UpdateAux = U,
+            GraphicalAux = G,
+            DisplayObject = DisplayCommand,
+        >,
+    > {
+        self.branches.iter_mut().map(|child| child.as_mut() as _).collect()
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<Responsive> for ResponsiveWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<Responsive> {
+        &mut self.data
+    }
+}
+
+impl<U, G> StoresParentPosition for ResponsiveWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
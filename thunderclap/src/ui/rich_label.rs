@@ -0,0 +1,696 @@
+//! Label widget which displays a sequence of individually styled spans.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw,
+        geom::*,
+        ui::{self, TextAlign},
+    },
+    reclutch::{
+        display::{
+            Color, CommandGroup, DisplayCommand, DisplayListBuilder, GraphicsDisplay,
+            GraphicsDisplayPaint, GraphicsDisplayStroke, Point, Rect, Size, TextDisplayItem,
+        },
+        event::RcEventQueue,
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::marker::PhantomData,
+};
+
+/// Events emitted by a rich label.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum RichLabelEvent {
+    /// Emitted when a span with a `link` is clicked, carrying the link.
+    #[event_key(link_activated)]
+    LinkActivated(String),
+}
+
+/// A run of text sharing the same style within a [`RichLabel`](struct.RichLabel.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextSpan {
+    pub text: String,
+    /// Weight and italics of the text.
+    pub style: draw::TextStyle,
+    /// Text size in pixels. If `None`, the size of the label's typeface is used.
+    pub size: Option<f32>,
+    /// Color of the text. If `None`, the label's `color` (or `link_color` for links) is used.
+    pub color: Option<Color>,
+    pub underline: bool,
+    pub strikethrough: bool,
+    /// Color highlighted behind the text.
+    pub background: Option<Color>,
+    /// Whether the span is drawn with the label's `code_typeface` and `code_background`.
+    pub code: bool,
+    /// URL (or any other identifier) emitted through `RichLabelEvent::LinkActivated` when the span is clicked.
+    pub link: Option<String>,
+}
+
+impl TextSpan {
+    /// Creates a span of regular text with the default style of the label.
+    pub fn new(text: impl Into<String>) -> Self {
+        TextSpan {
+            text: text.into(),
+            style: draw::TextStyle::Regular,
+            size: None,
+            color: None,
+            underline: false,
+            strikethrough: false,
+            background: None,
+            code: false,
+            link: None,
+        }
+    }
+}
+
+/// Parses a small subset of Markdown-like markup into spans.
+///
+/// The following is supported:
+/// - `**bold**`
+/// - `*italic*`
+/// - `` `code` `` (markup isn't parsed within code)
+/// - `[text](url)`, where `text` may itself contain bold, italic or code
+/// - `\` escapes the following character
+///
+/// Unterminated links are kept as plain text, and unterminated styles simply extend to the end of the text.
+pub fn parse_markup(markup: &str) -> Vec<TextSpan> {
+    fn flush(spans: &mut Vec<TextSpan>, buffer: &mut String, bold: bool, italic: bool, code: bool) {
+        if buffer.is_empty() {
+            return;
+        }
+        let mut span = TextSpan::new(std::mem::take(buffer));
+        span.style = match (bold, italic) {
+            (false, false) => draw::TextStyle::Regular,
+            (false, true) => draw::TextStyle::RegularItalic,
+            (true, false) => draw::TextStyle::Bold,
+            (true, true) => draw::TextStyle::BoldItalic,
+        };
+        span.code = code;
+        spans.push(span);
+    }
+
+    let mut spans = Vec::new();
+    let mut buffer = String::new();
+    let (mut bold, mut italic, mut code) = (false, false, false);
+    // Index of the first span within the link which is currently open.
+    let mut link_start: Option<usize> = None;
+
+    let chars: Vec<char> = markup.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if i + 1 < chars.len() => {
+                buffer.push(chars[i + 1]);
+                i += 1;
+            }
+            '`' => {
+                flush(&mut spans, &mut buffer, bold, italic, code);
+                code = !code;
+            }
+            _ if code => buffer.push(c),
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                flush(&mut spans, &mut buffer, bold, italic, code);
+                bold = !bold;
+                i += 1;
+            }
+            '*' => {
+                flush(&mut spans, &mut buffer, bold, italic, code);
+                italic = !italic;
+            }
+            '[' if link_start.is_none() => {
+                flush(&mut spans, &mut buffer, bold, italic, code);
+                link_start = Some(spans.len());
+            }
+            ']' if link_start.is_some() && chars.get(i + 1) == Some(&'(') => {
+                if let Some(end) = chars[i + 2..].iter().position(|&c| c == ')') {
+                    flush(&mut spans, &mut buffer, bold, italic, code);
+                    let url: String = chars[i + 2..i + 2 + end].iter().collect();
+                    for span in &mut spans[link_start.take().unwrap()..] {
+                        span.link = Some(url.clone());
+                    }
+                    i += end + 2;
+                } else {
+                    buffer.push(c);
+                }
+            }
+            _ => buffer.push(c),
+        }
+        i += 1;
+    }
+
+    flush(&mut spans, &mut buffer, bold, italic, code);
+
+    if let Some(start) = link_start {
+        spans.insert(start, TextSpan::new("["));
+    }
+
+    spans
+}
+
+/// A positioned piece of a span, confined to a single line.
+#[derive(Debug, Clone)]
+struct Fragment {
+    span: usize,
+    item: TextDisplayItem,
+    /// Rectangle covering the ascent and descent of the text.
+    rect: AbsoluteRect,
+}
+
+/// Label widget which displays a sequence of spans, each with its own style, wrapped and clipped within a rectangle.
+///
+/// Wrapping occurs at whitespace and works across span boundaries.
+/// If `wrap` is disabled (or the label has no width), the width of the label is fitted to the text.
+/// Either way, the height of the label is fitted to the text.
+#[derive(
+    WidgetChildren,
+    LayableWidget,
+    HasVisibility,
+    Repaintable,
+    Movable,
+    Resizable,
+    DropNotifier,
+    OperatesVerbGraph,
+)]
+#[widget_children_trait(base::WidgetChildren)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+pub struct RichLabelWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    pub event_queue: RcEventQueue<RichLabelEvent>,
+    pub data: base::Observed<RichLabel>,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    fragments: Vec<Fragment>,
+    /// Span index of the hovered link.
+    hovered_link: Option<usize>,
+    /// Span index of the link which the mouse was pressed on.
+    pressed_link: Option<usize>,
+    previous_rect: RelativeRect,
+    dirty: bool,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[repaint_target]
+    command_group: CommandGroup,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+    #[widget_visibility]
+    visibility: base::Visibility,
+    #[widget_drop_event]
+    drop_event: RcEventQueue<base::DropEvent>,
+
+    themed: draw::PhantomThemed,
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RichLabel {
+    pub spans: Vec<TextSpan>,
+    /// Typeface of spans, and the text size of spans without a `size`.
+    pub typeface: draw::TypefaceStyle,
+    /// Typeface of `code` spans. Ideally this is a monospace typeface.
+    pub code_typeface: draw::Typeface,
+    /// Color of text.
+    pub color: Color,
+    /// Color of links.
+    pub link_color: Color,
+    /// Background color of `code` spans without a `background`.
+    pub code_background: Color,
+    pub align: TextAlign,
+    pub wrap: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for RichLabel
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    type Target = RichLabelWidget<U, G>;
+}
+
+impl RichLabel {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        RichLabel {
+            spans: Vec::new(),
+            typeface: data.typography.body.clone(),
+            code_typeface: data.typography.body.typeface.clone(),
+            color: data.scheme.over_control_outset,
+            link_color: data.scheme.primary,
+            code_background: draw::strengthen(data.scheme.control_inset, 0.1, data.contrast),
+            align: TextAlign::Left,
+            wrap: true,
+        }
+    }
+
+    /// Replaces the spans with those parsed from `markup` (see [`parse_markup`](fn.parse_markup.html)).
+    pub fn set_markup(&mut self, markup: &str) {
+        self.spans = parse_markup(markup);
+    }
+
+    pub fn construct<U, G>(
+        self,
+        _theme: &dyn draw::Theme,
+        u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> RichLabelWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            RichLabelWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.hovered_link = None;
+                    obj.pressed_link = None;
+                    obj.update_fragments();
+                    obj.repaint();
+                }
+            }
+        };
+
+        graph = graph.add("links", rich_label_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let mut label = RichLabelWidget {
+            event_queue: Default::default(),
+            data,
+
+            graph: graph.into(),
+            fragments: Vec::new(),
+            hovered_link: None,
+            pressed_link: None,
+            previous_rect: Default::default(),
+            dirty: true,
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            command_group: Default::default(),
+            layout: Default::default(),
+            visibility: Default::default(),
+            drop_event: Default::default(),
+
+            themed: Default::default(),
+            phantom_g: Default::default(),
+        };
+
+        label.update_fragments();
+        label.previous_rect = label.rect;
+
+        label
+    }
+}
+
+pub fn rich_label_handler<U, G>(
+) -> vg::UnboundQueueHandler<RichLabelWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        RichLabelWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                *button == base::MouseButton::Left && obj.link_at(*pos).is_some()
+            }) {
+                obj.pressed_link = obj.link_at(*pos);
+            }
+        }
+
+        mouse_release => {
+            if let Some(pressed) = obj.pressed_link.take() {
+                if let Some((pos, _, _)) =
+                    event.with(|(_, button, _)| *button == base::MouseButton::Left)
+                {
+                    if obj.link_at(*pos) == Some(pressed) {
+                        if let Some(link) = obj.data.spans[pressed].link.clone() {
+                            obj.event_queue.emit_owned(RichLabelEvent::LinkActivated(link));
+                        }
+                    }
+                }
+            }
+        }
+
+        mouse_move => {
+            let hovered = obj.link_at(event.get().0);
+            if hovered.is_some() {
+                event.with(|_| true);
+            }
+            if hovered != obj.hovered_link {
+                obj.hovered_link = hovered;
+                obj.repaint();
+            }
+        }
+    }
+}
+
+impl<U: base::UpdateAuxiliary, G: base::GraphicalAuxiliary> RichLabelWidget<U, G> {
+    /// Returns the span index of the link under `pos`, if any.
+    fn link_at(&self, pos: AbsolutePoint) -> Option<usize> {
+        self.fragments
+            .iter()
+            .find(|fragment| fragment.rect.contains(pos))
+            .map(|fragment| fragment.span)
+            .filter(|&span| self.data.spans[span].link.is_some())
+    }
+
+    fn on_transform(&mut self) {
+        if self.previous_rect.size.width != self.rect.size.width {
+            self.dirty = true;
+        } else if self.previous_rect.origin != self.rect.origin {
+            let diff = self.rect.origin - self.previous_rect.origin;
+            for fragment in &mut self.fragments {
+                fragment.item.bottom_left += diff.cast_unit();
+                fragment.rect.origin += diff.cast_unit();
+            }
+        }
+
+        self.previous_rect = self.rect;
+        base::LayableWidget::notify_layout(self);
+        self.repaint();
+    }
+
+    fn text_item(&self, span: &TextSpan, text: String) -> TextDisplayItem {
+        let typeface =
+            if span.code { &self.data.code_typeface } else { &self.data.typeface.typeface };
+        let font = typeface.pick(span.style);
+        let color = match (span.color, &span.link) {
+            (Some(color), _) => color,
+            (None, Some(_)) => self.data.link_color,
+            (None, None) => self.data.color,
+        };
+        TextDisplayItem {
+            text: text.into(),
+            font: font.0,
+            font_info: font.1,
+            size: span.size.unwrap_or(self.data.typeface.size),
+            bottom_left: Default::default(),
+            color: color.into(),
+        }
+    }
+
+    fn text_width(&self, span: &TextSpan, text: &str) -> f32 {
+        if text.is_empty() {
+            0.0
+        } else {
+            self.text_item(span, text.to_string()).bounds().unwrap().size.width
+        }
+    }
+
+    /// Lays out the spans into lines of fragments and fits the label to the text.
+    fn update_fragments(&mut self) {
+        struct Line {
+            /// Span index, text, x offset and width of each fragment.
+            fragments: Vec<(usize, String, f32, f32)>,
+            /// Width excluding trailing whitespace.
+            width: f32,
+            /// Width including trailing whitespace.
+            advance: f32,
+        }
+
+        let origin = self.abs_rect().origin;
+        let max_width = if self.data.wrap && self.rect.size.width > 0.0 {
+            Some(self.rect.size.width)
+        } else {
+            None
+        };
+
+        let mut lines = Vec::new();
+        let mut line = Line { fragments: Vec::new(), width: 0.0, advance: 0.0 };
+
+        for (span_index, span) in self.data.spans.iter().enumerate() {
+            for (line_index, text) in span.text.split('\n').enumerate() {
+                if line_index > 0 {
+                    lines.push(std::mem::replace(
+                        &mut line,
+                        Line { fragments: Vec::new(), width: 0.0, advance: 0.0 },
+                    ));
+                }
+
+                // Words, including their trailing whitespace.
+                let mut words = Vec::new();
+                let mut start = 0;
+                let mut in_whitespace = false;
+                for (i, c) in text.char_indices() {
+                    if c.is_whitespace() {
+                        in_whitespace = true;
+                    } else if in_whitespace {
+                        words.push(&text[start..i]);
+                        start = i;
+                        in_whitespace = false;
+                    }
+                }
+                if start < text.len() {
+                    words.push(&text[start..]);
+                }
+
+                for word in words {
+                    let advance = self.text_width(span, word);
+                    let width = self.text_width(span, word.trim_end());
+
+                    if let Some(max_width) = max_width {
+                        if !line.fragments.is_empty() && line.advance + width > max_width {
+                            lines.push(std::mem::replace(
+                                &mut line,
+                                Line { fragments: Vec::new(), width: 0.0, advance: 0.0 },
+                            ));
+                        }
+                    }
+
+                    match line.fragments.last_mut() {
+                        Some(last) if last.0 == span_index => {
+                            last.1.push_str(word);
+                            last.3 += advance;
+                        }
+                        _ => line.fragments.push((
+                            span_index,
+                            word.to_string(),
+                            line.advance,
+                            advance,
+                        )),
+                    }
+
+                    line.width = line.advance + width;
+                    line.advance += advance;
+                }
+            }
+        }
+        if !line.fragments.is_empty() {
+            lines.push(line);
+        }
+
+        let available_width =
+            max_width.unwrap_or_else(|| lines.iter().map(|line| line.width).fold(0.0, f32::max));
+
+        let mut fragments = Vec::new();
+        let mut y = origin.y;
+        for line in lines {
+            // Ascent, descent and line gap of each fragment, in pixels.
+            let metrics: Vec<(f32, f32, f32)> = line
+                .fragments
+                .iter()
+                .map(|(span_index, _, _, _)| {
+                    let span = &self.data.spans[*span_index];
+                    let typeface = if span.code {
+                        &self.data.code_typeface
+                    } else {
+                        &self.data.typeface.typeface
+                    };
+                    let metrics = typeface.pick(span.style).1.font.metrics();
+                    let scale =
+                        span.size.unwrap_or(self.data.typeface.size) / metrics.units_per_em as f32;
+                    (metrics.ascent * scale, -metrics.descent * scale, metrics.line_gap * scale)
+                })
+                .collect();
+
+            let ascent = metrics.iter().map(|m| m.0).fold(0.0, f32::max);
+            let descent = metrics.iter().map(|m| m.1).fold(0.0, f32::max);
+            let line_gap = metrics.iter().map(|m| m.2).fold(0.0, f32::max);
+            let baseline = y + ascent;
+
+            let left = origin.x
+                + match self.data.align {
+                    TextAlign::Left => 0.0,
+                    TextAlign::Middle => (available_width - line.width) / 2.0,
+                    TextAlign::Right => available_width - line.width,
+                };
+
+            for ((span_index, text, x, width), (ascent, descent, _)) in
+                line.fragments.into_iter().zip(metrics)
+            {
+                let mut item = self.text_item(&self.data.spans[span_index], text);
+                item.bottom_left = Point::new(left + x, baseline);
+                fragments.push(Fragment {
+                    span: span_index,
+                    item,
+                    rect: AbsoluteRect::new(
+                        AbsolutePoint::new(left + x, baseline - ascent),
+                        Size::new(width, ascent + descent).cast_unit(),
+                    ),
+                });
+            }
+
+            y = baseline + descent + line_gap;
+        }
+
+        self.fragments = fragments;
+
+        let size = Size::new(available_width, y - origin.y);
+        // Avoid laying out again just because the width was fitted to the text.
+        self.previous_rect.size.width = size.width;
+        self.set_size(size);
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for RichLabelWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> Widget for RichLabelWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        if self.dirty {
+            self.dirty = false;
+            self.update_fragments();
+        }
+
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if self.hovered_link.is_some() {
+            aux.set_cursor_icon(base::CursorIcon::Pointer);
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle_clip(self.abs_rect().cast_unit(), true);
+
+        for fragment in &self.fragments {
+            let span = &self.data.spans[fragment.span];
+            let rect = fragment.rect.cast_unit();
+
+            let background =
+                span.background.or(if span.code { Some(self.data.code_background) } else { None });
+            if let Some(background) = background {
+                builder.push_rectangle(rect, GraphicsDisplayPaint::Fill(background.into()), None);
+            }
+
+            builder.push_text(fragment.item.clone(), None);
+
+            let color = fragment.item.color.clone();
+            let size = fragment.item.size;
+            let thickness = (size / 14.0).max(1.0);
+            let mut push_line = |y: f32| {
+                builder.push_line(
+                    Point::new(rect.min_x(), y),
+                    Point::new(rect.max_x(), y),
+                    GraphicsDisplayStroke { thickness, color: color.clone(), ..Default::default() },
+                    None,
+                );
+            };
+
+            let underline =
+                span.underline || (span.link.is_some() && self.hovered_link == Some(fragment.span));
+            if underline {
+                push_line(fragment.item.bottom_left.y + size * 0.1);
+            }
+            if span.strikethrough {
+                push_line(fragment.item.bottom_left.y - size * 0.3);
+            }
+        }
+
+        self.command_group.push(display, &builder.build(), Default::default(), None, None);
+    }
+}
+
+impl<U, G> StoresParentPosition for RichLabelWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
+
+impl<U, G> draw::HasTheme for RichLabelWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    #[inline]
+    fn theme(&mut self) -> &mut dyn draw::Themed {
+        &mut self.themed
+    }
+
+    fn resize_from_theme(&mut self) {}
+}
+
+impl<U, G> ui::DefaultEventQueue<RichLabelEvent> for RichLabelWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    #[inline]
+    fn default_event_queue(&self) -> &RcEventQueue<RichLabelEvent> {
+        &self.event_queue
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<RichLabel> for RichLabelWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<RichLabel> {
+        &mut self.data
+    }
+}
+
+impl<U, G> Drop for RichLabelWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    fn drop(&mut self) {
+        self.drop_event.emit_owned(base::DropEvent);
+    }
+}
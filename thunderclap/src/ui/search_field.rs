@@ -0,0 +1,684 @@
+//! Text input specialized for searching, with a clear button, debounced queries and suggestions.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state, HasTheme},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{CommandGroup, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::{RcEventListener, RcEventQueue},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{marker::PhantomData, time::Duration},
+};
+
+/// Width reserved for the magnifier icon on the left of the field.
+const ICON_WIDTH: f32 = 28.0;
+/// Side length of the clear button.
+const CLEAR_SIZE: f32 = 16.0;
+/// Padding between the border of the suggestion popup and its items.
+const POPUP_PADDING: f32 = 4.0;
+
+/// Events emitted by a search field.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum SearchEvent {
+    /// Emitted once typing has paused for `debounce`, or immediately when the field is cleared.
+    #[event_key(query)]
+    Query(String),
+    /// Emitted when `Return` is pressed without a highlighted suggestion.
+    #[event_key(submit)]
+    Submit(String),
+    /// Emitted when a suggestion is picked, either by clicking it or with the keyboard.
+    #[event_key(suggestion_selected)]
+    SuggestionSelected(String),
+}
+
+pub fn search_field_handler<U, G>(
+) -> vg::UnboundQueueHandler<SearchFieldWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        SearchFieldWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled && *button == base::MouseButton::Left && obj.handles_press(*pos)
+            }) {
+                // Clicking anywhere within the search field (or its popup) keeps the text area focused.
+                obj.focus_field();
+                if let Some(index) = obj.suggestion_at(*pos) {
+                    obj.select_suggestion(index);
+                } else if obj.clear_rect().map(|rect| rect.contains(*pos)).unwrap_or(false) {
+                    obj.clear();
+                }
+            }
+        }
+
+        mouse_move => {
+            let pos = event.get().0;
+            let clear_hovered = obj.clear_rect().map(|rect| rect.contains(pos)).unwrap_or(false);
+            let hovered_suggestion = obj.suggestion_at(pos);
+            if clear_hovered != obj.clear_hovered || hovered_suggestion != obj.hovered_suggestion {
+                obj.clear_hovered = clear_hovered;
+                obj.hovered_suggestion = hovered_suggestion;
+                obj.repaint();
+            }
+            if obj.popup_open() {
+                event.with(|(pos, _)| obj.popup_rect().contains(*pos));
+            }
+        }
+
+        key_press => {
+            if obj.focused && !obj.data.disabled {
+                event.with(|&(key, _)| obj.handle_key(key));
+            }
+        }
+    }
+}
+
+/// Bundles the painters for the field and the suggestion popup so that both follow theme changes.
+struct SearchFieldPainters {
+    field: Box<dyn draw::Painter<state::SearchFieldState>>,
+    suggestions: Box<dyn draw::Painter<state::SuggestionsState>>,
+}
+
+impl draw::Themed for SearchFieldPainters {
+    fn load_theme(&mut self, theme: &dyn draw::Theme, aux: &dyn base::GraphicalAuxiliary) {
+        self.field.load_theme(theme, aux);
+        self.suggestions.load_theme(theme, aux);
+    }
+}
+
+/// Text input with a magnifier icon and a button to clear the text.
+///
+/// Instead of reporting every keystroke, the search field emits `SearchEvent::Query` once typing pauses for `debounce`.
+/// While focused, `Escape` closes the suggestion popup or clears the text.
+/// Suggestions (set by the user of the widget, typically in response to `SearchEvent::Query`) are shown in a popup,
+/// where `Up`/`Down` highlight a suggestion and `Return` picks it.
+#[derive(
+    LayableWidget, DropNotifier, HasVisibility, Repaintable, Movable, Resizable, OperatesVerbGraph,
+)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+pub struct SearchFieldWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub event_queue: RcEventQueue<SearchEvent>,
+    pub data: base::Observed<SearchField>,
+
+    field: ui::TextAreaWidget<U, G>,
+    field_listener: RcEventListener<ui::TextAreaEvent>,
+    /// Whether the text area has keyboard focus.
+    focused: bool,
+    /// When the pending `SearchEvent::Query` is due.
+    pending_query: Option<std::time::Instant>,
+    clear_hovered: bool,
+    highlighted: Option<usize>,
+    hovered_suggestion: Option<usize>,
+    /// Whether the suggestion popup was closed since the text last changed.
+    popup_dismissed: bool,
+    graph: vg::OptionVerbGraph<Self, U>,
+    painters: SearchFieldPainters,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_visibility]
+    visibility: base::Visibility,
+    #[repaint_target]
+    command_group: CommandGroup,
+    #[repaint_target]
+    popup_command_group: CommandGroup,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+    #[widget_drop_event]
+    drop_event: RcEventQueue<base::DropEvent>,
+
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchField {
+    /// Text shown while the field is empty.
+    pub placeholder: String,
+    /// How long typing has to pause before `SearchEvent::Query` is emitted.
+    pub debounce: Duration,
+    /// Suggestions shown in a popup below the field while it's focused and non-empty.
+    pub suggestions: Vec<String>,
+    /// Maximum amount of suggestions shown at once.
+    pub max_suggestions: usize,
+    pub style: ui::PickerStyle,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for SearchField
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = SearchFieldWidget<U, G>;
+}
+
+impl SearchField {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        SearchField {
+            placeholder: "Search".to_string(),
+            debounce: Duration::from_millis(300),
+            suggestions: Vec::new(),
+            max_suggestions: 8,
+            style: ui::PickerStyle::from_theme(theme),
+            disabled: false,
+        }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        theme: &dyn draw::Theme,
+        u_aux: &mut U,
+        g_aux: &mut G,
+    ) -> SearchFieldWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            SearchFieldWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    if obj.highlighted.map(|i| i >= obj.visible_suggestions()).unwrap_or(false) {
+                        obj.highlighted = None;
+                    }
+                    obj.hovered_suggestion = None;
+                    obj.sync_field();
+                    obj.layout_field();
+                    obj.repaint();
+                }
+            }
+        };
+
+        graph =
+            graph.add("search_field", search_field_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let field = ui::TextArea {
+            placeholder: data.placeholder.clone(),
+            typeface: data.style.typeface.clone(),
+            color: data.style.color,
+            disabled: data.disabled,
+            consume_keys: false,
+            ..ui::TextArea::from_theme(theme)
+        }
+        .construct(theme, u_aux, g_aux);
+        let field_listener = field.event_queue.listen();
+
+        let mut search_field = SearchFieldWidget {
+            event_queue: Default::default(),
+            data,
+
+            field,
+            field_listener,
+            focused: false,
+            pending_query: None,
+            clear_hovered: false,
+            highlighted: None,
+            hovered_suggestion: None,
+            popup_dismissed: false,
+            graph: graph.into(),
+            painters: SearchFieldPainters {
+                field: theme.search_field(),
+                suggestions: theme.suggestions(),
+            },
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            visibility: Default::default(),
+            command_group: Default::default(),
+            popup_command_group: Default::default(),
+            layout: Default::default(),
+            drop_event: Default::default(),
+
+            phantom_g: Default::default(),
+        };
+
+        search_field.resize_from_theme();
+        search_field.layout_field();
+
+        search_field
+    }
+}
+
+impl<U, G> SearchFieldWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the text typed into the search field.
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.field.data.text
+    }
+
+    /// Replaces the text without emitting any events.
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        if self.field.data.text != text {
+            self.field.data.cursor = text.len();
+            self.field.data.text = text;
+        }
+        self.pending_query = None;
+        self.repaint();
+    }
+
+    /// Clears the text and immediately emits an empty `SearchEvent::Query`.
+    pub fn clear(&mut self) {
+        if self.field.data.text.is_empty() {
+            return;
+        }
+
+        self.set_text(String::new());
+        self.highlighted = None;
+        self.popup_dismissed = false;
+        self.event_queue.emit_owned(SearchEvent::Query(String::new()));
+    }
+
+    fn on_transform(&mut self) {
+        self.repaint();
+        self.layout_field();
+        base::LayableWidget::notify_layout(self);
+    }
+
+    /// Copies the relevant properties of the search field to the text area.
+    fn sync_field(&mut self) {
+        let field = &mut self.field;
+        let data = &self.data;
+        if field.data.placeholder != data.placeholder {
+            field.data.placeholder = data.placeholder.clone();
+        }
+        if field.data.typeface != data.style.typeface {
+            field.data.typeface = data.style.typeface.clone();
+        }
+        if field.data.color != data.style.color {
+            field.data.color = data.style.color;
+        }
+        if field.data.disabled != data.disabled {
+            field.data.disabled = data.disabled;
+        }
+    }
+
+    /// Positions the text area between the magnifier icon and the clear button.
+    fn layout_field(&mut self) {
+        let rect = self.abs_rect();
+        let padding = self.data.style.padding;
+        let height = self.data.style.line_height();
+        self.field.set_parent_position(self.abs_position());
+        self.field.set_ctxt_rect(AbsoluteRect::new(
+            AbsolutePoint::new(
+                rect.origin.x + ICON_WIDTH,
+                rect.origin.y + ((rect.size.height - height) / 2.0).round(),
+            ),
+            Size::new((rect.size.width - ICON_WIDTH - CLEAR_SIZE - padding * 2.0).max(0.0), height)
+                .cast_unit(),
+        ));
+    }
+
+    fn focus_field(&mut self) {
+        let interaction = ui::InteractiveWidget::interaction(&mut self.field);
+        if !interaction.contains(state::InteractionState::FOCUSED) {
+            interaction.insert(state::InteractionState::FOCUSED);
+            self.field.repaint();
+        }
+    }
+
+    fn icon_rect(&self) -> AbsoluteRect {
+        let rect = self.abs_rect();
+        AbsoluteRect::new(rect.origin, Size::new(ICON_WIDTH, rect.size.height).cast_unit())
+    }
+
+    /// Returns the rectangle of the clear button, which is only shown while there is text to clear.
+    fn clear_rect(&self) -> Option<AbsoluteRect> {
+        if self.field.data.text.is_empty() || self.data.disabled {
+            return None;
+        }
+
+        let rect = self.abs_rect();
+        Some(AbsoluteRect::new(
+            AbsolutePoint::new(
+                rect.max_x() - self.data.style.padding - CLEAR_SIZE,
+                rect.origin.y + ((rect.size.height - CLEAR_SIZE) / 2.0).round(),
+            ),
+            Size::new(CLEAR_SIZE, CLEAR_SIZE).cast_unit(),
+        ))
+    }
+
+    #[inline]
+    fn visible_suggestions(&self) -> usize {
+        self.data.suggestions.len().min(self.data.max_suggestions)
+    }
+
+    fn popup_open(&self) -> bool {
+        self.focused
+            && !self.data.disabled
+            && !self.popup_dismissed
+            && !self.field.data.text.is_empty()
+            && self.visible_suggestions() > 0
+    }
+
+    fn item_height(&self) -> f32 {
+        self.data.style.line_height() + self.data.style.padding
+    }
+
+    fn popup_rect(&self) -> AbsoluteRect {
+        AbsoluteRect::new(
+            ui::popup_origin(self.abs_rect()),
+            Size::new(
+                self.abs_rect().size.width,
+                self.visible_suggestions() as f32 * self.item_height() + POPUP_PADDING * 2.0,
+            )
+            .cast_unit(),
+        )
+    }
+
+    fn suggestion_rect(&self, index: usize) -> AbsoluteRect {
+        let popup = self.popup_rect();
+        let item_height = self.item_height();
+        AbsoluteRect::new(
+            AbsolutePoint::new(
+                popup.origin.x,
+                popup.origin.y + POPUP_PADDING + index as f32 * item_height,
+            ),
+            Size::new(popup.size.width, item_height).cast_unit(),
+        )
+    }
+
+    fn suggestion_at(&self, pos: AbsolutePoint) -> Option<usize> {
+        if !self.popup_open() {
+            return None;
+        }
+
+        (0..self.visible_suggestions()).find(|&i| self.suggestion_rect(i).contains(pos))
+    }
+
+    /// Returns whether a mouse press at `pos` is handled by the search field rather than the text area.
+    fn handles_press(&self, pos: AbsolutePoint) -> bool {
+        (self.popup_open() && self.popup_rect().contains(pos)) || self.abs_rect().contains(pos)
+    }
+
+    fn select_suggestion(&mut self, index: usize) {
+        let text = self.data.suggestions[index].clone();
+        self.set_text(text.clone());
+        self.highlighted = None;
+        self.hovered_suggestion = None;
+        self.popup_dismissed = true;
+        self.event_queue.emit_owned(SearchEvent::SuggestionSelected(text));
+    }
+
+    fn submit(&mut self) {
+        let text = self.field.data.text.clone();
+        if self.pending_query.take().is_some() {
+            self.event_queue.emit_owned(SearchEvent::Query(text.clone()));
+        }
+        self.popup_dismissed = true;
+        self.repaint();
+        self.event_queue.emit_owned(SearchEvent::Submit(text));
+    }
+
+    /// Handles a key press while the text area is focused, returning whether the key was used.
+    fn handle_key(&mut self, key: base::KeyInput) -> bool {
+        let count = self.visible_suggestions();
+        match key {
+            base::KeyInput::Escape => {
+                if self.popup_open() {
+                    self.popup_dismissed = true;
+                    self.highlighted = None;
+                    self.repaint();
+                } else if !self.field.data.text.is_empty() {
+                    self.clear();
+                } else {
+                    return false;
+                }
+            }
+            base::KeyInput::Down if count > 0 && !self.field.data.text.is_empty() => {
+                self.highlighted = if self.popup_open() {
+                    Some(self.highlighted.map(|i| (i + 1).min(count - 1)).unwrap_or(0))
+                } else {
+                    // Re-open a dismissed popup.
+                    self.popup_dismissed = false;
+                    None
+                };
+                self.repaint();
+            }
+            base::KeyInput::Up if self.popup_open() => {
+                self.highlighted = match self.highlighted {
+                    Some(0) | None => None,
+                    Some(i) => Some(i - 1),
+                };
+                self.repaint();
+            }
+            base::KeyInput::Return | base::KeyInput::NumpadEnter => {
+                match self.highlighted.filter(|_| self.popup_open()) {
+                    Some(index) => self.select_suggestion(index),
+                    None => self.submit(),
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn derive_field_state(&self) -> state::SearchFieldState {
+        state::SearchFieldState {
+            rect: self.abs_rect(),
+            style: self.data.style.clone(),
+            icon: self.icon_rect(),
+            clear: self.clear_rect().map(|rect| (rect, self.clear_hovered)),
+            focused: self.focused,
+            disabled: self.data.disabled,
+        }
+    }
+
+    fn derive_suggestions_state(&self) -> Option<state::SuggestionsState> {
+        if !self.popup_open() {
+            return None;
+        }
+
+        Some(state::SuggestionsState {
+            rect: self.popup_rect(),
+            style: self.data.style.clone(),
+            items: (0..self.visible_suggestions())
+                .map(|i| (self.data.suggestions[i].clone(), self.suggestion_rect(i)))
+                .collect(),
+            highlighted: self.highlighted,
+            hovered: self.hovered_suggestion,
+        })
+    }
+}
+
+impl<U, G> base::WidgetChildren for SearchFieldWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn children(
+        &self,
+    ) -> Vec<
+        &dyn base::WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>,
+    > {
+        vec![&self.field]
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut dyn base::WidgetChildren<
+            UpdateAux = U,
+            GraphicalAux = G,
+            DisplayObject = DisplayCommand,
+        >,
+    > {
+        vec![&mut self.field]
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for SearchFieldWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> Widget for SearchFieldWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.painters.field.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        base::invoke_update(self, aux);
+
+        let focused = ui::InteractiveWidget::interaction(&mut self.field)
+            .contains(state::InteractionState::FOCUSED);
+        if focused != self.focused {
+            self.focused = focused;
+            self.highlighted = None;
+            self.popup_dismissed = false;
+            self.repaint();
+        }
+
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if self.layout.direction_changed() {
+            let direction = self.layout.direction();
+            base::LayableWidget::set_layout_direction(&mut self.field, Some(direction));
+            self.command_group.repaint();
+        }
+
+        for event in self.field_listener.peek() {
+            if let ui::TextAreaEvent::UserModify(_) = event {
+                self.pending_query = Some(aux.now() + self.data.debounce);
+                self.highlighted = None;
+                self.popup_dismissed = false;
+                self.repaint();
+            }
+        }
+
+        if let Some(deadline) = self.pending_query {
+            if aux.now() >= deadline {
+                self.pending_query = None;
+                self.event_queue.emit_owned(SearchEvent::Query(self.field.data.text.clone()));
+            } else {
+                aux.request_wakeup(deadline);
+            }
+        }
+
+        if self.clear_hovered && self.clear_rect().is_some() {
+            aux.set_cursor_icon(base::CursorIcon::Pointer);
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let field_state = self.derive_field_state();
+        let suggestions_state = self.derive_suggestions_state();
+
+        let painters = &mut self.painters;
+        self.command_group.push_with(
+            display,
+            || painters.field.draw(field_state),
+            Default::default(),
+            None,
+            None,
+        );
+        self.popup_command_group.push_with(
+            display,
+            || suggestions_state.map(|state| painters.suggestions.draw(state)).unwrap_or_default(),
+            ui::OVERLAY_Z_ORDER,
+            None,
+            None,
+        );
+    }
+}
+
+impl<U, G> StoresParentPosition for SearchFieldWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
+
+impl<U, G> HasTheme for SearchFieldWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn theme(&mut self) -> &mut dyn draw::Themed {
+        &mut self.painters
+    }
+
+    fn resize_from_theme(&mut self) {
+        self.set_size(self.painters.field.size_hint(self.derive_field_state()));
+    }
+}
+
+impl<U, G> ui::DefaultEventQueue<SearchEvent> for SearchFieldWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_event_queue(&self) -> &RcEventQueue<SearchEvent> {
+        &self.event_queue
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<SearchField> for SearchFieldWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<SearchField> {
+        &mut self.data
+    }
+}
+
+impl<U, G> Drop for SearchFieldWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn drop(&mut self) {
+        self.drop_event.emit_owned(base::DropEvent);
+    }
+}
@@ -0,0 +1,149 @@
+use {
+    crate::{
+        base::{self, Layout},
+        draw,
+        geom::*,
+        ui,
+    },
+    reclutch::{display::Size, event::RcEventQueue, prelude::*, verbgraph as vg},
+    std::marker::PhantomData,
+};
+
+lazy_widget! {
+    generic SizedBoxWidget,
+    visibility: visibility,
+    theme: themed,
+    drop_event: drop_event
+}
+
+/// Abstract layout widget which constrains the size of a single child to a minimum, maximum or fixed width and height.
+///
+/// The child fills the box. The box is still subject to the constraints of its parent layout (e.g. a stretched `VStack` child is stretched regardless of its width).
+/// Pushing another child replaces the previous one.
+#[derive(LayableWidget, Movable, Resizable, OperatesVerbGraph)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+#[widget_measure(measure_children)]
+pub struct SizedBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub data: base::Observed<SizedBox>,
+
+    child: ui::SingleChild,
+    visibility: base::Visibility,
+    themed: draw::PhantomThemed,
+    drop_event: RcEventQueue<base::DropEvent>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    phantom_u: PhantomData<U>,
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizedBox {
+    /// Fixed width of the box, overriding `min_width` and `max_width`.
+    pub width: Option<f32>,
+    /// Fixed height of the box, overriding `min_height` and `max_height`.
+    pub height: Option<f32>,
+    pub min_width: f32,
+    pub min_height: f32,
+    pub max_width: f32,
+    pub max_height: f32,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for SizedBox
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = SizedBoxWidget<U, G>;
+}
+
+impl SizedBox {
+    pub fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        SizedBox {
+            width: None,
+            height: None,
+            min_width: 0.0,
+            min_height: 0.0,
+            max_width: std::f32::INFINITY,
+            max_height: std::f32::INFINITY,
+        }
+    }
+
+    /// Returns the constraints described by the box.
+    pub fn constraints(&self) -> base::Constraints {
+        base::Constraints::new(
+            Size::new(self.width.unwrap_or(self.min_width), self.height.unwrap_or(self.min_height)),
+            Size::new(self.width.unwrap_or(self.max_width), self.height.unwrap_or(self.max_height)),
+        )
+    }
+
+    pub fn construct<U, G>(
+        self,
+        _theme: &dyn draw::Theme,
+        _u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> SizedBoxWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            SizedBoxWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.layout.mark_dirty();
+                }
+            }
+        };
+
+        SizedBoxWidget {
+            data,
+
+            child: Default::default(),
+            visibility: Default::default(),
+            themed: Default::default(),
+            drop_event: Default::default(),
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            layout: Default::default(),
+
+            graph: graph.into(),
+            phantom_u: Default::default(),
+            phantom_g: Default::default(),
+        }
+    }
+}
+
+single_child_layout!(SizedBoxWidget, SizedBox);
+
+impl<U, G> SizedBoxWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn measure_single(&self, constraints: base::Constraints, child: Size) -> Size {
+        constraints.enforce(self.data.constraints()).constrain(child)
+    }
+
+    fn arrange_single(
+        &self,
+        rect: AbsoluteRect,
+        _child: Size,
+    ) -> (base::Constraints, AbsoluteRect) {
+        (base::Constraints::tight(rect.size.cast_unit()), rect)
+    }
+}
@@ -0,0 +1,730 @@
+//! Numeric input with increment/decrement buttons.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{Color, CommandGroup, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::RcEventQueue,
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{
+        fmt,
+        marker::PhantomData,
+        str::FromStr,
+        time::{Duration, Instant},
+    },
+};
+
+/// Width of the column containing the increment/decrement buttons.
+const STEPPER_WIDTH: f32 = 18.0;
+/// Time a stepper button has to be held before it starts repeating.
+const REPEAT_DELAY: Duration = Duration::from_millis(400);
+/// Time between repeated steps while a stepper button is held.
+const REPEAT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Numeric types which can be edited within a `SpinBox`.
+///
+/// Stepping and clamping are done in the type itself (rather than through a float), so that large integers stay exact.
+pub trait Num: Copy + PartialOrd + fmt::Debug + fmt::Display + FromStr + 'static {
+    /// Smallest representable value.
+    const MIN: Self;
+    /// Largest representable value.
+    const MAX: Self;
+    const ZERO: Self;
+    const ONE: Self;
+    /// Whether the type can represent fractional values.
+    const FRACTIONAL: bool;
+
+    /// Adds `steps` multiples of `step`, saturating at the bounds of the type.
+    fn step_by(self, step: Self, steps: i32) -> Self;
+    /// Rounds to `precision` decimal places; integers are returned unchanged.
+    fn round_to(self, precision: usize) -> Self;
+}
+
+macro_rules! impl_num {
+    (integer $($t:ident),*) => {
+        $(impl Num for $t {
+            const MIN: Self = std::$t::MIN;
+            const MAX: Self = std::$t::MAX;
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            const FRACTIONAL: bool = false;
+
+            #[inline]
+            fn step_by(self, step: Self, steps: i32) -> Self {
+                let delta = step.saturating_mul(steps.abs() as $t);
+                if steps < 0 {
+                    self.saturating_sub(delta)
+                } else {
+                    self.saturating_add(delta)
+                }
+            }
+
+            #[inline]
+            fn round_to(self, _precision: usize) -> Self {
+                self
+            }
+        })*
+    };
+    (float $($t:ident),*) => {
+        $(impl Num for $t {
+            const MIN: Self = std::$t::MIN;
+            const MAX: Self = std::$t::MAX;
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const FRACTIONAL: bool = true;
+
+            #[inline]
+            fn step_by(self, step: Self, steps: i32) -> Self {
+                (self + step * steps as $t).max(Self::MIN).min(Self::MAX)
+            }
+
+            #[inline]
+            fn round_to(self, precision: usize) -> Self {
+                let factor = (10.0 as $t).powi(precision as i32);
+                (self * factor).round() / factor
+            }
+        })*
+    };
+}
+
+impl_num!(integer i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+impl_num!(float f32, f64);
+
+/// How typed values which lie outside of `min`/`max` are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpinBoxValidation {
+    /// The value is clamped into range.
+    Snap,
+    /// The value is discarded and the previous value is restored.
+    Reject,
+}
+
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum SpinBoxEvent<T: Num> {
+    /// The spin box gained focus.
+    #[event_key(focus)]
+    Focus,
+    /// The spin box lost focus.
+    #[event_key(blur)]
+    Blur,
+    /// The value was changed by the user, either by stepping or by committing typed text.
+    #[event_key(value_changed)]
+    ValueChanged(T),
+}
+
+/// One of the two stepper buttons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stepper {
+    Increment,
+    Decrement,
+}
+
+impl Stepper {
+    #[inline]
+    fn direction(self) -> i32 {
+        match self {
+            Stepper::Increment => 1,
+            Stepper::Decrement => -1,
+        }
+    }
+}
+
+pub fn spin_box_handler<T, U, G>(
+) -> vg::UnboundQueueHandler<SpinBoxWidget<T, U, G>, U, base::WindowEvent>
+where
+    T: Num,
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        SpinBoxWidget<T, U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        text_input => {
+            if let Some(&c) = event.with(|c| {
+                !obj.data.disabled
+                    && obj.interaction.contains(state::InteractionState::FOCUSED)
+                    && obj.accepts_char(*c)
+            }) {
+                let index = byte_index(&obj.text, obj.cursor);
+                obj.text.insert(index, c);
+                obj.cursor += 1;
+                obj.editing = true;
+                obj.repaint();
+            }
+        }
+
+        key_press => {
+            if let Some((key, _)) = event.with(|_| {
+                !obj.data.disabled && obj.interaction.contains(state::InteractionState::FOCUSED)
+            }) {
+                match key {
+                    base::KeyInput::Up => obj.step(1),
+                    base::KeyInput::Down => obj.step(-1),
+                    base::KeyInput::PageUp => obj.step(10),
+                    base::KeyInput::PageDown => obj.step(-10),
+                    base::KeyInput::Return | base::KeyInput::NumpadEnter => obj.commit(),
+                    base::KeyInput::Escape => obj.revert(),
+                    base::KeyInput::Back => {
+                        if obj.cursor > 0 {
+                            let index = byte_index(&obj.text, obj.cursor - 1);
+                            obj.text.remove(index);
+                            obj.cursor -= 1;
+                            obj.editing = true;
+                        }
+                    }
+                    base::KeyInput::Delete => {
+                        if obj.cursor < obj.text.chars().count() {
+                            let index = byte_index(&obj.text, obj.cursor);
+                            obj.text.remove(index);
+                            obj.editing = true;
+                        }
+                    }
+                    base::KeyInput::Left => obj.cursor = obj.cursor.saturating_sub(1),
+                    base::KeyInput::Right => {
+                        obj.cursor = (obj.cursor + 1).min(obj.text.chars().count())
+                    }
+                    base::KeyInput::Home => obj.cursor = 0,
+                    base::KeyInput::End => obj.cursor = obj.text.chars().count(),
+                    _ => {}
+                }
+                obj.repaint();
+            }
+        }
+
+        mouse_scroll => {
+            let bounds = obj.abs_rect();
+            if let Some((_, delta, _)) = event.with(|(pos, _, _)| !obj.data.disabled && bounds.contains(*pos)) {
+                let y = delta.to_pixels(1.0).y;
+                if y > 0.0 {
+                    obj.step(1);
+                } else if y < 0.0 {
+                    obj.step(-1);
+                }
+            }
+        }
+
+        mouse_move => {
+            let (pos, _) = event.get();
+            let hovered = obj.stepper_at(*pos);
+            if hovered != obj.hovered_stepper {
+                obj.hovered_stepper = hovered;
+                obj.repaint();
+            }
+        }
+    }
+}
+
+/// Converts a character index into a byte index of `text`.
+fn byte_index(text: &str, index: usize) -> usize {
+    text.char_indices().nth(index).map(|(i, _)| i).unwrap_or_else(|| text.len())
+}
+
+/// Text field which only accepts numeric input, paired with increment and decrement buttons.
+///
+/// Holding down either button repeatedly steps the value.
+#[derive(
+    WidgetChildren,
+    LayableWidget,
+    DropNotifier,
+    HasVisibility,
+    Repaintable,
+    Movable,
+    Resizable,
+    OperatesVerbGraph,
+)]
+#[widget_children_trait(base::WidgetChildren)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+pub struct SpinBoxWidget<T, U, G>
+where
+    T: Num,
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub event_queue: RcEventQueue<SpinBoxEvent<T>>,
+    pub data: base::Observed<SpinBox<T>>,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    painter: Box<dyn draw::Painter<state::SpinBoxState>>,
+    interaction: state::InteractionState,
+    /// Text currently displayed, which may be mid-edit.
+    text: String,
+    /// Text cursor/caret position, as a character index into `text`.
+    cursor: usize,
+    /// Whether `text` has been edited since it was last committed.
+    editing: bool,
+    hovered_stepper: Option<Stepper>,
+    pressed_stepper: Option<Stepper>,
+    /// Time at which the held stepper button next repeats (`None` if the hold has only just begun).
+    next_repeat: Option<Instant>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_visibility]
+    visibility: base::Visibility,
+    #[repaint_target]
+    command_group: CommandGroup,
+    #[widget_drop_event]
+    drop_event: RcEventQueue<base::DropEvent>,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+
+    phantom_g: PhantomData<G>,
+}
+
+impl<T, U, G> ui::InteractiveWidget for SpinBoxWidget<T, U, G>
+where
+    T: Num,
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline(always)]
+    fn interaction(&mut self) -> &mut state::InteractionState {
+        &mut self.interaction
+    }
+
+    #[inline]
+    fn mouse_bounds(&self) -> RelativeRect {
+        self.painter.mouse_hint(self.rect)
+    }
+
+    #[inline]
+    fn disabled(&self) -> bool {
+        self.data.disabled
+    }
+
+    fn on_interaction_event(&mut self, event: ui::InteractionEvent) {
+        match event {
+            ui::InteractionEvent::Pressed(pos) => {
+                if let Some(stepper) = self.stepper_at(pos) {
+                    self.pressed_stepper = Some(stepper);
+                    self.next_repeat = None;
+                    self.step(stepper.direction());
+                } else {
+                    self.cursor = self.text.chars().count();
+                }
+                self.repaint();
+            }
+            ui::InteractionEvent::Released(_) => {
+                self.pressed_stepper = None;
+                self.next_repeat = None;
+                self.repaint();
+            }
+            ui::InteractionEvent::Focus => {
+                self.repaint();
+                self.event_queue.emit_owned(SpinBoxEvent::Focus);
+            }
+            ui::InteractionEvent::Blur => {
+                self.commit();
+                self.event_queue.emit_owned(SpinBoxEvent::Blur);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpinBox<T: Num> {
+    pub value: T,
+    pub min: T,
+    pub max: T,
+    /// Amount the value changes by per step.
+    pub step: T,
+    /// Amount of decimal places values are rounded to and displayed with. Ignored for integer types.
+    pub precision: usize,
+    pub validation: SpinBoxValidation,
+    pub typeface: draw::TypefaceStyle,
+    pub color: Color,
+    pub cursor_color: Color,
+    pub background: Color,
+    pub stepper_background: Color,
+    pub focus: Color,
+    pub contrast: draw::ThemeContrast,
+    pub disabled: bool,
+}
+
+impl<T, U, G> ui::WidgetDataTarget<U, G> for SpinBox<T>
+where
+    T: Num,
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = SpinBoxWidget<T, U, G>;
+}
+
+impl<T: Num> SpinBox<T> {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        SpinBox {
+            value: T::ZERO,
+            min: T::MIN,
+            max: T::MAX,
+            step: T::ONE,
+            precision: if T::FRACTIONAL { 2 } else { 0 },
+            validation: SpinBoxValidation::Snap,
+            typeface: data.typography.body.clone(),
+            color: data.scheme.over_control_inset,
+            cursor_color: draw::weaken(data.scheme.over_control_inset, 0.1, data.contrast),
+            background: data.scheme.control_inset,
+            stepper_background: data.scheme.control_outset,
+            focus: data.scheme.focus,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        theme: &dyn draw::Theme,
+        u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> SpinBoxWidget<T, U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            SpinBoxWidget<T, U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    if !obj.editing {
+                        obj.text = obj.data.format(obj.data.value);
+                        obj.cursor = obj.cursor.min(obj.text.chars().count());
+                    }
+                    obj.repaint();
+                }
+            }
+        };
+
+        graph = graph.add(
+            "interaction",
+            ui::basic_interaction_handler::<SpinBoxWidget<T, U, G>, U>().bind(u_aux.window_queue()),
+        );
+        graph = graph.add("spin_box", spin_box_handler::<T, U, G>().bind(u_aux.window_queue()));
+
+        let text = data.format(data.value);
+        let cursor = text.chars().count();
+
+        let mut spin_box = SpinBoxWidget {
+            event_queue: Default::default(),
+            data,
+
+            graph: graph.into(),
+            painter: theme.spin_box(),
+            interaction: state::InteractionState::empty(),
+            text,
+            cursor,
+            editing: false,
+            hovered_stepper: None,
+            pressed_stepper: None,
+            next_repeat: None,
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            visibility: Default::default(),
+            command_group: Default::default(),
+            drop_event: Default::default(),
+            layout: Default::default(),
+
+            phantom_g: Default::default(),
+        };
+
+        spin_box.resize_from_theme();
+
+        spin_box
+    }
+
+    /// Formats a value with the configured precision.
+    pub fn format(&self, value: T) -> String {
+        if T::FRACTIONAL {
+            format!("{:.*}", self.precision, value)
+        } else {
+            value.to_string()
+        }
+    }
+}
+
+impl<T, U, G> SpinBoxWidget<T, U, G>
+where
+    T: Num,
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn on_transform(&mut self) {
+        self.repaint();
+        base::LayableWidget::notify_layout(self);
+    }
+
+    /// Returns the rectangles of the increment and decrement buttons, respectively.
+    fn stepper_rects(&self) -> (AbsoluteRect, AbsoluteRect) {
+        let rect = self.abs_rect();
+        let size = Size::new(STEPPER_WIDTH, rect.size.height / 2.0).cast_unit();
+        let x = rect.max_x() - STEPPER_WIDTH;
+        (
+            AbsoluteRect::new(AbsolutePoint::new(x, rect.origin.y), size),
+            AbsoluteRect::new(AbsolutePoint::new(x, rect.origin.y + size.height), size),
+        )
+    }
+
+    fn stepper_at(&self, pos: AbsolutePoint) -> Option<Stepper> {
+        let (increment, decrement) = self.stepper_rects();
+        if increment.contains(pos) {
+            Some(Stepper::Increment)
+        } else if decrement.contains(pos) {
+            Some(Stepper::Decrement)
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether a typed character is acceptable at the current cursor position.
+    fn accepts_char(&self, c: char) -> bool {
+        match c {
+            '0'..='9' => true,
+            '-' => self.data.min < T::ZERO && self.cursor == 0 && !self.text.starts_with('-'),
+            '.' => T::FRACTIONAL && self.data.precision > 0 && !self.text.contains('.'),
+            _ => false,
+        }
+    }
+
+    /// Returns whether stepping in the direction of `stepper` can still change the value.
+    fn can_step(&self, stepper: Stepper) -> bool {
+        !self.data.disabled
+            && match stepper {
+                Stepper::Increment => self.data.value < self.data.max,
+                Stepper::Decrement => self.data.value > self.data.min,
+            }
+    }
+
+    /// Changes the value by `steps` multiples of the step size, clamping into range.
+    fn step(&mut self, steps: i32) {
+        if self.data.disabled {
+            return;
+        }
+
+        self.commit();
+        let value = self.data.value.step_by(self.data.step, steps);
+        self.set_value(value, true);
+    }
+
+    /// Parses the edited text and applies it as the value, honouring the validation mode.
+    fn commit(&mut self) {
+        if !self.editing {
+            return;
+        }
+
+        self.editing = false;
+        let snap = self.data.validation == SpinBoxValidation::Snap;
+        let value = match self.text.parse::<T>() {
+            Ok(value) => Some(value),
+            // Numbers beyond the bounds of the type still snap into range.
+            Err(_) if snap => {
+                self.text.parse::<f64>().ok().map(|value| if value < 0.0 { T::MIN } else { T::MAX })
+            }
+            Err(_) => None,
+        };
+        match value {
+            Some(value) => self.set_value(value, snap),
+            None => self.revert(),
+        }
+    }
+
+    /// Discards edited text, displaying the current value.
+    fn revert(&mut self) {
+        self.editing = false;
+        self.text = self.data.format(self.data.value);
+        self.cursor = self.text.chars().count();
+        self.repaint();
+    }
+
+    fn set_value(&mut self, value: T, snap: bool) {
+        let (min, max) = (self.data.min, self.data.max);
+        if snap || (value >= min && value <= max) {
+            let value = if value < min {
+                min
+            } else if value > max {
+                max
+            } else {
+                value
+            };
+            let value = value.round_to(self.data.precision);
+            if value != self.data.value {
+                self.data.value = value;
+                self.event_queue.emit_owned(SpinBoxEvent::ValueChanged(value));
+            }
+        }
+        self.revert();
+    }
+
+    fn derive_state(&self) -> state::SpinBoxState {
+        let (increment_rect, decrement_rect) = self.stepper_rects();
+        let stepper_interaction = |stepper| {
+            let mut interaction = state::InteractionState::empty();
+            if self.hovered_stepper == Some(stepper) {
+                interaction.insert(state::InteractionState::HOVERED);
+            }
+            if self.pressed_stepper == Some(stepper) {
+                interaction.insert(state::InteractionState::PRESSED);
+            }
+            interaction
+        };
+
+        state::SpinBoxState {
+            rect: self.abs_rect(),
+            text: self.text.clone(),
+            cursor: self.cursor,
+            typeface: self.data.typeface.clone(),
+            color: self.data.color,
+            cursor_color: self.data.cursor_color,
+            background: self.data.background,
+            stepper_background: self.data.stepper_background,
+            focus: self.data.focus,
+            contrast: self.data.contrast,
+            disabled: self.data.disabled,
+            interaction: self.interaction,
+            increment_rect,
+            decrement_rect,
+            increment_interaction: stepper_interaction(Stepper::Increment),
+            decrement_interaction: stepper_interaction(Stepper::Decrement),
+            can_increment: self.data.value < self.data.max,
+            can_decrement: self.data.value > self.data.min,
+        }
+    }
+}
+
+impl<T, U, G> vg::HasVerbGraph for SpinBoxWidget<T, U, G>
+where
+    T: Num,
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<T, U, G> Widget for SpinBoxWidget<T, U, G>
+where
+    T: Num,
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if let Some(stepper) = self.pressed_stepper {
+            let now = aux.now();
+            let next_repeat = match self.next_repeat {
+                Some(next_repeat) if now >= next_repeat => {
+                    self.step(stepper.direction());
+                    now + REPEAT_INTERVAL
+                }
+                Some(next_repeat) => next_repeat,
+                None => now + REPEAT_DELAY,
+            };
+            self.next_repeat = Some(next_repeat);
+            // Once the value is clamped, further steps can't change it, so there's nothing to repeat.
+            if self.can_step(stepper) {
+                aux.request_wakeup(next_repeat);
+            }
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
+
+impl<T, U, G> StoresParentPosition for SpinBoxWidget<T, U, G>
+where
+    T: Num,
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
+
+impl<T, U, G> draw::HasTheme for SpinBoxWidget<T, U, G>
+where
+    T: Num,
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn theme(&mut self) -> &mut dyn draw::Themed {
+        &mut self.painter
+    }
+
+    fn resize_from_theme(&mut self) {
+        self.set_size(self.painter.size_hint(self.derive_state()));
+    }
+}
+
+impl<T, U, G> ui::DefaultWidgetData<SpinBox<T>> for SpinBoxWidget<T, U, G>
+where
+    T: Num,
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<SpinBox<T>> {
+        &mut self.data
+    }
+}
+
+impl<T, U, G> ui::DefaultEventQueue<SpinBoxEvent<T>> for SpinBoxWidget<T, U, G>
+where
+    T: Num,
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_event_queue(&self) -> &RcEventQueue<SpinBoxEvent<T>> {
+        &self.event_queue
+    }
+}
+
+impl<T: Num, U: base::UpdateAuxiliary, G: base::GraphicalAuxiliary> Drop
+    for SpinBoxWidget<T, U, G>
+{
+    fn drop(&mut self) {
+        self.drop_event.emit_owned(base::DropEvent);
+    }
+}
@@ -0,0 +1,719 @@
+use {
+    crate::{
+        base::{self, Layout, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{Color, CommandGroup, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener, RcEventQueue},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{
+        marker::PhantomData,
+        time::{Duration, Instant},
+    },
+};
+
+/// Maximum time between two presses on a divider for them to count as a double-click.
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+
+/// Axis along which the panes of a `Split` are arranged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitDirection {
+    /// Panes are placed side by side, left to right, separated by vertical dividers.
+    Horizontal,
+    /// Panes are placed atop each other, top to bottom, separated by horizontal dividers.
+    Vertical,
+}
+
+impl Default for SplitDirection {
+    fn default() -> Self {
+        SplitDirection::Horizontal
+    }
+}
+
+/// Information about how a `Split` pane should be sized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplitItem {
+    /// Initial size of the pane along the split direction.
+    /// If `None`, the pane shares the remaining space equally with other unsized panes.
+    pub size: Option<f32>,
+    /// Minimum size of the pane along the split direction.
+    pub min_size: f32,
+    /// Maximum size of the pane along the split direction.
+    pub max_size: f32,
+    /// Whether the pane can be collapsed by double-clicking an adjacent divider.
+    pub collapsible: bool,
+}
+
+impl Default for SplitItem {
+    fn default() -> Self {
+        SplitItem { size: None, min_size: 0.0, max_size: std::f32::INFINITY, collapsible: false }
+    }
+}
+
+impl SplitItem {
+    /// Sets the `size` value.
+    pub fn size(self, size: f32) -> SplitItem {
+        SplitItem { size: Some(size), ..self }
+    }
+
+    /// Sets the `min_size` value.
+    pub fn min_size(self, min_size: f32) -> SplitItem {
+        SplitItem { min_size, ..self }
+    }
+
+    /// Sets the `max_size` value.
+    pub fn max_size(self, max_size: f32) -> SplitItem {
+        SplitItem { max_size, ..self }
+    }
+
+    /// Sets the `collapsible` value.
+    pub fn collapsible(self, collapsible: bool) -> SplitItem {
+        SplitItem { collapsible, ..self }
+    }
+}
+
+#[derive(Debug)]
+struct ChildData {
+    data: SplitItem,
+    evq: BidirSingleEventQueue<base::LayoutEvent, base::LayoutEvent>,
+    node: base::LayoutNode,
+    drop_listener: RcEventListener<base::DropEvent>,
+    /// Rectangle the child was last arranged in.
+    rect: AbsoluteRect,
+    original_rect: AbsoluteRect,
+    id: u64,
+    /// Current size along the split direction (`None` until first layed out).
+    size: Option<f32>,
+    /// Size to return to when expanding after being collapsed.
+    restore_size: f32,
+}
+
+/// Abstract layout widget which arranges two or more children along an axis, separated by draggable dividers.
+///
+/// Panes marked as collapsible can be collapsed (and expanded again) by double-clicking an adjacent divider.
+/// Divider positions can be read with `positions` and later restored with `restore_positions` (e.g. to persist pane sizes across runs).
+#[derive(
+    LayableWidget, DropNotifier, HasVisibility, Repaintable, Movable, Resizable, OperatesVerbGraph,
+)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+#[widget_measure(measure_children)]
+pub struct SplitWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub data: base::Observed<Split>,
+
+    rects: IndexMap<u64, ChildData>,
+    next_rect_id: u64,
+    /// Rectangle of each divider, in order.
+    dividers: Vec<AbsoluteRect>,
+    hovered: Option<usize>,
+    /// Index of the divider being dragged and the last main-axis cursor position.
+    drag: Option<(usize, f32)>,
+    /// Index of the last pressed divider and when it was pressed, for double-click detection.
+    last_press: Option<(usize, Instant)>,
+    /// Positions passed to `restore_positions` which are yet to be applied.
+    pending_positions: Option<Vec<f32>>,
+    painter: Box<dyn draw::Painter<state::SplitState>>,
+    graph: vg::OptionVerbGraph<Self, U>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_visibility]
+    visibility: base::Visibility,
+    #[repaint_target]
+    command_group: CommandGroup,
+    #[widget_drop_event]
+    drop_event: RcEventQueue<base::DropEvent>,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+
+    phantom_g: PhantomData<G>,
+}
+
+pub fn split_handler<U, G>() -> vg::UnboundQueueHandler<SplitWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        SplitWidget<U, G> as obj,
+        U as aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                !obj.data.disabled
+                    && *button == base::MouseButton::Left
+                    && obj.divider_at(*pos).is_some()
+            }) {
+                let index = obj.divider_at(*pos).unwrap();
+                let now = aux.now();
+                let double_click = obj
+                    .last_press
+                    .map(|(last, time)| last == index && now.duration_since(time) <= DOUBLE_CLICK_TIME)
+                    .unwrap_or(false);
+
+                if double_click {
+                    obj.last_press = None;
+                    obj.toggle_collapse(index);
+                } else {
+                    obj.last_press = Some((index, now));
+                    obj.drag = Some((index, obj.main_axis(*pos)));
+                }
+                obj.repaint();
+            }
+        }
+
+        mouse_release => {
+            if event.with(|(_, button, _)| *button == base::MouseButton::Left && obj.drag.is_some()).is_some() {
+                obj.drag = None;
+                obj.repaint();
+            }
+        }
+
+        mouse_move => {
+            if let Some((pos, _)) = event.with(|_| obj.drag.is_some()) {
+                obj.drag_to(*pos);
+            }
+
+            let hovered = obj.divider_at(event.get().0);
+            if hovered != obj.hovered {
+                obj.hovered = hovered;
+                obj.repaint();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Split {
+    pub direction: SplitDirection,
+    /// Thickness of the dividers between panes.
+    pub divider_thickness: f32,
+    /// Color of the divider line.
+    pub color: Color,
+    /// Color of a hovered or dragged divider.
+    pub highlight: Color,
+    pub contrast: draw::ThemeContrast,
+    /// Whether dividers can be dragged.
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Split
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = SplitWidget<U, G>;
+}
+
+impl Split {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Split {
+            direction: SplitDirection::Horizontal,
+            divider_thickness: 5.0,
+            color: draw::weaken(data.scheme.over_control_outset, 0.8, data.contrast),
+            highlight: data.scheme.focus,
+            contrast: data.contrast,
+            disabled: false,
+        }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        theme: &dyn draw::Theme,
+        u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> SplitWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            SplitWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.layout.mark_dirty(); obj.repaint(); } }
+        };
+
+        graph = graph.add("split", split_handler::<U, G>().bind(u_aux.window_queue()));
+
+        SplitWidget {
+            data,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            dividers: Vec::new(),
+            hovered: None,
+            drag: None,
+            last_press: None,
+            pending_positions: None,
+            painter: theme.split(),
+            graph: graph.into(),
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            visibility: Default::default(),
+            command_group: Default::default(),
+            drop_event: Default::default(),
+            layout: Default::default(),
+
+            phantom_g: Default::default(),
+        }
+    }
+}
+
+impl<U, G> SplitWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the position of each divider as a fraction (between 0 and 1) of the space available to the panes.
+    ///
+    /// These can be persisted and later passed to `restore_positions`.
+    pub fn positions(&self) -> Vec<f32> {
+        let length = self.available_length();
+        let mut advance = 0.0;
+        let mut positions = Vec::new();
+        for (_, child) in self.rects.iter().take(self.rects.len().saturating_sub(1)) {
+            advance += child.size.unwrap_or(0.0);
+            positions.push(if length > 0.0 { advance / length } else { 0.0 });
+        }
+        positions
+    }
+
+    /// Restores divider positions previously returned by `positions`.
+    ///
+    /// Positions are applied by the next relayout pass, once all the panes have been pushed.
+    pub fn restore_positions(&mut self, positions: &[f32]) {
+        self.pending_positions = Some(positions.to_vec());
+        self.layout.mark_dirty();
+    }
+
+    fn on_transform(&mut self) {
+        self.layout.mark_dirty();
+        self.repaint();
+        base::LayableWidget::notify_layout(self);
+    }
+
+    /// Returns the component of a point along the split direction.
+    fn main_axis(&self, point: AbsolutePoint) -> f32 {
+        match self.data.direction {
+            SplitDirection::Horizontal => point.x,
+            SplitDirection::Vertical => point.y,
+        }
+    }
+
+    /// Returns the space along the split direction which is available to panes (i.e. excluding dividers).
+    fn available_length(&self) -> f32 {
+        let size = self.rect.size;
+        let length = match self.data.direction {
+            SplitDirection::Horizontal => size.width,
+            SplitDirection::Vertical => size.height,
+        };
+        let dividers = self.rects.len().saturating_sub(1) as f32;
+        (length - dividers * self.data.divider_thickness).max(0.0)
+    }
+
+    fn divider_at(&self, pos: AbsolutePoint) -> Option<usize> {
+        self.dividers.iter().position(|divider| {
+            self.painter.mouse_hint(divider.cast_unit()).cast_unit::<AbsoluteUnit>().contains(pos)
+        })
+    }
+
+    /// Moves the dragged divider to follow the cursor, respecting pane size limits.
+    fn drag_to(&mut self, pos: AbsolutePoint) {
+        if let Some((index, last)) = self.drag {
+            let current = self.main_axis(pos);
+            let moved = self.move_divider(index, current - last);
+            self.drag = Some((index, last + moved));
+        }
+    }
+
+    /// Moves divider `index` by `delta`, clamped by the size limits of the adjacent panes.
+    /// Returns the distance actually moved.
+    fn move_divider(&mut self, index: usize, delta: f32) -> f32 {
+        let (before, after) = match (self.rects.get_index(index), self.rects.get_index(index + 1)) {
+            (Some((_, before)), Some((_, after))) => {
+                ((before.size.unwrap_or(0.0), before.data), (after.size.unwrap_or(0.0), after.data))
+            }
+            _ => return 0.0,
+        };
+
+        let lower = (before.1.min_size - before.0).max(after.0 - after.1.max_size);
+        let upper = (before.1.max_size - before.0).min(after.0 - after.1.min_size);
+        if lower > upper {
+            return 0.0;
+        }
+
+        let delta = delta.max(lower).min(upper);
+        if delta != 0.0 {
+            self.rects.get_index_mut(index).unwrap().1.size = Some(before.0 + delta);
+            self.rects.get_index_mut(index + 1).unwrap().1.size = Some(after.0 - delta);
+            self.layout.mark_dirty();
+        }
+        delta
+    }
+
+    /// Collapses (or expands, if already collapsed) the collapsible pane adjacent to divider `index`.
+    /// The pane before the divider takes precedence.
+    fn toggle_collapse(&mut self, index: usize) {
+        let (pane, neighbour) = match (self.rects.get_index(index), self.rects.get_index(index + 1))
+        {
+            (Some((_, before)), Some(_)) if before.data.collapsible => (index, index + 1),
+            (Some(_), Some((_, after))) if after.data.collapsible => (index + 1, index),
+            _ => return,
+        };
+
+        let (size, restore_size) = {
+            let pane = &self.rects.get_index(pane).unwrap().1;
+            (pane.size.unwrap_or(0.0), pane.restore_size)
+        };
+        let neighbour_size = self.rects.get_index(neighbour).unwrap().1.size.unwrap_or(0.0);
+
+        let (new_size, new_neighbour_size) = if size > 0.0 {
+            self.rects.get_index_mut(pane).unwrap().1.restore_size = size;
+            (0.0, neighbour_size + size)
+        } else {
+            let neighbour_min = self.rects.get_index(neighbour).unwrap().1.data.min_size;
+            let expanded = restore_size.min(neighbour_size - neighbour_min).max(0.0);
+            (expanded, neighbour_size - expanded)
+        };
+
+        self.rects.get_index_mut(pane).unwrap().1.size = Some(new_size);
+        self.rects.get_index_mut(neighbour).unwrap().1.size = Some(new_neighbour_size);
+        self.layout.mark_dirty();
+    }
+
+    /// Assigns sizes to unsized panes and fits all pane sizes to the available space.
+    fn fit_sizes(&mut self) {
+        let length = self.available_length();
+
+        if let Some(positions) = self.pending_positions.take() {
+            let mut last = 0.0;
+            let count = self.rects.len();
+            for (i, (_, child)) in self.rects.iter_mut().enumerate() {
+                let position = if i + 1 == count {
+                    1.0
+                } else {
+                    positions.get(i).cloned().unwrap_or(last).max(last).min(1.0)
+                };
+                child.size = Some((position - last) * length);
+                last = position;
+            }
+        }
+
+        let (assigned, unsized_count) =
+            self.rects.values().fold((0.0, 0), |(assigned, unsized_count), child| {
+                match child.size {
+                    Some(size) => (assigned + size, unsized_count),
+                    None => (assigned, unsized_count + 1),
+                }
+            });
+        if unsized_count > 0 {
+            let share = ((length - assigned) / unsized_count as f32).max(0.0);
+            for child in self.rects.values_mut() {
+                if child.size.is_none() {
+                    let size = child.data.size.unwrap_or(share);
+                    child.size = Some(size);
+                    child.restore_size = size;
+                }
+            }
+        }
+
+        // Scale expanded panes proportionally to the available space.
+        let total: f32 = self.rects.values().map(|child| child.size.unwrap_or(0.0)).sum();
+        if total > 0.0 && (total - length).abs() > std::f32::EPSILON {
+            let factor = length / total;
+            for child in self.rects.values_mut() {
+                child.size = child.size.map(|size| size * factor);
+            }
+        }
+
+        for child in self.rects.values_mut() {
+            if let Some(size) = child.size {
+                if size > 0.0 {
+                    child.size = Some(size.max(child.data.min_size).min(child.data.max_size));
+                }
+            }
+        }
+
+        // Spread any remaining difference caused by size limits over the expanded panes (starting from the last one),
+        // without pushing any of them past their limits.
+        let total: f32 = self.rects.values().map(|child| child.size.unwrap_or(0.0)).sum();
+        let mut remaining = length - total;
+        for child in self.rects.values_mut().rev() {
+            if remaining.abs() <= std::f32::EPSILON {
+                break;
+            }
+            if let Some(size) = child.size.filter(|&size| size > 0.0) {
+                let new_size =
+                    (size + remaining).max(child.data.min_size).min(child.data.max_size).max(0.0);
+                remaining -= new_size - size;
+                child.size = Some(new_size);
+            }
+        }
+    }
+
+    fn derive_state(&self) -> state::SplitState {
+        state::SplitState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            dividers: self
+                .dividers
+                .iter()
+                .enumerate()
+                .map(|(i, &divider)| {
+                    let mut interaction = state::InteractionState::empty();
+                    if self.hovered == Some(i) {
+                        interaction.insert(state::InteractionState::HOVERED);
+                    }
+                    if self.drag.map(|(index, _)| index == i).unwrap_or(false) {
+                        interaction.insert(state::InteractionState::PRESSED);
+                    }
+                    (divider, interaction)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for SplitWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> base::Layout for SplitWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = SplitItem;
+
+    fn push(&mut self, data: Option<SplitItem>, child: &mut impl base::LayableWidget) {
+        self.layout.mark_dirty();
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            layout_node: self.layout.node().clone(),
+        });
+
+        let rect = child.abs_rect();
+        let data = data.unwrap_or_default();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                data,
+                evq,
+                node: child.layout_events().node().clone(),
+                drop_listener: child.drop_event().listen(),
+                rect,
+                original_rect: rect,
+                id,
+                size: None,
+                restore_size: data.size.unwrap_or(data.min_size),
+            },
+        );
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.layout.mark_dirty();
+            child.listen_to_layout(None);
+            if restore_original {
+                child.arrange(data.original_rect);
+            }
+        }
+    }
+
+    /// The split doesn't size itself from its children; it keeps whichever size it's given.
+    fn measure_children(&self, constraints: base::Constraints) -> Size {
+        constraints.constrain(self.size())
+    }
+}
+
+impl<U, G> Widget for SplitWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
+
+impl<U, G> base::WidgetChildren for SplitWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    fn relayout(&mut self) {
+        if self.layout.direction_changed() {
+            self.layout.mark_dirty();
+        }
+
+        {
+            let mut removals = Vec::new();
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                }
+                // Children can't resize themselves along the split direction; their rectangles are always overridden.
+                if let Some(base::LayoutEvent::Measure { rect, .. }) = data.evq.retrieve_newest() {
+                    if rect != data.rect {
+                        self.layout.mark_dirty();
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.remove(&removal);
+                self.layout.mark_dirty();
+            }
+        }
+
+        if self.layout.is_dirty() {
+            self.fit_sizes();
+
+            let abs_rect = self.abs_rect();
+            let layout_direction = self.layout.direction();
+            let thickness = self.data.divider_thickness;
+            let direction = self.data.direction;
+            let count = self.rects.len();
+            let mut advance = self.main_axis(abs_rect.origin);
+            self.dividers.clear();
+            for (i, (_, data)) in self.rects.iter_mut().enumerate() {
+                let size = data.size.unwrap_or(0.0);
+                let (rect, divider) = match direction {
+                    SplitDirection::Horizontal => (
+                        AbsoluteRect::new(
+                            AbsolutePoint::new(advance, abs_rect.origin.y),
+                            Size::new(size, abs_rect.size.height).cast_unit(),
+                        ),
+                        AbsoluteRect::new(
+                            AbsolutePoint::new(advance + size, abs_rect.origin.y),
+                            Size::new(thickness, abs_rect.size.height).cast_unit(),
+                        ),
+                    ),
+                    SplitDirection::Vertical => (
+                        AbsoluteRect::new(
+                            AbsolutePoint::new(abs_rect.origin.x, advance),
+                            Size::new(abs_rect.size.width, size).cast_unit(),
+                        ),
+                        AbsoluteRect::new(
+                            AbsolutePoint::new(abs_rect.origin.x, advance + size),
+                            Size::new(abs_rect.size.width, thickness).cast_unit(),
+                        ),
+                    ),
+                };
+
+                data.evq.emit_owned(base::LayoutEvent::Arrange {
+                    constraints: base::Constraints::tight(rect.size.cast_unit()),
+                    rect,
+                    direction: layout_direction,
+                });
+
+                data.node.invalidate();
+                data.rect = rect;
+
+                if i + 1 < count {
+                    self.dividers.push(divider);
+                }
+
+                advance += size + thickness;
+            }
+
+            self.layout.clear_dirty();
+            self.command_group.repaint();
+        }
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<Split> for SplitWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<Split> {
+        &mut self.data
+    }
+}
+
+impl<U, G> StoresParentPosition for SplitWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
+
+impl<U, G> draw::HasTheme for SplitWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn theme(&mut self) -> &mut dyn draw::Themed {
+        &mut self.painter
+    }
+
+    fn resize_from_theme(&mut self) {}
+}
+
+impl<U: base::UpdateAuxiliary, G: base::GraphicalAuxiliary> Drop for SplitWidget<U, G> {
+    fn drop(&mut self) {
+        self.drop_event.emit_owned(base::DropEvent);
+    }
+}
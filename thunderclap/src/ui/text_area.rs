@@ -46,7 +46,13 @@ where
         }
 
         key_press => {
-            if let Some((key, _)) = event.with(|_| obj.interaction().contains(state::InteractionState::FOCUSED)) {
+            if let Some((key, _)) = event.with(|(key, _)| {
+                obj.interaction().contains(state::InteractionState::FOCUSED)
+                    && (obj.consumes_keys()
+                        || *key == base::KeyInput::Back
+                        || *key == base::KeyInput::Left
+                        || *key == base::KeyInput::Right)
+            }) {
                 match key {
                     base::KeyInput::Back => {
                         obj.remove_char();
@@ -73,8 +79,17 @@ pub trait LogicalTextArea {
     fn remove_char(&mut self);
     /// Move text cursor by an offset.
     fn move_cursor(&mut self, offset: isize);
+    /// Returns whether every key press is consumed while focused, rather than only the keys used to edit the text.
+    #[inline]
+    fn consumes_keys(&self) -> bool {
+        true
+    }
 }
 
+/// Single line text input.
+///
+/// Within a right-to-left layout the text is aligned to the right, but it's still laid out left-to-right,
+/// so the arrow keys move the cursor visually (i.e. `Left` always moves the cursor to the left).
 #[derive(
     WidgetChildren,
     LayableWidget,
@@ -168,6 +183,8 @@ where
         }
         self.repaint();
         self.data.cursor += 1;
+        let text = self.data.text.clone();
+        self.event_queue.emit_owned(TextAreaEvent::UserModify(text));
     }
 
     #[inline]
@@ -179,6 +196,8 @@ where
                 self.data.text.remove(cursor - 1);
             }
             self.data.cursor -= 1;
+            let text = self.data.text.clone();
+            self.event_queue.emit_owned(TextAreaEvent::UserModify(text));
         }
     }
 
@@ -190,6 +209,11 @@ where
             self.data.cursor = cursor as _;
         }
     }
+
+    #[inline]
+    fn consumes_keys(&self) -> bool {
+        self.data.consume_keys
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -199,9 +223,14 @@ pub struct TextArea {
     pub typeface: draw::TypefaceStyle,
     pub color: Color,
     pub placeholder_color: Color,
+    /// How a placeholder which doesn't fit within the text area is displayed.
+    pub placeholder_overflow: draw::TextOverflow,
     pub cursor_color: Color,
     pub disabled: bool,
     pub cursor: usize,
+    /// Whether every key press is consumed while focused.
+    /// Otherwise only `Back`, `Left` and `Right` are, leaving other keys (e.g. `Escape`) to enclosing widgets.
+    pub consume_keys: bool,
 }
 
 impl<U, G> ui::WidgetDataTarget<U, G> for TextArea
@@ -221,9 +250,11 @@ impl TextArea {
             typeface: data.typography.body.clone(),
             color: data.scheme.over_control_inset,
             placeholder_color: draw::weaken(data.scheme.over_control_inset, 0.5, data.contrast),
+            placeholder_overflow: draw::TextOverflow::Ellipsis,
             cursor_color: draw::weaken(data.scheme.over_control_inset, 0.1, data.contrast),
             disabled: false,
             cursor: 0,
+            consume_keys: true,
         }
     }
 
@@ -262,6 +293,7 @@ impl TextArea {
                     rect: Default::default(),
                     data: data.clone(),
                     interaction: state::InteractionState::empty(),
+                    direction: Default::default(),
                 })
                 .cast_unit(),
         );
@@ -293,7 +325,7 @@ where
 {
     fn on_transform(&mut self) {
         self.repaint();
-        self.layout.notify(self.abs_rect());
+        base::LayableWidget::notify_layout(self);
     }
 
     fn derive_state(&self) -> state::TextAreaState {
@@ -301,6 +333,7 @@ where
             rect: self.abs_rect(),
             data: self.data.clone(),
             interaction: self.interaction,
+            direction: self.layout.direction(),
         }
     }
 }
@@ -333,8 +366,7 @@ where
         graph.update_all(self, aux);
         self.graph = Some(graph);
 
-        if let Some(rect) = self.layout.receive() {
-            self.set_ctxt_rect(rect);
+        if self.layout.direction_changed() {
             self.command_group.repaint();
         }
     }
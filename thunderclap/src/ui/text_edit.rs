@@ -0,0 +1,677 @@
+//! Multi-line text editor widget.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{
+            Color, CommandGroup, DisplayCommand, DisplayText, GraphicsDisplay, Point, Rect,
+            TextDisplayItem,
+        },
+        event::RcEventQueue,
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{marker::PhantomData, ops::Range},
+};
+
+/// Describes a modification made to the text of a `TextEdit`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextModification {
+    /// Character range of the text (before the modification) which was replaced.
+    pub range: Range<usize>,
+    /// Text which replaced `range`.
+    pub text: String,
+}
+
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum TextEditEvent {
+    /// The text edit gained focus.
+    #[event_key(focus)]
+    Focus,
+    /// The text edit lost focus.
+    #[event_key(blur)]
+    Blur,
+    /// The user modified text within the text edit.
+    #[event_key(user_modify)]
+    UserModify(TextModification),
+}
+
+pub fn text_edit_handler<U, G>(
+) -> vg::UnboundQueueHandler<TextEditWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        TextEditWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        text_input => {
+            if let Some(&c) = event.with(|c| {
+                obj.interaction.contains(state::InteractionState::FOCUSED) && !c.is_control()
+            }) {
+                obj.replace(obj.data.cursor..obj.data.cursor, c.to_string());
+            }
+        }
+
+        key_press => {
+            if let Some((key, _)) = event.with(|_| obj.interaction.contains(state::InteractionState::FOCUSED)) {
+                let cursor = obj.data.cursor;
+                match key {
+                    base::KeyInput::Back => {
+                        if cursor > 0 {
+                            obj.replace(cursor - 1..cursor, String::new());
+                        }
+                    }
+                    base::KeyInput::Delete => {
+                        if cursor < obj.data.text.chars().count() {
+                            obj.replace(cursor..cursor + 1, String::new());
+                        }
+                    }
+                    base::KeyInput::Return | base::KeyInput::NumpadEnter => {
+                        obj.replace(cursor..cursor, "\n".to_string());
+                    }
+                    base::KeyInput::Left => {
+                        obj.set_cursor(cursor.saturating_sub(1));
+                    }
+                    base::KeyInput::Right => {
+                        obj.set_cursor(cursor + 1);
+                    }
+                    base::KeyInput::Up => {
+                        obj.move_vertically(-1);
+                    }
+                    base::KeyInput::Down => {
+                        obj.move_vertically(1);
+                    }
+                    base::KeyInput::Home => {
+                        let line = obj.cursor_line();
+                        obj.set_cursor(obj.lines[line].start);
+                    }
+                    base::KeyInput::End => {
+                        let line = obj.cursor_line();
+                        obj.set_cursor(obj.lines[line].end());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        mouse_scroll => {
+            let bounds = obj.abs_rect();
+            if let Some((_, delta, _)) = event.with(|(pos, _, _)| bounds.contains(*pos)) {
+                let line_height = obj.line_height();
+                obj.scroll_by(-delta.to_pixels(line_height).y);
+            }
+        }
+    }
+}
+
+/// A single visual (i.e. possibly soft-wrapped) line of text.
+#[derive(Debug, Clone)]
+struct VisualLine {
+    /// Character index of the first character in the line.
+    start: usize,
+    /// Amount of characters in the line.
+    len: usize,
+    /// Whether the line was soft-wrapped, i.e. continues on the next line without a line break.
+    wrapped: bool,
+    item: TextDisplayItem,
+}
+
+impl VisualLine {
+    #[inline]
+    fn end(&self) -> usize {
+        // The position after the last character of a wrapped line is the start of the next line.
+        if self.wrapped {
+            self.start + self.len - 1
+        } else {
+            self.start + self.len
+        }
+    }
+
+    /// Returns the horizontal position of the caret placed before the character at `index` (relative to the line start).
+    fn caret_x(&self, index: usize) -> f32 {
+        if index == 0 {
+            self.item.bottom_left.x
+        } else {
+            self.item.limited_bounds(index).unwrap().max_x()
+        }
+    }
+}
+
+fn display_text_len(text: &DisplayText) -> usize {
+    match text {
+        DisplayText::Simple(text) => text.chars().count(),
+        DisplayText::Shaped(glyphs) => glyphs.len(),
+    }
+}
+
+/// Converts a character index into a byte index of `text`.
+fn byte_index(text: &str, index: usize) -> usize {
+    text.char_indices().nth(index).map(|(i, _)| i).unwrap_or_else(|| text.len())
+}
+
+/// Multi-line text editor with soft wrapping and vertical scrolling.
+#[derive(
+    WidgetChildren,
+    LayableWidget,
+    DropNotifier,
+    HasVisibility,
+    Repaintable,
+    Movable,
+    Resizable,
+    OperatesVerbGraph,
+)]
+#[widget_children_trait(base::WidgetChildren)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+pub struct TextEditWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub event_queue: RcEventQueue<TextEditEvent>,
+    pub data: base::Observed<TextEdit>,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    painter: Box<dyn draw::Painter<state::TextEditState>>,
+    interaction: state::InteractionState,
+    lines: Vec<VisualLine>,
+    scroll: f32,
+    preferred_x: Option<f32>,
+    dirty: bool,
+    /// Data the lines were last computed from.
+    laid_out: TextEdit,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_visibility]
+    visibility: base::Visibility,
+    #[repaint_target]
+    command_group: CommandGroup,
+    #[widget_drop_event]
+    drop_event: RcEventQueue<base::DropEvent>,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+
+    phantom_g: PhantomData<G>,
+}
+
+impl<U, G> ui::InteractiveWidget for TextEditWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline(always)]
+    fn interaction(&mut self) -> &mut state::InteractionState {
+        &mut self.interaction
+    }
+
+    #[inline]
+    fn mouse_bounds(&self) -> RelativeRect {
+        self.painter.mouse_hint(self.rect)
+    }
+
+    #[inline]
+    fn disabled(&self) -> bool {
+        self.data.disabled
+    }
+
+    fn on_interaction_event(&mut self, event: ui::InteractionEvent) {
+        match event {
+            ui::InteractionEvent::Pressed(pos) => {
+                let cursor = self.cursor_at(pos);
+                self.set_cursor(cursor);
+            }
+            ui::InteractionEvent::Focus => {
+                self.repaint();
+                self.event_queue.emit_owned(TextEditEvent::Focus);
+            }
+            ui::InteractionEvent::Blur => {
+                self.repaint();
+                self.event_queue.emit_owned(TextEditEvent::Blur);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub text: String,
+    pub placeholder: String,
+    pub typeface: draw::TypefaceStyle,
+    pub color: Color,
+    pub placeholder_color: Color,
+    pub cursor_color: Color,
+    pub disabled: bool,
+    /// Whether lines which are too long are wrapped onto the next line.
+    pub wrap: bool,
+    /// Text cursor/caret position, as a character index.
+    pub cursor: usize,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for TextEdit
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = TextEditWidget<U, G>;
+}
+
+impl TextEdit {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        TextEdit {
+            text: "".into(),
+            placeholder: "".into(),
+            typeface: data.typography.body.clone(),
+            color: data.scheme.over_control_inset,
+            placeholder_color: draw::weaken(data.scheme.over_control_inset, 0.5, data.contrast),
+            cursor_color: draw::weaken(data.scheme.over_control_inset, 0.1, data.contrast),
+            disabled: false,
+            wrap: true,
+            cursor: 0,
+        }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        theme: &dyn draw::Theme,
+        u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> TextEditWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let laid_out = self.clone();
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            TextEditWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    if obj.lines_outdated() {
+                        obj.dirty = true;
+                    } else {
+                        // Only the cursor or the look of the caret changed, which doesn't affect the lines.
+                        obj.scroll_to_cursor();
+                        obj.repaint();
+                    }
+                }
+            }
+        };
+
+        graph = graph.add(
+            "interaction",
+            ui::basic_interaction_handler::<TextEditWidget<U, G>, U>().bind(u_aux.window_queue()),
+        );
+        graph = graph.add("text_edit", text_edit_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let mut text_edit = TextEditWidget {
+            event_queue: Default::default(),
+            data,
+
+            graph: graph.into(),
+            painter: theme.text_edit(),
+            interaction: state::InteractionState::empty(),
+            lines: Vec::new(),
+            scroll: 0.0,
+            preferred_x: None,
+            dirty: false,
+            laid_out,
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            visibility: Default::default(),
+            command_group: Default::default(),
+            drop_event: Default::default(),
+            layout: Default::default(),
+
+            phantom_g: Default::default(),
+        };
+
+        text_edit.resize_from_theme();
+        text_edit.update_lines();
+
+        text_edit
+    }
+}
+
+impl<U, G> TextEditWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn on_transform(&mut self) {
+        self.dirty = true;
+        self.repaint();
+        base::LayableWidget::notify_layout(self);
+    }
+
+    fn line_height(&self) -> f32 {
+        let font = self.data.typeface.typeface.pick(self.data.typeface.style);
+        let metrics = font.1.font.metrics();
+        (metrics.ascent - metrics.descent + metrics.line_gap) / metrics.units_per_em as f32
+            * self.data.typeface.size
+    }
+
+    fn ascent(&self) -> f32 {
+        let font = self.data.typeface.typeface.pick(self.data.typeface.style);
+        let metrics = font.1.font.metrics();
+        metrics.ascent / metrics.units_per_em as f32 * self.data.typeface.size
+    }
+
+    /// Re-computes the (wrapped) visual lines from the text.
+    fn update_lines(&mut self) {
+        let rect = self.abs_rect();
+        let line_height = self.line_height();
+        let ascent = self.ascent();
+        let font = self.data.typeface.typeface.pick(self.data.typeface.style);
+
+        let (text, color) = if self.data.text.is_empty() {
+            (&self.data.placeholder, self.data.placeholder_color)
+        } else {
+            (&self.data.text, self.data.color)
+        };
+
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for paragraph in text.split('\n') {
+            let item = TextDisplayItem {
+                text: paragraph.to_string().into(),
+                font: font.0.clone(),
+                font_info: font.1.clone(),
+                size: self.data.typeface.size,
+                bottom_left: rect.origin.cast_unit(),
+                color: color.into(),
+            };
+
+            let items = if self.data.wrap && !paragraph.is_empty() {
+                item.linebreak(rect.cast_unit(), line_height, false).unwrap()
+            } else {
+                vec![item]
+            };
+
+            let count = items.len();
+            let mut offset = 0;
+            for (i, item) in items.into_iter().enumerate() {
+                let len = display_text_len(&item.text);
+                lines.push(VisualLine { start: start + offset, len, wrapped: i + 1 < count, item });
+                offset += len;
+            }
+
+            start += paragraph.chars().count() + 1;
+        }
+
+        // Position lines uniformly, regardless of how the line breaker placed them.
+        for (i, line) in lines.iter_mut().enumerate() {
+            line.item.bottom_left = Point::new(
+                rect.origin.x,
+                rect.origin.y + i as f32 * line_height + ascent - self.scroll,
+            );
+        }
+
+        self.lines = lines;
+        self.dirty = false;
+        self.laid_out = self.data.clone();
+        self.scroll_to_cursor();
+        self.repaint();
+    }
+
+    /// Returns whether the data has changed in a way which affects the lines since they were last computed.
+    fn lines_outdated(&self) -> bool {
+        let (data, laid_out) = (&*self.data, &self.laid_out);
+        data.text != laid_out.text
+            || data.placeholder != laid_out.placeholder
+            || data.typeface != laid_out.typeface
+            || data.color != laid_out.color
+            || data.placeholder_color != laid_out.placeholder_color
+            || data.wrap != laid_out.wrap
+    }
+
+    /// Returns the total height of the text content.
+    fn content_height(&self) -> f32 {
+        self.lines.len() as f32 * self.line_height()
+    }
+
+    /// Scrolls the content vertically by `delta` pixels, clamped to the content.
+    fn scroll_by(&mut self, delta: f32) {
+        let max_scroll = (self.content_height() - self.rect.size.height).max(0.0);
+        let scroll = (self.scroll + delta).max(0.0).min(max_scroll);
+        if scroll != self.scroll {
+            let offset = self.scroll - scroll;
+            for line in &mut self.lines {
+                line.item.bottom_left.y += offset;
+            }
+            self.scroll = scroll;
+            self.repaint();
+        }
+    }
+
+    /// Scrolls just enough for the line containing the cursor to be visible.
+    fn scroll_to_cursor(&mut self) {
+        if self.lines.is_empty() {
+            return;
+        }
+
+        let line_height = self.line_height();
+        let top = self.cursor_line() as f32 * line_height;
+        if top < self.scroll {
+            self.scroll_by(top - self.scroll);
+        } else if top + line_height > self.scroll + self.rect.size.height {
+            self.scroll_by(top + line_height - self.scroll - self.rect.size.height);
+        }
+    }
+
+    /// Returns the index of the visual line containing the cursor.
+    fn cursor_line(&self) -> usize {
+        let cursor = self.data.cursor;
+        self.lines.iter().rposition(|line| line.start <= cursor).unwrap_or(0)
+    }
+
+    /// Returns the character index nearest to `x` within `line`.
+    fn index_in_line(&self, line: usize, x: f32) -> usize {
+        let line = &self.lines[line];
+        let end = line.end() - line.start;
+        (0..=end)
+            .min_by(|&a, &b| {
+                (line.caret_x(a) - x).abs().partial_cmp(&(line.caret_x(b) - x).abs()).unwrap()
+            })
+            .unwrap_or(0)
+            + line.start
+    }
+
+    /// Returns the character index nearest to an absolute point.
+    fn cursor_at(&self, pos: AbsolutePoint) -> usize {
+        if self.lines.is_empty() || self.data.text.is_empty() {
+            return 0;
+        }
+
+        let line = ((pos.y - self.abs_rect().origin.y + self.scroll) / self.line_height()).floor();
+        let line = (line.max(0.0) as usize).min(self.lines.len() - 1);
+        self.index_in_line(line, pos.x)
+    }
+
+    fn set_cursor(&mut self, cursor: usize) {
+        self.preferred_x = None;
+        self.data.cursor = cursor.min(self.data.text.chars().count());
+        self.scroll_to_cursor();
+        self.repaint();
+    }
+
+    /// Moves the cursor up (negative) or down (positive) by visual lines, keeping the preferred column.
+    fn move_vertically(&mut self, lines: isize) {
+        if self.lines.is_empty() || self.data.text.is_empty() {
+            return;
+        }
+
+        let current = self.cursor_line();
+        let x = match self.preferred_x {
+            Some(x) => x,
+            None => self.lines[current].caret_x(self.data.cursor - self.lines[current].start),
+        };
+
+        let target = (current as isize + lines).max(0).min(self.lines.len() as isize - 1) as usize;
+        let cursor = if target == current {
+            if lines < 0 {
+                0
+            } else {
+                self.data.text.chars().count()
+            }
+        } else {
+            self.index_in_line(target, x)
+        };
+
+        self.data.cursor = cursor;
+        self.preferred_x = Some(x);
+        self.scroll_to_cursor();
+        self.repaint();
+    }
+
+    /// Replaces a character range of the text, moving the cursor to the end of the replacement.
+    fn replace(&mut self, range: Range<usize>, text: String) {
+        {
+            let start = byte_index(&self.data.text, range.start);
+            let end = byte_index(&self.data.text, range.end);
+            self.data.text.replace_range(start..end, &text);
+        }
+        self.preferred_x = None;
+        self.data.cursor = range.start + text.chars().count();
+        // Later key presses within the same update work on the lines, so they can't wait for the next update.
+        self.update_lines();
+        self.event_queue.emit_owned(TextEditEvent::UserModify(TextModification { range, text }));
+    }
+
+    fn derive_state(&self) -> state::TextEditState {
+        let caret = if self.lines.is_empty() || self.data.text.is_empty() {
+            let rect = self.abs_rect();
+            (rect.origin, AbsolutePoint::new(rect.origin.x, rect.origin.y + self.line_height()))
+        } else {
+            let line = &self.lines[self.cursor_line()];
+            let x = line.caret_x(self.data.cursor - line.start);
+            let top = line.item.bottom_left.y - self.ascent();
+            (AbsolutePoint::new(x, top), AbsolutePoint::new(x, top + self.line_height()))
+        };
+
+        state::TextEditState {
+            rect: self.abs_rect(),
+            data: self.data.clone(),
+            lines: self.lines.iter().map(|line| line.item.clone()).collect(),
+            caret,
+            interaction: self.interaction,
+        }
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for TextEditWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> Widget for TextEditWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.painter.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        if self.dirty {
+            self.update_lines();
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let state = self.derive_state();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || painter.draw(state),
+            Default::default(),
+            None,
+            None,
+        );
+    }
+}
+
+impl<U, G> StoresParentPosition for TextEditWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
+
+impl<U, G> draw::HasTheme for TextEditWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn theme(&mut self) -> &mut dyn draw::Themed {
+        &mut self.painter
+    }
+
+    fn resize_from_theme(&mut self) {
+        self.set_size(self.painter.size_hint(self.derive_state()));
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<TextEdit> for TextEditWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<TextEdit> {
+        &mut self.data
+    }
+}
+
+impl<U, G> ui::DefaultEventQueue<TextEditEvent> for TextEditWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_event_queue(&self) -> &RcEventQueue<TextEditEvent> {
+        &self.event_queue
+    }
+}
+
+impl<U: base::UpdateAuxiliary, G: base::GraphicalAuxiliary> Drop for TextEditWidget<U, G> {
+    fn drop(&mut self) {
+        self.drop_event.emit_owned(base::DropEvent);
+    }
+}
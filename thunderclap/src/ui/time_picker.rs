@@ -0,0 +1,732 @@
+//! Time picker widget.
+
+use {
+    crate::{
+        base::{self, Repaintable, Resizable},
+        draw::{self, state, HasTheme},
+        geom::*,
+        ui::{self, picker},
+    },
+    reclutch::{
+        display::{CommandGroup, DisplayCommand, GraphicsDisplay, Rect, Size},
+        event::RcEventQueue,
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{fmt, marker::PhantomData},
+};
+
+/// Width of a column in the time popup.
+const COLUMN_WIDTH: f32 = 44.0;
+/// Height of the step buttons above and below each value.
+const ARROW_HEIGHT: f32 = 20.0;
+/// Height of the value of each column.
+const VALUE_HEIGHT: f32 = 30.0;
+/// Padding around the contents of the time popup.
+const PADDING: f32 = 6.0;
+
+/// A time of day, with a precision of one second.
+///
+/// Times are ordered chronologically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time {
+    hour: u32,
+    minute: u32,
+    second: u32,
+}
+
+impl Time {
+    /// Creates a time, returning `None` if any component is out of range.
+    pub fn new(hour: u32, minute: u32, second: u32) -> Option<Self> {
+        if hour < 24 && minute < 60 && second < 60 {
+            Some(Time { hour, minute, second })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the hour, between 0 and 23.
+    #[inline]
+    pub fn hour(self) -> u32 {
+        self.hour
+    }
+
+    #[inline]
+    pub fn minute(self) -> u32 {
+        self.minute
+    }
+
+    #[inline]
+    pub fn second(self) -> u32 {
+        self.second
+    }
+}
+
+/// Formats the time as `HH:MM:SS`.
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum TimePickerEvent {
+    /// The time popup was opened.
+    #[event_key(open)]
+    Open,
+    /// The time popup was closed.
+    #[event_key(close)]
+    Close,
+    /// The user changed the time.
+    #[event_key(changed)]
+    Changed(Time),
+}
+
+/// Component of the time shown as a column in the popup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeColumn {
+    Hour,
+    Minute,
+    Second,
+    Meridiem,
+}
+
+/// Part of the time popup under the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeHit {
+    Up(usize),
+    Value(usize),
+    Down(usize),
+}
+
+pub fn time_picker_handler<U, G>(
+) -> vg::UnboundQueueHandler<TimePickerWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        TimePickerWidget<U, G> as obj,
+        U as _aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            let pos = event.get().0;
+            let in_field = obj.abs_convert_rect(ui::InteractiveWidget::mouse_bounds(obj)).contains(pos);
+            if obj.open && !in_field {
+                if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                    *button == base::MouseButton::Left && obj.popup_rect().contains(*pos)
+                }) {
+                    // Clicking the popup shouldn't take keyboard focus away from the picker.
+                    obj.interaction.insert(state::InteractionState::FOCUSED);
+                    match obj.hit_at(*pos) {
+                        Some(TimeHit::Up(column)) => {
+                            obj.selected = column;
+                            obj.step(column, 1);
+                        }
+                        Some(TimeHit::Down(column)) => {
+                            obj.selected = column;
+                            obj.step(column, -1);
+                        }
+                        Some(TimeHit::Value(column)) => {
+                            obj.selected = column;
+                            obj.repaint();
+                        }
+                        None => {}
+                    }
+                } else {
+                    // Clicking anywhere else dismisses the popup.
+                    obj.close_popup();
+                }
+            }
+        }
+
+        mouse_move => {
+            if obj.open {
+                let pos = event.get().0;
+                let hovered = obj.hit_at(pos);
+                if hovered != obj.hovered {
+                    obj.hovered = hovered;
+                    obj.repaint();
+                }
+                event.with(|(pos, _)| obj.popup_rect().contains(*pos));
+            }
+        }
+
+        mouse_scroll => {
+            if let Some((pos, delta, _)) = event.with(|(pos, _, _)| obj.open && obj.popup_rect().contains(*pos)) {
+                let column = match obj.hit_at(*pos) {
+                    Some(TimeHit::Up(column))
+                    | Some(TimeHit::Value(column))
+                    | Some(TimeHit::Down(column)) => Some(column),
+                    None => None,
+                };
+                if let Some(column) = column {
+                    let y = delta.to_pixels(1.0).y;
+                    if y > 0.0 {
+                        obj.step(column, 1);
+                    } else if y < 0.0 {
+                        obj.step(column, -1);
+                    }
+                }
+            }
+        }
+
+        key_press => {
+            if obj.interaction.contains(state::InteractionState::FOCUSED) && !obj.data.disabled {
+                event.with(|&(key, _)| obj.handle_key(key));
+            }
+        }
+    }
+}
+
+/// Bundles the painters for the field and the time popup so that both follow theme changes.
+struct TimePickerPainters {
+    field: Box<dyn draw::Painter<state::PickerFieldState>>,
+    popup: Box<dyn draw::Painter<state::TimePopupState>>,
+}
+
+impl draw::Themed for TimePickerPainters {
+    fn load_theme(&mut self, theme: &dyn draw::Theme, aux: &dyn base::GraphicalAuxiliary) {
+        self.field.load_theme(theme, aux);
+        self.popup.load_theme(theme, aux);
+    }
+}
+
+/// Compact field showing a time, which pops up columns of hours, minutes (and optionally seconds) to change the time.
+///
+/// While focused, the popup opens with `Down`, `Space` or `Return`.
+/// Within the popup, `Left`/`Right` select a column, `Up`/`Down` step its value and `Return`/`Escape` close the popup.
+/// Values can also be stepped with the mouse wheel.
+#[derive(
+    WidgetChildren,
+    LayableWidget,
+    DropNotifier,
+    HasVisibility,
+    Repaintable,
+    Movable,
+    Resizable,
+    OperatesVerbGraph,
+)]
+#[widget_children_trait(base::WidgetChildren)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+pub struct TimePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub event_queue: RcEventQueue<TimePickerEvent>,
+    pub data: base::Observed<TimePicker>,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    painters: TimePickerPainters,
+    interaction: state::InteractionState,
+    open: bool,
+    /// Index of the column selected for keyboard input.
+    selected: usize,
+    hovered: Option<TimeHit>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_visibility]
+    visibility: base::Visibility,
+    #[repaint_target]
+    command_group: CommandGroup,
+    #[repaint_target]
+    popup_command_group: CommandGroup,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+    #[widget_drop_event]
+    drop_event: RcEventQueue<base::DropEvent>,
+
+    phantom_g: PhantomData<G>,
+}
+
+impl<U, G> ui::InteractiveWidget for TimePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline(always)]
+    fn interaction(&mut self) -> &mut state::InteractionState {
+        &mut self.interaction
+    }
+
+    #[inline]
+    fn mouse_bounds(&self) -> RelativeRect {
+        self.painters.field.mouse_hint(self.rect)
+    }
+
+    #[inline]
+    fn disabled(&self) -> bool {
+        self.data.disabled
+    }
+
+    fn on_interaction_event(&mut self, event: ui::InteractionEvent) {
+        self.repaint();
+        if let ui::InteractionEvent::Released(pos) = event {
+            if self.abs_convert_rect(self.mouse_bounds()).contains(pos) {
+                if self.open {
+                    self.close_popup();
+                } else {
+                    self.open_popup();
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimePicker {
+    pub time: Time,
+    /// Earliest time which can be picked.
+    pub min: Option<Time>,
+    /// Latest time which can be picked.
+    pub max: Option<Time>,
+    /// Amount of minutes the minute column steps by.
+    pub minute_step: u32,
+    /// Whether seconds are shown and can be changed.
+    pub show_seconds: bool,
+    /// Whether hours are shown from 1 to 12, followed by `meridiem`.
+    pub twelve_hour: bool,
+    /// Names of the first and second half of the day, used when `twelve_hour` is set.
+    pub meridiem: [String; 2],
+    pub style: ui::PickerStyle,
+    pub disabled: bool,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for TimePicker
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = TimePickerWidget<U, G>;
+}
+
+impl TimePicker {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        TimePicker {
+            time: Time { hour: 0, minute: 0, second: 0 },
+            min: None,
+            max: None,
+            minute_step: 1,
+            show_seconds: false,
+            twelve_hour: false,
+            meridiem: ["AM".to_string(), "PM".to_string()],
+            style: ui::PickerStyle::from_theme(theme),
+            disabled: false,
+        }
+    }
+
+    /// Returns `true` if `time` is within `min`/`max`.
+    pub fn is_selectable(&self, time: Time) -> bool {
+        self.min.map(|min| time >= min).unwrap_or(true)
+            && self.max.map(|max| time <= max).unwrap_or(true)
+    }
+
+    /// Formats `time` as it's shown in the field.
+    pub fn format(&self, time: Time) -> String {
+        let mut text = if self.twelve_hour {
+            format!("{}:{:02}", (time.hour + 11) % 12 + 1, time.minute)
+        } else {
+            format!("{:02}:{:02}", time.hour, time.minute)
+        };
+        if self.show_seconds {
+            text.push_str(&format!(":{:02}", time.second));
+        }
+        if self.twelve_hour {
+            text.push(' ');
+            text.push_str(&self.meridiem[(time.hour / 12) as usize]);
+        }
+        text
+    }
+
+    fn columns(&self) -> Vec<TimeColumn> {
+        let mut columns = vec![TimeColumn::Hour, TimeColumn::Minute];
+        if self.show_seconds {
+            columns.push(TimeColumn::Second);
+        }
+        if self.twelve_hour {
+            columns.push(TimeColumn::Meridiem);
+        }
+        columns
+    }
+
+    fn column_text(&self, column: TimeColumn) -> String {
+        match column {
+            TimeColumn::Hour if self.twelve_hour => ((self.time.hour + 11) % 12 + 1).to_string(),
+            TimeColumn::Hour => format!("{:02}", self.time.hour),
+            TimeColumn::Minute => format!("{:02}", self.time.minute),
+            TimeColumn::Second => format!("{:02}", self.time.second),
+            TimeColumn::Meridiem => self.meridiem[(self.time.hour / 12) as usize].clone(),
+        }
+    }
+
+    /// Returns the time with one component stepped forwards (`direction > 0`) or backwards, wrapping within its range.
+    fn stepped(&self, column: TimeColumn, direction: i32) -> Time {
+        let time = self.time;
+        match column {
+            TimeColumn::Hour => {
+                Time { hour: (time.hour as i32 + direction).rem_euclid(24) as u32, ..time }
+            }
+            TimeColumn::Minute => {
+                let step = self.minute_step.max(1) as i32;
+                let minute = time.minute as i32;
+                // Snap onto the step before moving, so that off-step minutes land on a step.
+                let snapped = minute - minute % step;
+                let minute = if direction < 0 && snapped != minute {
+                    snapped
+                } else {
+                    (snapped + direction.signum() * step).rem_euclid(60)
+                };
+                Time { minute: minute as u32, ..time }
+            }
+            TimeColumn::Second => {
+                Time { second: (time.second as i32 + direction).rem_euclid(60) as u32, ..time }
+            }
+            TimeColumn::Meridiem => Time { hour: (time.hour + 12) % 24, ..time },
+        }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        theme: &dyn draw::Theme,
+        u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> TimePickerWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            TimePickerWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.selected = obj.selected.min(obj.data.columns().len() - 1);
+                    obj.resize_from_theme();
+                    obj.repaint();
+                }
+            }
+        };
+
+        graph = graph.add(
+            "interaction",
+            ui::basic_interaction_handler::<TimePickerWidget<U, G>, U>().bind(u_aux.window_queue()),
+        );
+        graph = graph.add("time_picker", time_picker_handler::<U, G>().bind(u_aux.window_queue()));
+
+        let mut time_picker = TimePickerWidget {
+            event_queue: Default::default(),
+            data,
+
+            graph: graph.into(),
+            painters: TimePickerPainters { field: theme.picker_field(), popup: theme.time_popup() },
+            interaction: state::InteractionState::empty(),
+            open: false,
+            selected: 0,
+            hovered: None,
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            visibility: Default::default(),
+            command_group: Default::default(),
+            popup_command_group: Default::default(),
+            layout: Default::default(),
+            drop_event: Default::default(),
+
+            phantom_g: Default::default(),
+        };
+
+        time_picker.resize_from_theme();
+
+        time_picker
+    }
+}
+
+impl<U, G> TimePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn on_transform(&mut self) {
+        self.repaint();
+        base::LayableWidget::notify_layout(self);
+    }
+
+    /// Returns `true` if the time popup is open.
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn open_popup(&mut self) {
+        if self.open || self.data.disabled {
+            return;
+        }
+
+        self.open = true;
+        self.selected = 0;
+        self.hovered = None;
+        self.repaint();
+        self.event_queue.emit_owned(TimePickerEvent::Open);
+    }
+
+    fn close_popup(&mut self) {
+        if self.open {
+            self.open = false;
+            self.hovered = None;
+            self.repaint();
+            self.event_queue.emit_owned(TimePickerEvent::Close);
+        }
+    }
+
+    /// Steps the value of a column, unless the resulting time is out of range.
+    fn step(&mut self, column: usize, direction: i32) {
+        let time = match self.data.columns().get(column) {
+            Some(&column) => self.data.stepped(column, direction),
+            None => return,
+        };
+
+        if time != self.data.time && self.data.is_selectable(time) {
+            self.data.time = time;
+            self.event_queue.emit_owned(TimePickerEvent::Changed(time));
+        }
+        self.repaint();
+    }
+
+    /// Handles a key press, returning `true` if the key was consumed.
+    fn handle_key(&mut self, key: base::KeyInput) -> bool {
+        if !self.open {
+            return match key {
+                base::KeyInput::Down
+                | base::KeyInput::Space
+                | base::KeyInput::Return
+                | base::KeyInput::NumpadEnter => {
+                    self.open_popup();
+                    true
+                }
+                _ => false,
+            };
+        }
+
+        let column_count = self.data.columns().len();
+        match key {
+            base::KeyInput::Left => {
+                self.selected = (self.selected + column_count - 1) % column_count;
+                self.repaint();
+            }
+            base::KeyInput::Right | base::KeyInput::Tab => {
+                self.selected = (self.selected + 1) % column_count;
+                self.repaint();
+            }
+            base::KeyInput::Up => self.step(self.selected, 1),
+            base::KeyInput::Down => self.step(self.selected, -1),
+            base::KeyInput::Return
+            | base::KeyInput::NumpadEnter
+            | base::KeyInput::Space
+            | base::KeyInput::Escape => self.close_popup(),
+            _ => return false,
+        }
+
+        true
+    }
+
+    fn popup_rect(&self) -> AbsoluteRect {
+        AbsoluteRect::new(
+            picker::popup_origin(self.abs_rect()),
+            Size::new(
+                COLUMN_WIDTH * self.data.columns().len() as f32 + PADDING * 2.0,
+                ARROW_HEIGHT * 2.0 + VALUE_HEIGHT + PADDING * 2.0,
+            )
+            .cast_unit(),
+        )
+    }
+
+    /// Returns the step up button, value and step down button rectangles of a column.
+    fn column_rects(&self, column: usize) -> (AbsoluteRect, AbsoluteRect, AbsoluteRect) {
+        let popup = self.popup_rect();
+        let x = popup.min_x() + PADDING + column as f32 * COLUMN_WIDTH;
+        let y = popup.min_y() + PADDING;
+        let rect = |y: f32, height: f32| {
+            AbsoluteRect::new(AbsolutePoint::new(x, y), Size::new(COLUMN_WIDTH, height).cast_unit())
+        };
+
+        (
+            rect(y, ARROW_HEIGHT),
+            rect(y + ARROW_HEIGHT, VALUE_HEIGHT),
+            rect(y + ARROW_HEIGHT + VALUE_HEIGHT, ARROW_HEIGHT),
+        )
+    }
+
+    fn hit_at(&self, pos: AbsolutePoint) -> Option<TimeHit> {
+        (0..self.data.columns().len()).find_map(|column| {
+            let (up, value, down) = self.column_rects(column);
+            if up.contains(pos) {
+                Some(TimeHit::Up(column))
+            } else if value.contains(pos) {
+                Some(TimeHit::Value(column))
+            } else if down.contains(pos) {
+                Some(TimeHit::Down(column))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn derive_field_state(&self) -> state::PickerFieldState {
+        state::PickerFieldState {
+            rect: self.abs_rect(),
+            text: self.data.format(self.data.time),
+            style: self.data.style.clone(),
+            open: self.open,
+            disabled: self.data.disabled,
+            interaction: self.interaction,
+        }
+    }
+
+    fn derive_popup_state(&self) -> Option<state::TimePopupState> {
+        if !self.open {
+            return None;
+        }
+
+        Some(state::TimePopupState {
+            rect: self.popup_rect(),
+            style: self.data.style.clone(),
+            columns: self
+                .data
+                .columns()
+                .into_iter()
+                .enumerate()
+                .map(|(index, column)| {
+                    let (up, value, down) = self.column_rects(index);
+                    state::TimeColumnState { text: self.data.column_text(column), up, value, down }
+                })
+                .collect(),
+            selected: self.selected,
+            hovered: match self.hovered {
+                Some(TimeHit::Up(column)) => Some(self.column_rects(column).0),
+                Some(TimeHit::Down(column)) => Some(self.column_rects(column).2),
+                _ => None,
+            },
+        })
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for TimePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> Widget for TimePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.painters.field.paint_hint(self.rect).cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let field_state = self.derive_field_state();
+        let popup_state = self.derive_popup_state();
+
+        let painters = &mut self.painters;
+        self.command_group.push_with(
+            display,
+            || painters.field.draw(field_state),
+            Default::default(),
+            None,
+            None,
+        );
+        self.popup_command_group.push_with(
+            display,
+            || popup_state.map(|state| painters.popup.draw(state)).unwrap_or_default(),
+            ui::OVERLAY_Z_ORDER,
+            None,
+            None,
+        );
+    }
+}
+
+impl<U, G> StoresParentPosition for TimePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
+
+impl<U, G> HasTheme for TimePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn theme(&mut self) -> &mut dyn draw::Themed {
+        &mut self.painters
+    }
+
+    fn resize_from_theme(&mut self) {
+        self.set_size(self.painters.field.size_hint(self.derive_field_state()));
+    }
+}
+
+impl<U, G> ui::DefaultEventQueue<TimePickerEvent> for TimePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_event_queue(&self) -> &RcEventQueue<TimePickerEvent> {
+        &self.event_queue
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<TimePicker> for TimePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<TimePicker> {
+        &mut self.data
+    }
+}
+
+impl<U, G> Drop for TimePickerWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn drop(&mut self) {
+        self.drop_event.emit_owned(base::DropEvent);
+    }
+}
@@ -0,0 +1,650 @@
+//! Transient, non-blocking notifications ("toasts").
+
+use {
+    crate::{
+        base::{self, Repaintable},
+        draw::{self, state},
+        geom::*,
+        ui,
+    },
+    reclutch::{
+        display::{
+            Color, CommandGroup, DisplayCommand, GraphicsDisplay, Rect, Size, TextDisplayItem,
+        },
+        event::RcEventQueue,
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::{
+        marker::PhantomData,
+        time::{Duration, Instant},
+    },
+};
+
+/// Side length of the dismiss button of a toast.
+const CLOSE_SIZE: f32 = 16.0;
+
+/// Identifies a toast shown by a `Toaster`.
+pub type ToastId = u64;
+
+/// Severity of a toast, which decides its colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ToastKind {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// Corner of the toaster which toasts are stacked in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ToastCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Button shown on a toast, which emits `ToasterEvent::Action` when pressed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToastAction {
+    /// Identifier emitted when the action is pressed.
+    pub id: String,
+    pub text: String,
+}
+
+impl ToastAction {
+    pub fn new(id: impl Into<String>, text: impl Into<String>) -> Self {
+        ToastAction { id: id.into(), text: text.into() }
+    }
+}
+
+/// Background and text colors of each kind of toast.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToastPalette {
+    pub info: Color,
+    pub over_info: Color,
+    pub success: Color,
+    pub over_success: Color,
+    pub warning: Color,
+    pub over_warning: Color,
+    pub error: Color,
+    pub over_error: Color,
+}
+
+impl ToastPalette {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let scheme = &theme.data().scheme;
+        ToastPalette {
+            info: scheme.primary,
+            over_info: scheme.over_primary,
+            success: Color::new(0.157, 0.655, 0.271, 1.0),
+            over_success: Color::new(1.0, 1.0, 1.0, 1.0),
+            warning: Color::new(1.0, 0.827, 0.239, 1.0),
+            over_warning: Color::new(0.141, 0.161, 0.180, 1.0),
+            error: scheme.error,
+            over_error: scheme.over_error,
+        }
+    }
+
+    /// Returns the background and text color of a kind of toast.
+    pub fn colors(&self, kind: ToastKind) -> (Color, Color) {
+        match kind {
+            ToastKind::Info => (self.info, self.over_info),
+            ToastKind::Success => (self.success, self.over_success),
+            ToastKind::Warning => (self.warning, self.over_warning),
+            ToastKind::Error => (self.error, self.over_error),
+        }
+    }
+}
+
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum ToasterEvent {
+    /// The action button of a toast was pressed, carrying the toast and the action ID.
+    /// The toast is dismissed afterwards.
+    #[event_key(action)]
+    Action(ToastId, String),
+    /// A toast was dismissed, either by the user, its timer or `dismiss`.
+    #[event_key(dismiss)]
+    Dismiss(ToastId),
+}
+
+/// A single toast and the state of its timer.
+#[derive(Debug, Clone)]
+struct ToastEntry {
+    id: ToastId,
+    message: String,
+    kind: ToastKind,
+    action: Option<ToastAction>,
+    /// Time left on the timer; `None` if the toast stays until it's dismissed.
+    remaining: Option<Duration>,
+    /// When the timer expires, if it's running.
+    deadline: Option<Instant>,
+}
+
+impl ToastEntry {
+    fn pause(&mut self, now: Instant) {
+        if let Some(deadline) = self.deadline.take() {
+            self.remaining = Some(deadline.checked_duration_since(now).unwrap_or_default());
+        }
+    }
+}
+
+/// Part of a toast under the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ToastPart {
+    Body,
+    Action,
+    Close,
+}
+
+pub fn toaster_handler<U, G>() -> vg::UnboundQueueHandler<ToasterWidget<U, G>, U, base::WindowEvent>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    vg::unbound_queue_handler! {
+        ToasterWidget<U, G> as obj,
+        U as aux,
+        base::WindowEvent as event,
+
+        mouse_press => {
+            if let Some((pos, _, _)) = event.with(|(pos, button, _)| {
+                *button == base::MouseButton::Left && obj.hit_at(*pos).is_some()
+            }) {
+                match obj.hit_at(*pos) {
+                    Some((index, ToastPart::Action)) => {
+                        let id = obj.toasts[index].id;
+                        if let Some(action) = obj.toasts[index].action.clone() {
+                            obj.event_queue.emit_owned(ToasterEvent::Action(id, action.id));
+                        }
+                        obj.dismiss(id);
+                    }
+                    Some((index, ToastPart::Close)) => {
+                        let id = obj.toasts[index].id;
+                        obj.dismiss(id);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        mouse_move => {
+            let hovered = obj.hit_at(event.get().0);
+            if hovered != obj.hovered {
+                let now = aux.now();
+                if let Some((index, _)) = obj.hovered {
+                    // Resume the timer of the toast which is no longer hovered.
+                    if hovered.map(|(hovered, _)| hovered != index).unwrap_or(true) {
+                        if let Some(toast) = obj.toasts.get_mut(index) {
+                            toast.deadline = toast.remaining.map(|remaining| now + remaining);
+                        }
+                    }
+                }
+                if let Some((index, _)) = hovered {
+                    obj.toasts[index].pause(now);
+                }
+                obj.hovered = hovered;
+                obj.repaint();
+            }
+            event.with(|_| hovered.is_some());
+        }
+    }
+}
+
+/// Overlay which stacks transient notifications in a corner of its rectangle.
+///
+/// The toaster should generally cover the whole window; only the toasts themselves capture mouse input.
+/// Toasts are shown with `show` (or `show_with_action`) and are dismissed once their duration elapses,
+/// when their dismiss button is pressed, or with `dismiss`. Hovering over a toast pauses its timer.
+#[derive(
+    WidgetChildren,
+    LayableWidget,
+    DropNotifier,
+    HasVisibility,
+    Repaintable,
+    Movable,
+    Resizable,
+    OperatesVerbGraph,
+)]
+#[widget_children_trait(base::WidgetChildren)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+pub struct ToasterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub event_queue: RcEventQueue<ToasterEvent>,
+    pub data: base::Observed<Toaster>,
+
+    toasts: Vec<ToastEntry>,
+    next_id: ToastId,
+    hovered: Option<(usize, ToastPart)>,
+    graph: vg::OptionVerbGraph<Self, U>,
+    painter: Box<dyn draw::Painter<state::ToastState>>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_visibility]
+    visibility: base::Visibility,
+    #[repaint_target]
+    command_group: CommandGroup,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+    #[widget_drop_event]
+    drop_event: RcEventQueue<base::DropEvent>,
+
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toaster {
+    pub corner: ToastCorner,
+    /// Width of each toast.
+    pub width: f32,
+    /// Space between toasts.
+    pub spacing: f32,
+    /// Space between the toasts and the edges of the toaster.
+    pub margin: f32,
+    /// Maximum amount of toasts shown at once; further toasts wait (with paused timers) until there's room.
+    pub max_visible: usize,
+    pub typeface: draw::TypefaceStyle,
+    pub palette: ToastPalette,
+    pub contrast: draw::ThemeContrast,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for Toaster
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = ToasterWidget<U, G>;
+}
+
+impl Toaster {
+    pub fn from_theme(theme: &dyn draw::Theme) -> Self {
+        let data = theme.data();
+        Toaster {
+            corner: ToastCorner::BottomRight,
+            width: 320.0,
+            spacing: 8.0,
+            margin: 16.0,
+            max_visible: 5,
+            typeface: data.typography.body.clone(),
+            palette: ToastPalette::from_theme(theme),
+            contrast: data.contrast,
+        }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        theme: &dyn draw::Theme,
+        u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> ToasterWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let mut graph = vg::verbgraph! {
+            ToasterWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => { change => { obj.command_group.repaint(); } }
+        };
+
+        graph = graph.add("toaster", toaster_handler::<U, G>().bind(u_aux.window_queue()));
+
+        ToasterWidget {
+            event_queue: Default::default(),
+            data,
+
+            toasts: Vec::new(),
+            next_id: 0,
+            hovered: None,
+            graph: graph.into(),
+            painter: theme.toast(),
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            visibility: Default::default(),
+            command_group: Default::default(),
+            layout: Default::default(),
+            drop_event: Default::default(),
+
+            phantom_g: Default::default(),
+        }
+    }
+}
+
+impl<U, G> ToasterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn on_transform(&mut self) {
+        self.repaint();
+        base::LayableWidget::notify_layout(self);
+    }
+
+    /// Shows a toast, returning its ID.
+    ///
+    /// The toast is dismissed after `duration`, or stays until it's dismissed if `duration` is `None`.
+    pub fn show(
+        &mut self,
+        message: impl Into<String>,
+        kind: ToastKind,
+        duration: Option<Duration>,
+    ) -> ToastId {
+        self.push(message.into(), kind, duration, None)
+    }
+
+    /// Shows a toast with an action button, returning its ID.
+    pub fn show_with_action(
+        &mut self,
+        message: impl Into<String>,
+        kind: ToastKind,
+        duration: Option<Duration>,
+        action: ToastAction,
+    ) -> ToastId {
+        self.push(message.into(), kind, duration, Some(action))
+    }
+
+    /// Dismisses a toast, returning `false` if it was already dismissed.
+    pub fn dismiss(&mut self, id: ToastId) -> bool {
+        match self.toasts.iter().position(|toast| toast.id == id) {
+            Some(index) => {
+                self.toasts.remove(index);
+                // Keep the hovered toast pointing at the same entry now that the ones after it have moved up.
+                self.hovered = match self.hovered {
+                    Some((hovered, _)) if hovered == index => None,
+                    Some((hovered, part)) if hovered > index => Some((hovered - 1, part)),
+                    hovered => hovered,
+                };
+                self.repaint();
+                self.event_queue.emit_owned(ToasterEvent::Dismiss(id));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the IDs of the toasts which haven't been dismissed, in the order they were shown.
+    pub fn toasts(&self) -> Vec<ToastId> {
+        self.toasts.iter().map(|toast| toast.id).collect()
+    }
+
+    fn push(
+        &mut self,
+        message: String,
+        kind: ToastKind,
+        duration: Option<Duration>,
+        action: Option<ToastAction>,
+    ) -> ToastId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        // The timer is started on the next update, where the current time is known.
+        self.toasts.push(ToastEntry {
+            id,
+            message,
+            kind,
+            action,
+            remaining: duration,
+            deadline: None,
+        });
+        self.repaint();
+
+        id
+    }
+
+    fn text_item(&self, text: &str) -> TextDisplayItem {
+        let font = self.data.typeface.typeface.pick(self.data.typeface.style);
+        TextDisplayItem {
+            text: text.to_string().into(),
+            font: font.0,
+            font_info: font.1,
+            size: self.data.typeface.size,
+            bottom_left: Default::default(),
+            color: Color::default().into(),
+        }
+    }
+
+    fn toast_height(&self) -> f32 {
+        let font = self.data.typeface.typeface.pick(self.data.typeface.style);
+        let metrics = font.1.font.metrics();
+        let line_height = (metrics.ascent - metrics.descent) / metrics.units_per_em as f32
+            * self.data.typeface.size;
+        (line_height + 24.0).round()
+    }
+
+    /// Returns the rectangle of each visible toast, in the same order as `toasts`.
+    fn toast_rects(&self) -> Vec<AbsoluteRect> {
+        let bounds = self.abs_rect();
+        let height = self.toast_height();
+        let width = self.data.width.min(bounds.size.width - self.data.margin * 2.0).max(0.0);
+        let x = match self.data.corner {
+            ToastCorner::TopLeft | ToastCorner::BottomLeft => bounds.min_x() + self.data.margin,
+            ToastCorner::TopRight | ToastCorner::BottomRight => {
+                bounds.max_x() - self.data.margin - width
+            }
+        };
+
+        (0..self.toasts.len().min(self.data.max_visible))
+            .map(|i| {
+                let offset = self.data.margin + i as f32 * (height + self.data.spacing);
+                let y = match self.data.corner {
+                    ToastCorner::TopLeft | ToastCorner::TopRight => bounds.min_y() + offset,
+                    ToastCorner::BottomLeft | ToastCorner::BottomRight => {
+                        bounds.max_y() - offset - height
+                    }
+                };
+                AbsoluteRect::new(AbsolutePoint::new(x, y), Size::new(width, height).cast_unit())
+            })
+            .collect()
+    }
+
+    fn close_rect(&self, toast: AbsoluteRect) -> AbsoluteRect {
+        AbsoluteRect::new(
+            AbsolutePoint::new(
+                toast.max_x() - 12.0 - CLOSE_SIZE,
+                toast.min_y() + ((toast.size.height - CLOSE_SIZE) / 2.0).round(),
+            ),
+            Size::new(CLOSE_SIZE, CLOSE_SIZE).cast_unit(),
+        )
+    }
+
+    fn action_rect(&self, toast: AbsoluteRect, action: &ToastAction) -> AbsoluteRect {
+        let width = self.text_item(&action.text).bounds().unwrap().size.width + 16.0;
+        let close = self.close_rect(toast);
+        AbsoluteRect::new(
+            AbsolutePoint::new(close.min_x() - 8.0 - width, toast.min_y() + 6.0),
+            Size::new(width, toast.size.height - 12.0).cast_unit(),
+        )
+    }
+
+    fn hit_at(&self, pos: AbsolutePoint) -> Option<(usize, ToastPart)> {
+        let (index, rect) =
+            self.toast_rects().into_iter().enumerate().find(|(_, rect)| rect.contains(pos))?;
+        let toast = &self.toasts[index];
+
+        let part = if self.close_rect(rect).contains(pos) {
+            ToastPart::Close
+        } else if toast
+            .action
+            .as_ref()
+            .map(|action| self.action_rect(rect, action).contains(pos))
+            .unwrap_or(false)
+        {
+            ToastPart::Action
+        } else {
+            ToastPart::Body
+        };
+
+        Some((index, part))
+    }
+
+    fn derive_states(&self) -> Vec<state::ToastState> {
+        self.toast_rects()
+            .into_iter()
+            .zip(self.toasts.iter())
+            .enumerate()
+            .map(|(index, (rect, toast))| {
+                let (background, foreground) = self.data.palette.colors(toast.kind);
+                let hovered = match self.hovered {
+                    Some((hovered, part)) if hovered == index => Some(part),
+                    _ => None,
+                };
+
+                state::ToastState {
+                    rect,
+                    message: toast.message.clone(),
+                    typeface: self.data.typeface.clone(),
+                    background,
+                    foreground,
+                    contrast: self.data.contrast,
+                    action: toast.action.as_ref().map(|action| {
+                        (
+                            action.text.clone(),
+                            self.action_rect(rect, action),
+                            hovered == Some(ToastPart::Action),
+                        )
+                    }),
+                    close: (self.close_rect(rect), hovered == Some(ToastPart::Close)),
+                    hovered: hovered.is_some(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for ToasterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> Widget for ToasterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    #[inline]
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+
+        let now = aux.now();
+
+        // Expired toasts are dismissed first so that waiting toasts which come into view start their timers right away.
+        let expired: Vec<_> = self
+            .toasts
+            .iter()
+            .filter(|toast| toast.deadline.map(|deadline| deadline <= now).unwrap_or(false))
+            .map(|toast| toast.id)
+            .collect();
+        for id in expired {
+            self.dismiss(id);
+        }
+
+        let hovered = self.hovered.map(|(index, _)| index);
+        for (index, toast) in self.toasts.iter_mut().enumerate().take(self.data.max_visible) {
+            if toast.deadline.is_none() && Some(index) != hovered {
+                toast.deadline = toast.remaining.map(|remaining| now + remaining);
+            }
+        }
+
+        if let Some(deadline) = self.toasts.iter().filter_map(|toast| toast.deadline).min() {
+            aux.request_wakeup(deadline);
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let states = self.derive_states();
+        let painter = &mut self.painter;
+        self.command_group.push_with(
+            display,
+            || states.into_iter().flat_map(|state| painter.draw(state)).collect(),
+            ui::OVERLAY_Z_ORDER,
+            None,
+            None,
+        );
+    }
+}
+
+impl<U, G> StoresParentPosition for ToasterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}
+
+impl<U, G> draw::HasTheme for ToasterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn theme(&mut self) -> &mut dyn draw::Themed {
+        &mut self.painter
+    }
+
+    #[inline]
+    fn resize_from_theme(&mut self) {}
+}
+
+impl<U, G> ui::DefaultEventQueue<ToasterEvent> for ToasterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_event_queue(&self) -> &RcEventQueue<ToasterEvent> {
+        &self.event_queue
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<Toaster> for ToasterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<Toaster> {
+        &mut self.data
+    }
+}
+
+impl<U, G> Drop for ToasterWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn drop(&mut self) {
+        self.drop_event.emit_owned(base::DropEvent);
+    }
+}
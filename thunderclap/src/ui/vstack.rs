@@ -1,7 +1,7 @@
 use {
-    super::Align,
+    super::{Align, Justify},
     crate::{
-        base::{self, Resizable},
+        base::{self, Layout, Resizable},
         draw,
         geom::*,
         ui,
@@ -25,6 +25,14 @@ pub struct VStackItem {
     pub bottom_margin: f32,
     /// How the child should be horizontally aligned within the `VStack`.
     pub alignment: Align,
+    /// Share of the leftover vertical space given to the child, relative to the other children.
+    pub grow: f32,
+    /// How much the child gives up when the children don't fit, relative to the other children (weighted by their height).
+    pub shrink: f32,
+    /// Height the child is never shrunk below.
+    pub min_height: Option<f32>,
+    /// Height the child is never grown above.
+    pub max_height: Option<f32>,
 }
 
 impl VStackItem {
@@ -42,14 +50,47 @@ impl VStackItem {
     pub fn align(self, alignment: Align) -> VStackItem {
         VStackItem { alignment, ..self }
     }
+
+    /// Sets the `grow` value.
+    pub fn grow(self, grow: f32) -> VStackItem {
+        VStackItem { grow, ..self }
+    }
+
+    /// Sets the `shrink` value.
+    pub fn shrink(self, shrink: f32) -> VStackItem {
+        VStackItem { shrink, ..self }
+    }
+
+    /// Sets the `min_height` value.
+    pub fn min_height(self, min_height: f32) -> VStackItem {
+        VStackItem { min_height: Some(min_height), ..self }
+    }
+
+    /// Sets the `max_height` value.
+    pub fn max_height(self, max_height: f32) -> VStackItem {
+        VStackItem { max_height: Some(max_height), ..self }
+    }
+
+    fn flex_item(&self, height: f32) -> ui::FlexItem {
+        ui::FlexItem {
+            basis: height,
+            grow: self.grow,
+            shrink: self.shrink,
+            min: self.min_height.unwrap_or(0.0),
+            max: self.max_height.unwrap_or(std::f32::INFINITY),
+        }
+    }
 }
 
 #[derive(Debug)]
 struct ChildData {
     data: VStackItem,
-    evq: BidirSingleEventQueue<AbsoluteRect, AbsoluteRect>,
+    evq: BidirSingleEventQueue<base::LayoutEvent, base::LayoutEvent>,
+    node: base::LayoutNode,
     drop_listener: RcEventListener<base::DropEvent>,
     rect: AbsoluteRect,
+    /// Size last measured by the child.
+    size: Size,
     original_rect: AbsoluteRect,
     id: u64,
 }
@@ -62,10 +103,13 @@ lazy_widget! {
 }
 
 /// Abstract layout widget which arranges children in a vertical list, possibly with top/bottom margins and horizontal alignment (see `VStackData`).
-#[derive(WidgetChildren, LayableWidget, Movable, Resizable, OperatesVerbGraph)]
-#[widget_children_trait(base::WidgetChildren)]
+///
+/// Until it's given a different size (by a parent layout or `set_size`), the stack sizes itself to fit its children.
+/// Otherwise the children fill the height of the stack according to their `grow`/`shrink` factors, and any space left over is distributed by `justify`.
+#[derive(LayableWidget, Movable, Resizable, OperatesVerbGraph)]
 #[thunderclap_crate(crate)]
 #[widget_transform_callback(on_transform)]
+#[widget_measure(measure_children)]
 pub struct VStackWidget<U, G>
 where
     U: base::UpdateAuxiliary,
@@ -75,7 +119,10 @@ where
 
     rects: IndexMap<u64, ChildData>,
     next_rect_id: u64,
-    dirty: bool,
+    /// Whether the stack sizes itself to fit its children.
+    fit: bool,
+    /// Size last given to the stack by `resize_to_fit`.
+    fitted_size: Size,
     visibility: base::Visibility,
     themed: draw::PhantomThemed,
     drop_event: RcEventQueue<base::DropEvent>,
@@ -96,6 +143,8 @@ pub struct VStack {
     pub top_margin: f32,
     pub bottom_margin: f32,
     pub alignment: Align,
+    /// How vertical space left over by the children is distributed.
+    pub justify: Justify,
 }
 
 impl<U, G> ui::WidgetDataTarget<U, G> for VStack
@@ -108,7 +157,12 @@ where
 
 impl VStack {
     pub fn from_theme(_theme: &dyn draw::Theme) -> Self {
-        VStack { top_margin: 0.0, bottom_margin: 0.0, alignment: Align::Begin }
+        VStack {
+            top_margin: 0.0,
+            bottom_margin: 0.0,
+            alignment: Align::Begin,
+            justify: Justify::Start,
+        }
     }
 
     pub fn construct<U, G>(
@@ -128,7 +182,8 @@ impl VStack {
 
             rects: IndexMap::new(),
             next_rect_id: 0,
-            dirty: true,
+            fit: true,
+            fitted_size: Size::zero(),
             visibility: Default::default(),
             themed: Default::default(),
             drop_event: Default::default(),
@@ -150,21 +205,22 @@ where
     G: base::GraphicalAuxiliary,
 {
     fn resize_to_fit(&mut self) {
-        let mut max_size = Size::zero();
-        for (_, child) in &self.rects {
-            let size: Size = child.rect.size.cast_unit();
-            max_size.height += size.height + child.data.top_margin + child.data.bottom_margin;
-            if size.width > max_size.width {
-                max_size.width = size.width;
-            }
+        if !self.fit {
+            return;
         }
 
-        self.set_size(max_size);
+        let size = self.measure_children(self.layout.constraints());
+        self.fitted_size = size;
+        self.set_size(size);
     }
 
     fn on_transform(&mut self) {
-        self.dirty = true;
-        self.layout.notify(self.abs_rect());
+        if self.size() != self.fitted_size {
+            // The stack has been sized from the outside, so fill that size from now on.
+            self.fit = false;
+        }
+        self.layout.mark_dirty();
+        base::LayableWidget::notify_layout(self);
     }
 }
 
@@ -186,14 +242,18 @@ where
     type PushData = VStackItem;
 
     fn push(&mut self, data: Option<VStackItem>, child: &mut impl base::LayableWidget) {
-        self.dirty = true;
+        self.layout.mark_dirty();
 
         let id = self.next_rect_id;
         self.next_rect_id += 1;
 
         let evq = BidirSingleEventQueue::new();
 
-        child.listen_to_layout(base::WidgetLayoutEventsInner { id, evq: evq.secondary() });
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            layout_node: self.layout.node().clone(),
+        });
 
         let rect = child.abs_rect();
 
@@ -204,10 +264,13 @@ where
                     top_margin: self.data.top_margin,
                     bottom_margin: self.data.bottom_margin,
                     alignment: self.data.alignment,
+                    ..Default::default()
                 }),
                 evq,
+                node: child.layout_events().node().clone(),
                 drop_listener: child.drop_event().listen(),
                 rect,
+                size: rect.size.cast_unit(),
                 original_rect: rect,
                 id,
             },
@@ -220,9 +283,24 @@ where
         if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
             child.listen_to_layout(None);
             if restore_original {
-                child.set_ctxt_rect(data.original_rect);
+                child.arrange(data.original_rect);
+            }
+        }
+    }
+
+    fn measure_children(&self, constraints: base::Constraints) -> Size {
+        let mut size = Size::zero();
+        for (_, child) in &self.rects {
+            let item = child.data.flex_item(child.size.height);
+            size.height += item.basis.max(item.min).min(item.max)
+                + child.data.top_margin
+                + child.data.bottom_margin;
+            if child.size.width > size.width {
+                size.width = child.size.width;
             }
         }
+
+        constraints.constrain(size)
     }
 }
 
@@ -238,26 +316,35 @@ where
     fn bounds(&self) -> Rect {
         self.rect.cast_unit()
     }
+}
 
-    fn update(&mut self, _aux: &mut U) {
-        if let Some(rect) = self.layout.receive() {
-            self.set_ctxt_rect(rect);
-            self.dirty = true;
+impl<U, G> base::WidgetChildren for VStackWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    fn relayout(&mut self) {
+        if self.layout.direction_changed() {
+            self.layout.mark_dirty();
         }
 
         {
             let mut removals = Vec::new();
-            let dirty = &mut self.dirty;
             for (_, data) in &mut self.rects {
                 if !data.drop_listener.peek().is_empty() {
                     removals.push(data.id);
-                    *dirty = true;
+                    self.layout.mark_dirty();
                     continue;
                 }
 
-                if let Some(new_ev) = data.evq.retrieve_newest() {
-                    *dirty = true;
-                    data.rect = new_ev;
+                if let Some(base::LayoutEvent::Measure { rect, size }) = data.evq.retrieve_newest()
+                {
+                    // Only the child echoing the rectangle it was arranged in doesn't require another pass.
+                    if rect != data.rect || size != data.size {
+                        self.layout.mark_dirty();
+                        data.rect = rect;
+                        data.size = size;
+                    }
                 }
             }
             for removal in removals {
@@ -265,14 +352,38 @@ where
             }
         }
 
-        if self.dirty {
+        if self.layout.is_dirty() {
+            self.layout.invalidate();
             self.resize_to_fit();
             let abs_rect = self.abs_rect();
-            let mut advance = abs_rect.origin.y;
-            for (_, data) in &mut self.rects {
+            let direction = self.layout.direction();
+            // Without a size of its own, the stack passes on the constraints it was given.
+            let (available, cross) = if self.fit {
+                let constraints = self.layout.constraints();
+                (constraints.max.height, constraints.max.width)
+            } else {
+                (abs_rect.size.height, abs_rect.size.width)
+            };
+
+            let items: Vec<_> =
+                self.rects.values().map(|data| data.data.flex_item(data.size.height)).collect();
+            let margins: f32 = self
+                .rects
+                .values()
+                .map(|data| data.data.top_margin + data.data.bottom_margin)
+                .sum();
+            let heights = ui::flex_sizes(&items, abs_rect.size.height - margins);
+            let (offset, spacing) = self.data.justify.distribute(
+                abs_rect.size.height - margins - heights.iter().sum::<f32>(),
+                heights.len(),
+            );
+
+            let mut advance = abs_rect.origin.y + offset;
+            for ((_, data), height) in self.rects.iter_mut().zip(heights) {
                 advance += data.data.top_margin;
 
                 let mut rect = data.rect;
+                rect.size = Size::new(data.size.width, height).cast_unit();
                 rect.origin.y = advance;
                 rect.origin.x = match data.data.alignment {
                     Align::Begin => abs_rect.origin.x,
@@ -286,13 +397,27 @@ where
                     }
                 };
 
-                data.evq.emit_owned(rect);
+                let constraints = base::Constraints::new(
+                    if data.data.alignment == Align::Stretch {
+                        Size::new(abs_rect.size.width, 0.0)
+                    } else {
+                        Size::zero()
+                    },
+                    Size::new(cross, available - margins),
+                );
+
+                let rect = ui::mirror(rect, abs_rect, direction);
+                data.evq.emit_owned(base::LayoutEvent::Arrange { constraints, rect, direction });
+                data.node.invalidate();
                 data.rect = rect;
 
-                advance += rect.size.height + data.data.bottom_margin;
+                advance += rect.size.height + data.data.bottom_margin + spacing;
             }
 
-            self.dirty = false;
+            self.layout.clear_dirty();
+            if self.layout.measured().is_none() {
+                base::LayableWidget::notify_layout(self);
+            }
         }
     }
 }
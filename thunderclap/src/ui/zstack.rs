@@ -0,0 +1,387 @@
+use {
+    super::{align_within, Align},
+    crate::{
+        base::{self, Layout, Resizable},
+        draw,
+        geom::*,
+        ui,
+    },
+    indexmap::IndexMap,
+    reclutch::{
+        display::{DisplayCommand, Rect, Size, Vector},
+        event::{bidir_single::Queue as BidirSingleEventQueue, RcEventListener, RcEventQueue},
+        prelude::*,
+        verbgraph as vg,
+    },
+    std::marker::PhantomData,
+};
+
+/// Information about how a `ZStack` child should be layed out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZStackItem {
+    /// How the child should be horizontally aligned within the `ZStack`.
+    pub horizontal: Align,
+    /// How the child should be vertically aligned within the `ZStack`.
+    pub vertical: Align,
+    /// Offset applied to the child after it has been aligned.
+    pub offset: Vector,
+}
+
+impl Default for ZStackItem {
+    fn default() -> Self {
+        ZStackItem { horizontal: Align::Begin, vertical: Align::Begin, offset: Vector::zero() }
+    }
+}
+
+impl ZStackItem {
+    /// Creates an item aligned within the stack as specified on both axes.
+    pub fn new(horizontal: Align, vertical: Align) -> ZStackItem {
+        ZStackItem { horizontal, vertical, ..Default::default() }
+    }
+
+    /// Sets the `offset` value.
+    pub fn offset(self, offset: Vector) -> ZStackItem {
+        ZStackItem { offset, ..self }
+    }
+}
+
+#[derive(Debug)]
+struct ChildData {
+    data: ZStackItem,
+    evq: BidirSingleEventQueue<base::LayoutEvent, base::LayoutEvent>,
+    node: base::LayoutNode,
+    drop_listener: RcEventListener<base::DropEvent>,
+    rect: AbsoluteRect,
+    /// Size last measured by the child.
+    size: Size,
+    original_rect: AbsoluteRect,
+    id: u64,
+}
+
+lazy_widget! {
+    generic ZStackWidget,
+    visibility: visibility,
+    theme: themed,
+    drop_event: drop_event
+}
+
+/// Abstract layout widget which places children on top of one another, each aligned independently within the stack.
+///
+/// The stack only positions its children; it doesn't own them, so it can't decide which child is drawn on top or receives input first.
+/// Both follow the order in which the children are stored in the parent widget (siblings are drawn in order and updated in reverse order).
+/// Storing children in the same order they are pushed therefore places later children above earlier ones, both visually and for input.
+/// `child_at` finds the child pushed last among those containing a point, which is the top-most child only when the orders match.
+#[derive(LayableWidget, Movable, Resizable, OperatesVerbGraph)]
+#[thunderclap_crate(crate)]
+#[widget_transform_callback(on_transform)]
+#[widget_measure(measure_children)]
+pub struct ZStackWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    pub data: base::Observed<ZStack>,
+
+    rects: IndexMap<u64, ChildData>,
+    next_rect_id: u64,
+    visibility: base::Visibility,
+    themed: draw::PhantomThemed,
+    drop_event: RcEventQueue<base::DropEvent>,
+    parent_position: AbsolutePoint,
+
+    #[widget_rect]
+    rect: RelativeRect,
+    #[widget_layout]
+    layout: base::WidgetLayoutEvents,
+
+    graph: vg::OptionVerbGraph<Self, U>,
+    phantom_u: PhantomData<U>,
+    phantom_g: PhantomData<G>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZStack {
+    /// Horizontal alignment of children pushed without a `ZStackItem`.
+    pub horizontal_alignment: Align,
+    /// Vertical alignment of children pushed without a `ZStackItem`.
+    pub vertical_alignment: Align,
+}
+
+impl<U, G> ui::WidgetDataTarget<U, G> for ZStack
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type Target = ZStackWidget<U, G>;
+}
+
+impl ZStack {
+    pub fn from_theme(_theme: &dyn draw::Theme) -> Self {
+        ZStack { horizontal_alignment: Align::Begin, vertical_alignment: Align::Begin }
+    }
+
+    pub fn construct<U, G>(
+        self,
+        _theme: &dyn draw::Theme,
+        _u_aux: &mut U,
+        _g_aux: &mut G,
+    ) -> ZStackWidget<U, G>
+    where
+        U: base::UpdateAuxiliary,
+        G: base::GraphicalAuxiliary,
+    {
+        let data = base::Observed::new(self);
+
+        let graph = vg::verbgraph! {
+            ZStackWidget<U, G> as obj,
+            U as _aux,
+            "bind" => _ev in &data.on_change => {
+                change => {
+                    obj.layout.mark_dirty();
+                }
+            }
+        };
+
+        ZStackWidget {
+            data,
+
+            rects: IndexMap::new(),
+            next_rect_id: 0,
+            visibility: Default::default(),
+            themed: Default::default(),
+            drop_event: Default::default(),
+            parent_position: Default::default(),
+
+            rect: Default::default(),
+            layout: Default::default(),
+
+            graph: graph.into(),
+            phantom_u: Default::default(),
+            phantom_g: Default::default(),
+        }
+    }
+}
+
+impl<U, G> ZStackWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    /// Returns the layout ID (see `LayableWidget::layout_id`) of the child pushed last among those containing `point`.
+    pub fn child_at(&self, point: AbsolutePoint) -> Option<u64> {
+        self.rects.values().rev().find(|child| child.rect.contains(point)).map(|child| child.id)
+    }
+
+    fn resize_to_fit(&mut self) {
+        let size = self.measure_children(self.layout.constraints());
+        self.set_size(size);
+    }
+
+    fn on_transform(&mut self) {
+        self.layout.mark_dirty();
+        base::LayableWidget::notify_layout(self);
+    }
+}
+
+impl<U, G> vg::HasVerbGraph for ZStackWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn verb_graph(&mut self) -> &mut vg::OptionVerbGraph<Self, U> {
+        &mut self.graph
+    }
+}
+
+impl<U, G> base::Layout for ZStackWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    type PushData = ZStackItem;
+
+    /// Pushes a child on top of the stack.
+    fn push(&mut self, data: Option<ZStackItem>, child: &mut impl base::LayableWidget) {
+        self.layout.mark_dirty();
+
+        let id = self.next_rect_id;
+        self.next_rect_id += 1;
+
+        let evq = BidirSingleEventQueue::new();
+
+        child.listen_to_layout(base::WidgetLayoutEventsInner {
+            id,
+            evq: evq.secondary(),
+            layout_node: self.layout.node().clone(),
+        });
+
+        let rect = child.abs_rect();
+
+        self.rects.insert(
+            id,
+            ChildData {
+                data: data.unwrap_or(ZStackItem {
+                    horizontal: self.data.horizontal_alignment,
+                    vertical: self.data.vertical_alignment,
+                    ..Default::default()
+                }),
+                evq,
+                node: child.layout_events().node().clone(),
+                drop_listener: child.drop_event().listen(),
+                rect,
+                size: rect.size.cast_unit(),
+                original_rect: rect,
+                id,
+            },
+        );
+
+        self.resize_to_fit();
+    }
+
+    fn remove(&mut self, child: &mut impl base::LayableWidget, restore_original: bool) {
+        if let Some(data) = child.layout_id().and_then(|id| self.rects.remove(&id)) {
+            self.layout.mark_dirty();
+            child.listen_to_layout(None);
+            if restore_original {
+                child.arrange(data.original_rect);
+            }
+        }
+    }
+
+    fn measure_children(&self, constraints: base::Constraints) -> Size {
+        let mut size = Size::zero();
+        for (_, child) in &self.rects {
+            size.width = size.width.max(child.size.width);
+            size.height = size.height.max(child.size.height);
+        }
+
+        constraints.constrain(size)
+    }
+}
+
+impl<U, G> Widget for ZStackWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.rect.cast_unit()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        let mut graph = self.graph.take().unwrap();
+        graph.update_all(self, aux);
+        self.graph = Some(graph);
+    }
+}
+
+impl<U, G> base::WidgetChildren for ZStackWidget<U, G>
+where
+    U: base::UpdateAuxiliary + 'static,
+    G: base::GraphicalAuxiliary + 'static,
+{
+    fn relayout(&mut self) {
+        if self.layout.direction_changed() {
+            self.layout.mark_dirty();
+        }
+
+        {
+            let mut removals = Vec::new();
+            for (_, data) in &mut self.rects {
+                if !data.drop_listener.peek().is_empty() {
+                    removals.push(data.id);
+                    self.layout.mark_dirty();
+                    continue;
+                }
+
+                if let Some(base::LayoutEvent::Measure { rect, size }) = data.evq.retrieve_newest()
+                {
+                    if rect != data.rect || size != data.size {
+                        self.layout.mark_dirty();
+                        data.rect = rect;
+                        data.size = size;
+                    }
+                }
+            }
+            for removal in removals {
+                self.rects.remove(&removal);
+            }
+        }
+
+        if self.layout.is_dirty() {
+            self.layout.invalidate();
+            self.resize_to_fit();
+            let abs_rect = self.abs_rect();
+            let direction = self.layout.direction();
+            let max = self.layout.constraints().max;
+            for (_, data) in &mut self.rects {
+                let item = data.data;
+                let (x, width) = align_within(
+                    item.horizontal,
+                    abs_rect.origin.x,
+                    abs_rect.size.width,
+                    data.size.width,
+                );
+                let (y, height) = align_within(
+                    item.vertical,
+                    abs_rect.origin.y,
+                    abs_rect.size.height,
+                    data.size.height,
+                );
+                let rect = AbsoluteRect::new(
+                    AbsolutePoint::new(x, y) + item.offset.cast_unit(),
+                    Size::new(width, height).cast_unit(),
+                );
+
+                // Children may be larger than the stack, in which case the stack grows to fit them.
+                let constraints = base::Constraints::new(
+                    Size::new(
+                        if item.horizontal == Align::Stretch { abs_rect.size.width } else { 0.0 },
+                        if item.vertical == Align::Stretch { abs_rect.size.height } else { 0.0 },
+                    ),
+                    max,
+                );
+
+                let rect = ui::mirror(rect, abs_rect, direction);
+                data.evq.emit_owned(base::LayoutEvent::Arrange { constraints, rect, direction });
+                data.node.invalidate();
+                data.rect = rect;
+            }
+
+            self.layout.clear_dirty();
+            if self.layout.measured().is_none() {
+                base::LayableWidget::notify_layout(self);
+            }
+        }
+    }
+}
+
+impl<U, G> ui::DefaultWidgetData<ZStack> for ZStackWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    #[inline]
+    fn default_data(&mut self) -> &mut base::Observed<ZStack> {
+        &mut self.data
+    }
+}
+
+impl<U, G> StoresParentPosition for ZStackWidget<U, G>
+where
+    U: base::UpdateAuxiliary,
+    G: base::GraphicalAuxiliary,
+{
+    fn set_parent_position(&mut self, parent_pos: AbsolutePoint) {
+        self.parent_position = parent_pos;
+        self.on_transform();
+    }
+
+    fn parent_position(&self) -> AbsolutePoint {
+        self.parent_position
+    }
+}